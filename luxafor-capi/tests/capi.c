@@ -0,0 +1,63 @@
+/* Exercises the C API against a mock device.  Built and run by tests/capi.rs. */
+
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "luxafor.h"
+
+#define CHECK(expr)                                                                   \
+    do {                                                                              \
+        if (!(expr)) {                                                                \
+            const char *message = luxafor_last_error_message();                       \
+            fprintf(stderr, "%s:%d: check failed: %s (last error: %s)\n", __FILE__,   \
+                    __LINE__, #expr, message ? message : "none");                     \
+            exit(1);                                                                  \
+        }                                                                             \
+    } while (0)
+
+static void check_frame(const LuxaforDevice *device, size_t index, const uint8_t *expected,
+                        size_t len) {
+    uint8_t frame[16];
+    CHECK(luxafor_mock_frame(device, index, frame, sizeof frame) == len);
+    CHECK(memcmp(frame, expected, len) == 0);
+}
+
+int main(void) {
+    LuxaforDevice *device = NULL;
+    LuxaforColor color;
+
+    CHECK(luxafor_open_mock(&device) == LUXAFOR_STATUS_OK);
+    CHECK(device != NULL);
+
+    CHECK(luxafor_parse_color("hsl(30 100% 50%)", &color) == LUXAFOR_STATUS_OK);
+    CHECK(color.r == 255 && color.g == 128 && color.b == 0);
+
+    CHECK(luxafor_set_simple_color(device, LUXAFOR_SIMPLE_COLOR_RED) == LUXAFOR_STATUS_OK);
+    CHECK(luxafor_set_rgb_color(device, color, LUXAFOR_LIGHTS_FLAG) == LUXAFOR_STATUS_OK);
+    CHECK(luxafor_fade_to_color(device, color, LUXAFOR_LIGHTS_ALL, 40) == LUXAFOR_STATUS_OK);
+    CHECK(luxafor_strobe(device, color, LUXAFOR_LIGHT_BACK_TOP, 20, 3) == LUXAFOR_STATUS_OK);
+    CHECK(luxafor_wave(device, color, LUXAFOR_WAVE_LONG, 10, 2) == LUXAFOR_STATUS_OK);
+    CHECK(luxafor_pattern(device, LUXAFOR_PATTERN_POLICE, 5) == LUXAFOR_STATUS_OK);
+    CHECK(luxafor_mock_frame_count(device) == 6);
+
+    {
+        const uint8_t simple[] = {0, 0, 'R'};
+        const uint8_t rgb[] = {0, 1, 'A', 255, 128, 0};
+        const uint8_t pattern[] = {0, 6, 5, 5};
+        check_frame(device, 0, simple, sizeof simple);
+        check_frame(device, 1, rgb, sizeof rgb);
+        check_frame(device, 5, pattern, sizeof pattern);
+    }
+
+    CHECK(luxafor_set_simple_color(device, 'X') == LUXAFOR_STATUS_INVALID_ARGUMENT);
+    CHECK(strcmp(luxafor_last_error_message(), "88 isn't a valid simple color") == 0);
+    CHECK(luxafor_set_rgb_color(NULL, color, LUXAFOR_LIGHTS_ALL) == LUXAFOR_STATUS_NULL_POINTER);
+    CHECK(luxafor_parse_color("not a color", &color) == LUXAFOR_STATUS_INVALID_COLOR);
+    CHECK(strstr(luxafor_last_error_message(), "not a color") != NULL);
+    CHECK(luxafor_mock_frame_count(device) == 6);
+
+    luxafor_close(device);
+    return 0;
+}
@@ -0,0 +1,55 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Builds `tests/capi.c` against the shared library and runs it.
+
+#![cfg(unix)]
+
+use std::{env, path::PathBuf, process::Command};
+
+#[test]
+fn c_program_runs_against_the_mock_transport() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let include_dir = manifest_dir.join("..").join("luxafor-usb").join("include");
+    // Cargo builds the shared library into `target/<profile>/deps`, alongside this test.
+    let lib_dir = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let program = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("capi");
+
+    let status = Command::new(env::var("CC").unwrap_or_else(|_| String::from("cc")))
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(&include_dir)
+        .arg(manifest_dir.join("tests").join("capi.c"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lluxafor")
+        .arg("-o")
+        .arg(&program)
+        .status()
+        .expect("couldn't run the C compiler");
+    assert!(status.success(), "couldn't compile tests/capi.c");
+
+    // Cargo puts `target/<profile>` on the library path, which would win over the rpath and can
+    // hold a stale copy of the library.
+    let status = Command::new(&program)
+        .env("LD_LIBRARY_PATH", &lib_dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "tests/capi.c failed");
+}
@@ -0,0 +1,597 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Python bindings for `luxafor-usb`.
+//!
+//! The Python-facing types mirror the library's, with enum members spelt the Python way
+//! (`WaveType.OVERLAPPING_SHORT`) and optional arguments taken by keyword.  Wherever a color,
+//! set of lights or enum member is wanted, its name (anything the library's `FromStr` accepts)
+//! will do too.
+
+use luxafor_usb::device::{self, LightSelection};
+use luxafor_usb::transport::MockTransport;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyRuntimeError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyIterator, PyTuple};
+use std::sync::{Mutex, MutexGuard};
+
+create_exception!(
+    luxafor_usb,
+    LuxaforError,
+    PyException,
+    "Something went wrong talking to a device."
+);
+create_exception!(
+    luxafor_usb,
+    DeviceNotFoundError,
+    LuxaforError,
+    "No Luxafor device was found."
+);
+create_exception!(
+    luxafor_usb,
+    PermissionDeniedError,
+    LuxaforError,
+    "The device couldn't be opened for lack of permission."
+);
+create_exception!(
+    luxafor_usb,
+    DisconnectedError,
+    LuxaforError,
+    "The device has been unplugged."
+);
+create_exception!(
+    luxafor_usb,
+    UnsupportedError,
+    LuxaforError,
+    "The device's model can't do what was asked of it."
+);
+
+fn raise(e: luxafor_usb::LuxaforError) -> PyErr {
+    let message = e.to_string();
+    match e {
+        luxafor_usb::LuxaforError::DeviceNotFound => DeviceNotFoundError::new_err(message),
+        luxafor_usb::LuxaforError::PermissionDenied(_) => PermissionDeniedError::new_err(message),
+        luxafor_usb::LuxaforError::Disconnected => DisconnectedError::new_err(message),
+        luxafor_usb::LuxaforError::Unsupported { .. } => UnsupportedError::new_err(message),
+        _ => LuxaforError::new_err(message),
+    }
+}
+
+/// Defines a Python enum mirroring one of the library's, with the same values (the codes the
+/// device uses), conversions both ways and a `parse` static method taking the library's names for
+/// its members.
+macro_rules! python_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident = $python:literal),* $(,)? }) => {
+        $(#[$meta])*
+        #[pyclass(eq, eq_int, frozen, hash, module = "luxafor_usb")]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $(#[pyo3(name = $python)] $variant = device::$name::$variant as isize),*
+        }
+
+        impl From<$name> for device::$name {
+            fn from(value: $name) -> Self {
+                match value {
+                    $($name::$variant => device::$name::$variant),*
+                }
+            }
+        }
+
+        impl From<device::$name> for $name {
+            fn from(value: device::$name) -> Self {
+                match value {
+                    $(device::$name::$variant => $name::$variant),*
+                }
+            }
+        }
+
+        impl $name {
+            /// Accepts either a member of this enum or its name.
+            fn extract(value: &Bound<'_, PyAny>) -> PyResult<device::$name> {
+                if let Ok(member) = value.extract::<$name>() {
+                    Ok(member.into())
+                } else if let Ok(name) = value.extract::<String>() {
+                    name.parse()
+                        .map_err(|e: device::ParseNameError| PyValueError::new_err(e.to_string()))
+                } else {
+                    Err(PyTypeError::new_err(concat!(
+                        "expected a ",
+                        stringify!($name),
+                        " or a str"
+                    )))
+                }
+            }
+        }
+
+        #[pymethods]
+        impl $name {
+            #[staticmethod]
+            fn parse(name: &Bound<'_, PyAny>) -> PyResult<Self> {
+                Self::extract(name).map(Self::from)
+            }
+
+            fn __str__(&self) -> String {
+                device::$name::from(*self).to_string()
+            }
+        }
+    };
+}
+
+python_enum! {
+    /// The colors `Luxafor.set_simple_color` can set.
+    SimpleColor {
+        Red = "RED",
+        Green = "GREEN",
+        Blue = "BLUE",
+        Cyan = "CYAN",
+        Magenta = "MAGENTA",
+        Yellow = "YELLOW",
+        White = "WHITE",
+        Off = "OFF",
+    }
+}
+
+python_enum! {
+    /// The waves `Luxafor.wave` can play.
+    WaveType {
+        Short = "SHORT",
+        Long = "LONG",
+        OverlappingShort = "OVERLAPPING_SHORT",
+        OverlappingLong = "OVERLAPPING_LONG",
+    }
+}
+
+python_enum! {
+    /// The patterns built into the device, for `Luxafor.pattern`.
+    PatternType {
+        Luxafor = "LUXAFOR",
+        Police = "POLICE",
+        Random1 = "RANDOM1",
+        Random2 = "RANDOM2",
+        Random3 = "RANDOM3",
+        Random4 = "RANDOM4",
+        Random5 = "RANDOM5",
+        RainbowWave = "RAINBOW_WAVE",
+    }
+}
+
+/// An RGB color.  `RgbColor.parse` takes any CSS color, such as `"tomato"`, `"#ff8000"` or
+/// `"hsl(30 100% 50%)"`, as well as `"R,G,B"` and `"0xRRGGBB"`.  Unlike luxide, it reads names as
+/// CSS colors even where the Luxafor's differ, so `"green"` is `#008000`.
+#[pyclass(eq, frozen, hash, module = "luxafor_usb")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RgbColor {
+    #[pyo3(get)]
+    r: u8,
+    #[pyo3(get)]
+    g: u8,
+    #[pyo3(get)]
+    b: u8,
+}
+
+impl From<device::RgbColor> for RgbColor {
+    fn from(device::RgbColor(r, g, b): device::RgbColor) -> Self {
+        RgbColor { r, g, b }
+    }
+}
+
+impl From<RgbColor> for device::RgbColor {
+    fn from(color: RgbColor) -> Self {
+        device::RgbColor(color.r, color.g, color.b)
+    }
+}
+
+impl RgbColor {
+    /// Accepts an `RgbColor`, an `(r, g, b)` tuple or a color's name.
+    fn extract(value: &Bound<'_, PyAny>) -> PyResult<device::RgbColor> {
+        if let Ok(color) = value.extract::<RgbColor>() {
+            Ok(color.into())
+        } else if let Ok((r, g, b)) = value.extract::<(u8, u8, u8)>() {
+            Ok(device::RgbColor(r, g, b))
+        } else if let Ok(name) = value.extract::<String>() {
+            name.parse()
+                .map_err(|e: device::RgbColorParseError| PyValueError::new_err(e.to_string()))
+        } else {
+            Err(PyTypeError::new_err(
+                "expected an RgbColor, an (r, g, b) tuple or a str",
+            ))
+        }
+    }
+}
+
+#[pymethods]
+impl RgbColor {
+    #[new]
+    fn new(r: u8, g: u8, b: u8) -> Self {
+        RgbColor { r, g, b }
+    }
+
+    #[staticmethod]
+    fn parse(color: &Bound<'_, PyAny>) -> PyResult<Self> {
+        Self::extract(color).map(Self::from)
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+        PyTuple::new_bound(py, [self.r, self.g, self.b])
+            .as_any()
+            .iter()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("RgbColor({}, {}, {})", self.r, self.g, self.b)
+    }
+
+    fn __str__(&self) -> String {
+        device::RgbColor::from(*self).to_string()
+    }
+}
+
+/// A set of the device's six lights.  Combine them with `|`, or parse them from luxide's syntax
+/// with `Lights("flag,back-top")`.
+#[pyclass(eq, frozen, hash, module = "luxafor_usb")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Lights(u8);
+
+impl From<device::BitFlags<device::Lights>> for Lights {
+    fn from(lights: device::BitFlags<device::Lights>) -> Self {
+        Lights(lights.bits())
+    }
+}
+
+impl From<Lights> for device::BitFlags<device::Lights> {
+    fn from(lights: Lights) -> Self {
+        device::BitFlags::from_bits_truncate(lights.0)
+    }
+}
+
+impl Lights {
+    /// Accepts `Lights` or a description of them; `None` means all of them.
+    fn extract(value: Option<&Bound<'_, PyAny>>) -> PyResult<device::BitFlags<device::Lights>> {
+        match value {
+            None => Ok(device::Lights::all()),
+            Some(value) => {
+                if let Ok(lights) = value.extract::<Lights>() {
+                    Ok(lights.into())
+                } else if let Ok(selection) = value.extract::<String>() {
+                    selection
+                        .parse::<LightSelection>()
+                        .map(|selection| selection.0)
+                        .map_err(|e| PyValueError::new_err(e.to_string()))
+                } else {
+                    Err(PyTypeError::new_err("expected Lights or a str"))
+                }
+            }
+        }
+    }
+}
+
+#[pymethods]
+impl Lights {
+    #[classattr]
+    const FLAG_BOTTOM: Lights = Lights(device::Lights::FlagBottom as u8);
+    #[classattr]
+    const FLAG_MIDDLE: Lights = Lights(device::Lights::FlagMiddle as u8);
+    #[classattr]
+    const FLAG_TOP: Lights = Lights(device::Lights::FlagTop as u8);
+    #[classattr]
+    const BACK_BOTTOM: Lights = Lights(device::Lights::BackBottom as u8);
+    #[classattr]
+    const BACK_MIDDLE: Lights = Lights(device::Lights::BackMiddle as u8);
+    #[classattr]
+    const BACK_TOP: Lights = Lights(device::Lights::BackTop as u8);
+    #[classattr]
+    const NONE: Lights = Lights(0);
+    #[classattr]
+    const FLAG: Lights = Lights(0b000111);
+    #[classattr]
+    const BACK: Lights = Lights(0b111000);
+    #[classattr]
+    const ALL: Lights = Lights(0b111111);
+
+    #[new]
+    fn new(selection: &Bound<'_, PyAny>) -> PyResult<Self> {
+        Self::extract(Some(selection)).map(Self::from)
+    }
+
+    fn __or__(&self, other: Lights) -> Lights {
+        Lights(self.0 | other.0)
+    }
+
+    fn __and__(&self, other: Lights) -> Lights {
+        Lights(self.0 & other.0)
+    }
+
+    fn __sub__(&self, other: Lights) -> Lights {
+        Lights(self.0 & !other.0)
+    }
+
+    fn __invert__(&self) -> Lights {
+        Lights(!self.0 & Self::ALL.0)
+    }
+
+    fn __contains__(&self, other: Lights) -> bool {
+        other.0 & self.0 == other.0
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    fn __bool__(&self) -> bool {
+        self.0 != 0
+    }
+
+    fn __int__(&self) -> u8 {
+        self.0
+    }
+
+    /// The individual lights, from the bottom of the flag to the top of the back.
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+        let lights: Vec<PyObject> = device::BitFlags::<device::Lights>::from(*self)
+            .iter()
+            .map(|light| Lights(light as u8).into_py(py))
+            .collect();
+        PyTuple::new_bound(py, lights).as_any().iter()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Lights({:?})", self.__str__())
+    }
+
+    fn __str__(&self) -> String {
+        LightSelection(device::BitFlags::from(*self)).to_string()
+    }
+}
+
+/// One of the devices `Luxafor.enumerate` found.
+#[pyclass(frozen, get_all, module = "luxafor_usb")]
+pub struct DeviceInfo {
+    /// Pass this to `Luxafor(path=...)` to open the device.
+    path: String,
+    serial_number: Option<String>,
+    product_string: Option<String>,
+    release_number: u16,
+    model: &'static str,
+}
+
+#[pymethods]
+impl DeviceInfo {
+    fn __repr__(&self) -> String {
+        format!(
+            "DeviceInfo(path={:?}, serial_number={:?}, model={:?})",
+            self.path, self.serial_number, self.model
+        )
+    }
+}
+
+/// A Luxafor device.
+///
+/// `Luxafor()` opens the first one found; pass `serial_number` or `path` (see `enumerate`) to
+/// pick another.  `Luxafor.mock()` makes one that doesn't need any hardware.
+#[pyclass(module = "luxafor_usb")]
+pub struct Luxafor {
+    // Talking to the device happens with the GIL released, so other Python threads may be using
+    // it at the same time.
+    device: Mutex<device::Luxafor>,
+    mock: Option<MockTransport>,
+}
+
+impl Luxafor {
+    fn from_device(device: device::Luxafor, mock: Option<MockTransport>) -> Self {
+        Luxafor {
+            device: Mutex::new(device),
+            mock,
+        }
+    }
+
+    fn device(&self) -> MutexGuard<'_, device::Luxafor> {
+        self.device.lock().unwrap()
+    }
+
+    /// Runs `f` on the device with the GIL released, so that other Python threads can get on
+    /// while it waits on USB.
+    fn call<T, F>(&self, py: Python<'_>, f: F) -> PyResult<T>
+    where
+        T: Send,
+        F: FnOnce(&device::Luxafor) -> Result<T, luxafor_usb::LuxaforError> + Send,
+    {
+        py.allow_threads(|| f(&self.device())).map_err(raise)
+    }
+
+    fn mock_transport(&self) -> PyResult<&MockTransport> {
+        self.mock
+            .as_ref()
+            .ok_or_else(|| PyRuntimeError::new_err("only Luxafor.mock() devices record frames"))
+    }
+}
+
+#[pymethods]
+impl Luxafor {
+    #[new]
+    #[pyo3(signature = (*, serial_number = None, path = None))]
+    fn new(py: Python<'_>, serial_number: Option<&str>, path: Option<&str>) -> PyResult<Self> {
+        if serial_number.is_some() && path.is_some() {
+            return Err(PyValueError::new_err(
+                "give serial_number or path, not both",
+            ));
+        }
+
+        py.allow_threads(|| match (serial_number, path) {
+            (Some(serial_number), _) => device::Luxafor::open_serial(serial_number),
+            (_, Some(path)) => device::Luxafor::open_path(path),
+            (None, None) => device::Luxafor::new(),
+        })
+        .map(|device| Luxafor::from_device(device, None))
+        .map_err(raise)
+    }
+
+    /// Makes a device that records the frames it's sent instead of lighting anything.
+    /// `product_string` decides which model it pretends to be.
+    #[staticmethod]
+    #[pyo3(signature = (product_string = "LUXAFOR FLAG"))]
+    fn mock(product_string: &str) -> Self {
+        let mock = MockTransport::with_product_string(product_string);
+        Luxafor::from_device(
+            device::Luxafor::with_transport(Box::new(mock.clone())),
+            Some(mock),
+        )
+    }
+
+    /// Lists the connected devices.
+    #[staticmethod]
+    fn enumerate(py: Python<'_>) -> PyResult<Vec<DeviceInfo>> {
+        let devices = py
+            .allow_threads(device::Luxafor::enumerate)
+            .map_err(raise)?;
+        Ok(devices
+            .into_iter()
+            .map(|descriptor| DeviceInfo {
+                path: descriptor.path,
+                serial_number: descriptor.serial_number,
+                product_string: descriptor.product_string,
+                release_number: descriptor.release_number,
+                model: descriptor.model.name,
+            })
+            .collect())
+    }
+
+    #[getter]
+    fn model(&self) -> &'static str {
+        self.device().model().name
+    }
+
+    #[getter]
+    fn serial_number(&self) -> Option<String> {
+        self.device().serial_number().map(String::from)
+    }
+
+    /// Sets every light to one of the simple colors.
+    fn set_simple_color(&self, py: Python<'_>, color: &Bound<'_, PyAny>) -> PyResult<()> {
+        let color = SimpleColor::extract(color)?;
+        self.call(py, |device| device.set_simple_color(color))
+    }
+
+    #[pyo3(signature = (color, *, lights = None))]
+    fn set_rgb_color(
+        &self,
+        py: Python<'_>,
+        color: &Bound<'_, PyAny>,
+        lights: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<()> {
+        let (color, lights) = (RgbColor::extract(color)?, Lights::extract(lights)?);
+        self.call(py, |device| device.set_rgb_color(color, lights))
+    }
+
+    /// `fade_time` runs from 0 (instant) to 255 (very slow).
+    #[pyo3(signature = (color, *, lights = None, fade_time = 0))]
+    fn fade_to_color(
+        &self,
+        py: Python<'_>,
+        color: &Bound<'_, PyAny>,
+        lights: Option<&Bound<'_, PyAny>>,
+        fade_time: u8,
+    ) -> PyResult<()> {
+        let (color, lights) = (RgbColor::extract(color)?, Lights::extract(lights)?);
+        self.call(py, |device| device.fade_to_color(color, lights, fade_time))
+    }
+
+    /// `speed` runs from 0 (very fast) to 255 (very slow).
+    #[pyo3(signature = (color, *, lights = None, speed = 31, repeat = 3))]
+    fn strobe(
+        &self,
+        py: Python<'_>,
+        color: &Bound<'_, PyAny>,
+        lights: Option<&Bound<'_, PyAny>>,
+        speed: u8,
+        repeat: u8,
+    ) -> PyResult<()> {
+        let (color, lights) = (RgbColor::extract(color)?, Lights::extract(lights)?);
+        self.call(py, |device| device.strobe(color, lights, speed, repeat))
+    }
+
+    #[pyo3(signature = (color, wave_type, *, speed = 31, repeat = 3))]
+    fn wave(
+        &self,
+        py: Python<'_>,
+        color: &Bound<'_, PyAny>,
+        wave_type: &Bound<'_, PyAny>,
+        speed: u8,
+        repeat: u8,
+    ) -> PyResult<()> {
+        let (color, wave_type) = (RgbColor::extract(color)?, WaveType::extract(wave_type)?);
+        self.call(py, |device| device.wave(color, wave_type, speed, repeat))
+    }
+
+    #[pyo3(signature = (pattern_type, *, repeat = 3))]
+    fn pattern(&self, py: Python<'_>, pattern_type: &Bound<'_, PyAny>, repeat: u8) -> PyResult<()> {
+        let pattern_type = PatternType::extract(pattern_type)?;
+        self.call(py, |device| device.pattern(pattern_type, repeat))
+    }
+
+    /// Every frame sent to a `Luxafor.mock()` device so far, oldest first.
+    fn frames<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyBytes>>> {
+        let frames = self.mock_transport()?.frames();
+        Ok(frames
+            .iter()
+            .map(|frame| PyBytes::new_bound(py, frame))
+            .collect())
+    }
+
+    /// Like `frames`, but forgets them, so the next call only returns newer ones.
+    fn take_frames<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyBytes>>> {
+        let frames = self.mock_transport()?.take_frames();
+        Ok(frames
+            .iter()
+            .map(|frame| PyBytes::new_bound(py, frame))
+            .collect())
+    }
+
+    fn __repr__(&self) -> String {
+        match self.serial_number() {
+            Some(serial_number) => format!("<Luxafor {} {}>", self.model(), serial_number),
+            None => format!("<Luxafor {}>", self.model()),
+        }
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "luxafor_usb")]
+fn python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add_class::<Luxafor>()?;
+    m.add_class::<DeviceInfo>()?;
+    m.add_class::<RgbColor>()?;
+    m.add_class::<Lights>()?;
+    m.add_class::<SimpleColor>()?;
+    m.add_class::<WaveType>()?;
+    m.add_class::<PatternType>()?;
+    m.add("LuxaforError", py.get_type_bound::<LuxaforError>())?;
+    m.add(
+        "DeviceNotFoundError",
+        py.get_type_bound::<DeviceNotFoundError>(),
+    )?;
+    m.add(
+        "PermissionDeniedError",
+        py.get_type_bound::<PermissionDeniedError>(),
+    )?;
+    m.add(
+        "DisconnectedError",
+        py.get_type_bound::<DisconnectedError>(),
+    )?;
+    m.add("UnsupportedError", py.get_type_bound::<UnsupportedError>())?;
+    Ok(())
+}
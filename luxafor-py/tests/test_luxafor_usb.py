@@ -0,0 +1,105 @@
+import pytest
+
+from luxafor_usb import (
+    Lights,
+    Luxafor,
+    LuxaforError,
+    PatternType,
+    RgbColor,
+    SimpleColor,
+    UnsupportedError,
+    WaveType,
+)
+
+
+@pytest.fixture
+def luxafor():
+    return Luxafor.mock()
+
+
+def test_operations_reach_the_device(luxafor):
+    luxafor.set_simple_color(SimpleColor.RED)
+    luxafor.set_rgb_color(RgbColor(255, 128, 0), lights=Lights.FLAG)
+    luxafor.fade_to_color("blue", fade_time=40)
+    luxafor.strobe((0, 255, 0), lights="back-top", speed=20, repeat=5)
+    luxafor.wave("white", WaveType.OVERLAPPING_LONG, speed=10, repeat=2)
+    luxafor.pattern("police", repeat=4)
+
+    assert luxafor.take_frames() == [
+        bytes([0, 0, ord("R")]),
+        bytes([0, 1, ord("A"), 255, 128, 0]),
+        bytes([0, 2, 0xFF, 0, 0, 255, 40]),
+        bytes([0, 3, 6, 0, 255, 0, 20, 0, 5]),
+        bytes([0, 4, 4, 255, 255, 255, 0, 2, 10]),
+        bytes([0, 6, 5, 4]),
+    ]
+    assert luxafor.frames() == []
+
+
+def test_colors():
+    color = RgbColor.parse("tomato")
+    assert color == RgbColor(255, 99, 71)
+    assert (color.r, color.g, color.b) == (255, 99, 71)
+    assert tuple(color) == (255, 99, 71)
+    assert str(color) == "#ff6347"
+    assert repr(color) == "RgbColor(255, 99, 71)"
+    assert RgbColor.parse((1, 2, 3)) == RgbColor(1, 2, 3)
+    assert len({RgbColor(1, 2, 3), RgbColor.parse("#010203")}) == 1
+
+    with pytest.raises(ValueError, match="isn't a hex digit"):
+        RgbColor.parse("#ff80fg")
+    with pytest.raises(TypeError):
+        RgbColor.parse(12)
+
+
+def test_lights():
+    lights = Lights.FLAG_TOP | Lights.BACK
+    assert Lights.BACK_MIDDLE in lights
+    assert Lights.FLAG_BOTTOM not in lights
+    assert len(lights) == 4
+    assert list(lights) == [
+        Lights.FLAG_TOP,
+        Lights.BACK_BOTTOM,
+        Lights.BACK_MIDDLE,
+        Lights.BACK_TOP,
+    ]
+    assert lights - Lights.BACK == Lights.FLAG_TOP
+    assert ~Lights.FLAG == Lights.BACK
+    assert Lights("flag-top,back") == lights
+    assert Lights(str(lights)) == lights
+    assert not Lights.NONE
+
+    with pytest.raises(ValueError):
+        Lights("sideways")
+
+
+def test_enums():
+    assert WaveType.parse("overlapping-short") == WaveType.OVERLAPPING_SHORT
+    assert str(PatternType.RAINBOW_WAVE) == "rainbow-wave"
+    assert SimpleColor.parse(SimpleColor.OFF) == SimpleColor.OFF
+
+    with pytest.raises(ValueError):
+        PatternType.parse("disco")
+
+
+def test_errors_are_mapped():
+    mute = Luxafor.mock("LUXAFOR MUTE")
+    assert mute.model == "Luxafor Mute Button"
+
+    with pytest.raises(UnsupportedError) as error:
+        mute.pattern("police")
+    assert isinstance(error.value, LuxaforError)
+    assert mute.frames() == []
+
+
+def test_bad_arguments_send_nothing(luxafor):
+    with pytest.raises(ValueError):
+        luxafor.set_rgb_color("not a color")
+    with pytest.raises(ValueError):
+        luxafor.set_simple_color("ultraviolet")
+    with pytest.raises(OverflowError):
+        luxafor.strobe("red", speed=256)
+    with pytest.raises(TypeError):
+        luxafor.pattern(PatternType.POLICE, 3)
+
+    assert luxafor.frames() == []
@@ -0,0 +1,356 @@
+/* The C API of luxafor-usb, built into libluxafor by luxafor-capi. */
+
+#ifndef LUXAFOR_H
+#define LUXAFOR_H
+
+/* Generated by cbindgen from src/capi.rs; don't edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define LUXAFOR_LIGHT_FLAG_BOTTOM 1
+
+#define LUXAFOR_LIGHT_FLAG_MIDDLE 2
+
+#define LUXAFOR_LIGHT_FLAG_TOP 4
+
+#define LUXAFOR_LIGHT_BACK_BOTTOM 8
+
+#define LUXAFOR_LIGHT_BACK_MIDDLE 16
+
+#define LUXAFOR_LIGHT_BACK_TOP 32
+
+#define LUXAFOR_LIGHTS_FLAG 7
+
+#define LUXAFOR_LIGHTS_BACK 56
+
+#define LUXAFOR_LIGHTS_ALL 63
+
+#define LUXAFOR_SIMPLE_COLOR_RED 82
+
+#define LUXAFOR_SIMPLE_COLOR_GREEN 71
+
+#define LUXAFOR_SIMPLE_COLOR_BLUE 66
+
+#define LUXAFOR_SIMPLE_COLOR_CYAN 67
+
+#define LUXAFOR_SIMPLE_COLOR_MAGENTA 77
+
+#define LUXAFOR_SIMPLE_COLOR_YELLOW 89
+
+#define LUXAFOR_SIMPLE_COLOR_WHITE 87
+
+#define LUXAFOR_SIMPLE_COLOR_OFF 79
+
+#define LUXAFOR_WAVE_SHORT 1
+
+#define LUXAFOR_WAVE_LONG 2
+
+#define LUXAFOR_WAVE_OVERLAPPING_SHORT 3
+
+#define LUXAFOR_WAVE_OVERLAPPING_LONG 4
+
+#define LUXAFOR_PATTERN_LUXAFOR 1
+
+#define LUXAFOR_PATTERN_RANDOM1 2
+
+#define LUXAFOR_PATTERN_RANDOM2 3
+
+#define LUXAFOR_PATTERN_RANDOM3 4
+
+#define LUXAFOR_PATTERN_POLICE 5
+
+#define LUXAFOR_PATTERN_RANDOM4 6
+
+#define LUXAFOR_PATTERN_RANDOM5 7
+
+#define LUXAFOR_PATTERN_RAINBOW_WAVE 8
+
+/**
+ * What a C API call did.  Everything but `Ok` is a failure, with a message waiting in
+ * `luxafor_last_error_message`.
+ */
+typedef enum LuxaforStatus {
+  LUXAFOR_STATUS_OK = 0,
+  /**
+   * A pointer argument was `NULL`.
+   */
+  LUXAFOR_STATUS_NULL_POINTER,
+  /**
+   * A string argument wasn't valid UTF-8.
+   */
+  LUXAFOR_STATUS_INVALID_STRING,
+  /**
+   * A lights mask, simple color, wave type or pattern wasn't one the device knows.
+   */
+  LUXAFOR_STATUS_INVALID_ARGUMENT,
+  /**
+   * `luxafor_parse_color` couldn't make sense of its input.
+   */
+  LUXAFOR_STATUS_INVALID_COLOR,
+  LUXAFOR_STATUS_DEVICE_NOT_FOUND,
+  LUXAFOR_STATUS_PERMISSION_DENIED,
+  LUXAFOR_STATUS_UNEXPECTED_PRODUCT_STRING,
+  LUXAFOR_STATUS_OPEN,
+  LUXAFOR_STATUS_WRITE,
+  LUXAFOR_STATUS_SHORT_WRITE,
+  LUXAFOR_STATUS_READ,
+  LUXAFOR_STATUS_DISCONNECTED,
+  LUXAFOR_STATUS_UNSUPPORTED,
+  /**
+   * Something went wrong that the C API doesn't distinguish, including a bug in this library.
+   */
+  LUXAFOR_STATUS_OTHER,
+} LuxaforStatus;
+
+/**
+ * An open device.
+ */
+typedef struct LuxaforDevice LuxaforDevice;
+
+/**
+ * The devices found by `luxafor_enumerate`.
+ */
+typedef struct LuxaforDeviceList LuxaforDeviceList;
+
+/**
+ * An RGB color, as used by `luxafor_parse_color` and the operations that take one.
+ */
+typedef struct LuxaforColor {
+  uint8_t r;
+  uint8_t g;
+  uint8_t b;
+} LuxaforColor;
+
+
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Describes the most recent failure on the calling thread, or returns `NULL` if nothing has
+ * failed yet.  The string belongs to the library, and stays valid until the next failure on
+ * the same thread.
+ */
+const char *luxafor_last_error_message(void);
+
+/**
+ * Lists the connected devices.  On success, `*out` points to a list that must be released with
+ * `luxafor_device_list_free`.
+ *
+ * # Safety
+ *
+ * `out` must be `NULL` or valid for writes.
+ */
+enum LuxaforStatus luxafor_enumerate(struct LuxaforDeviceList **out);
+
+/**
+ * The number of devices in `list`.
+ *
+ * # Safety
+ *
+ * `list` must be `NULL` or a list returned by `luxafor_enumerate`.
+ */
+size_t luxafor_device_list_len(const struct LuxaforDeviceList *list);
+
+/**
+ * The path of the `index`th device in `list`, to pass to `luxafor_open_path`, or `NULL` if
+ * `index` is out of range.  The string belongs to the list.
+ *
+ * # Safety
+ *
+ * `list` must be `NULL` or a list returned by `luxafor_enumerate`.
+ */
+const char *luxafor_device_list_path(const struct LuxaforDeviceList *list, size_t index);
+
+/**
+ * The serial number of the `index`th device in `list`, or `NULL` if it doesn't report one or
+ * `index` is out of range.  The string belongs to the list.
+ *
+ * # Safety
+ *
+ * `list` must be `NULL` or a list returned by `luxafor_enumerate`.
+ */
+const char *luxafor_device_list_serial_number(const struct LuxaforDeviceList *list, size_t index);
+
+/**
+ * The model name of the `index`th device in `list` (e.g. "Luxafor Flag"), or `NULL` if `index`
+ * is out of range.  The string belongs to the list.
+ *
+ * # Safety
+ *
+ * `list` must be `NULL` or a list returned by `luxafor_enumerate`.
+ */
+const char *luxafor_device_list_model(const struct LuxaforDeviceList *list, size_t index);
+
+/**
+ * Releases a list returned by `luxafor_enumerate`.  Does nothing if `list` is `NULL`.
+ *
+ * # Safety
+ *
+ * `list` must be `NULL` or a list returned by `luxafor_enumerate` that hasn't been freed yet.
+ */
+void luxafor_device_list_free(struct LuxaforDeviceList *list);
+
+/**
+ * Opens the first device found.  On success, `*out` points to the device, which must be
+ * released with `luxafor_close`.
+ *
+ * # Safety
+ *
+ * `out` must be `NULL` or valid for writes.
+ */
+enum LuxaforStatus luxafor_open(struct LuxaforDevice **out);
+
+/**
+ * Opens the device at `path`, as returned by `luxafor_device_list_path`.
+ *
+ * # Safety
+ *
+ * `path` must be `NULL` or a NUL-terminated string, and `out` `NULL` or valid for writes.
+ */
+enum LuxaforStatus luxafor_open_path(const char *path, struct LuxaforDevice **out);
+
+/**
+ * Opens the device with the given serial number.
+ *
+ * # Safety
+ *
+ * `serial_number` must be `NULL` or a NUL-terminated string, and `out` `NULL` or valid for
+ * writes.
+ */
+enum LuxaforStatus luxafor_open_serial(const char *serial_number, struct LuxaforDevice **out);
+
+/**
+ * Opens a pretend Luxafor Flag that records the frames written to it instead of lighting
+ * anything, for testing code that uses this library.  See `luxafor_mock_frame`.
+ *
+ * # Safety
+ *
+ * `out` must be `NULL` or valid for writes.
+ */
+enum LuxaforStatus luxafor_open_mock(struct LuxaforDevice **out);
+
+/**
+ * Closes a device.  Does nothing if `device` is `NULL`.
+ *
+ * # Safety
+ *
+ * `device` must be `NULL` or a device returned by one of the `luxafor_open` functions that
+ * hasn't been closed yet.
+ */
+void luxafor_close(struct LuxaforDevice *device);
+
+/**
+ * The number of frames written so far to a device opened with `luxafor_open_mock`; always 0
+ * for a real device.
+ *
+ * # Safety
+ *
+ * `device` must be `NULL` or an open device.
+ */
+size_t luxafor_mock_frame_count(const struct LuxaforDevice *device);
+
+/**
+ * Copies as much of the `index`th frame written to a device opened with `luxafor_open_mock` as
+ * fits into `buf`, and returns the frame's full length, or 0 if there's no such frame.
+ *
+ * # Safety
+ *
+ * `device` must be `NULL` or an open device, and `buf` valid for writes of `len` bytes.
+ */
+size_t luxafor_mock_frame(const struct LuxaforDevice *device,
+                          size_t index,
+                          uint8_t *buf,
+                          size_t len);
+
+/**
+ * Sets every light to one of the `LUXAFOR_SIMPLE_COLOR_*` colors.
+ *
+ * # Safety
+ *
+ * `device` must be `NULL` or an open device.
+ */
+enum LuxaforStatus luxafor_set_simple_color(const struct LuxaforDevice *device, uint8_t color);
+
+/**
+ * Sets `lights`, a mask of `LUXAFOR_LIGHT_*` values, to `color`.
+ *
+ * # Safety
+ *
+ * `device` must be `NULL` or an open device.
+ */
+enum LuxaforStatus luxafor_set_rgb_color(const struct LuxaforDevice *device,
+                                         struct LuxaforColor color,
+                                         uint8_t lights);
+
+/**
+ * Fades `lights` to `color`.  `fade_time` runs from 0 (instant) to 255 (very slow).
+ *
+ * # Safety
+ *
+ * `device` must be `NULL` or an open device.
+ */
+enum LuxaforStatus luxafor_fade_to_color(const struct LuxaforDevice *device,
+                                         struct LuxaforColor color,
+                                         uint8_t lights,
+                                         uint8_t fade_time);
+
+/**
+ * Flashes `lights` in `color` `repeat` times.  `speed` runs from 0 (very fast) to 255 (very
+ * slow).
+ *
+ * # Safety
+ *
+ * `device` must be `NULL` or an open device.
+ */
+enum LuxaforStatus luxafor_strobe(const struct LuxaforDevice *device,
+                                  struct LuxaforColor color,
+                                  uint8_t lights,
+                                  uint8_t speed,
+                                  uint8_t repeat);
+
+/**
+ * Plays one of the `LUXAFOR_WAVE_*` waves in `color` `repeat` times.
+ *
+ * # Safety
+ *
+ * `device` must be `NULL` or an open device.
+ */
+enum LuxaforStatus luxafor_wave(const struct LuxaforDevice *device,
+                                struct LuxaforColor color,
+                                uint8_t wave_type,
+                                uint8_t speed,
+                                uint8_t repeat);
+
+/**
+ * Plays one of the `LUXAFOR_PATTERN_*` patterns `repeat` times.
+ *
+ * # Safety
+ *
+ * `device` must be `NULL` or an open device.
+ */
+enum LuxaforStatus luxafor_pattern(const struct LuxaforDevice *device,
+                                   uint8_t pattern_type,
+                                   uint8_t repeat);
+
+/**
+ * Parses a CSS color (such as "tomato", "#ff8000" or "hsl(30 100% 50%)"), "R,G,B" in decimal or
+ * "0xRRGGBB".  Names always mean their CSS colors, so "green" is #008000; luxide instead sends the
+ * eight Luxafor color names as simple colors, where green is #00ff00.
+ *
+ * # Safety
+ *
+ * `s` must be `NULL` or a NUL-terminated string, and `out` `NULL` or valid for writes.
+ */
+enum LuxaforStatus luxafor_parse_color(const char *s, struct LuxaforColor *out);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* LUXAFOR_H */
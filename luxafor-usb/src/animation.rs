@@ -0,0 +1,357 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Animations worked out on the host and shown a frame at a time.
+//!
+//! The hardware's own effects can't address arbitrary lights or use more than one color, so an
+//! `Animation` gives each LED its own `Track` of keyframes, eased from one to the next, and
+//! `Animation::play` renders it with `Luxafor::show` at a steady frame rate until it ends or is
+//! cancelled.  `Animation::spawn` does the same from a background thread.
+
+use crate::device::{Lights, Luxafor, RgbColor};
+use crate::frame::Frame;
+use crate::LuxaforError;
+use enumflags2::BitFlags;
+use log::{debug, trace};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+mod easing;
+#[cfg(test)]
+mod tests;
+
+pub use easing::Easing;
+
+/// A frame rate that looks smooth without keeping the USB bus busy.
+pub const DEFAULT_FPS: u32 = 30;
+
+/// The fastest frame rate animations are played at; faster ones are slowed to this.  The device
+/// can't take frames anywhere near this quickly anyway.
+pub const MAX_FPS: u32 = 1000;
+
+/// The color a track reaches at a point in time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Keyframe {
+    /// How long after the start of the animation the color is reached.
+    pub at: Duration,
+    pub color: RgbColor,
+    /// How the color changes on the way here from the previous keyframe.
+    pub easing: Easing,
+}
+
+/// A sequence of keyframes for one or more LEDs.  Before the first keyframe the LEDs show its
+/// color, and after the last they hold on to its color.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Track {
+    keyframes: Vec<Keyframe>,
+}
+
+impl Track {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a keyframe, keeping them in time order.  A keyframe at the same time as an existing
+    /// one goes after it, which makes for an instant jump between the two.
+    pub fn keyframe(mut self, at: Duration, color: RgbColor, easing: Easing) -> Self {
+        let index = self.keyframes.partition_point(|k| k.at <= at);
+        self.keyframes.insert(index, Keyframe { at, color, easing });
+        self
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// The time of the last keyframe.
+    pub fn duration(&self) -> Duration {
+        self.keyframes.last().map_or(Duration::ZERO, |k| k.at)
+    }
+
+    /// The color at `time`, or `None` if the track has no keyframes.
+    pub fn color_at(&self, time: Duration) -> Option<RgbColor> {
+        let next = self.keyframes.partition_point(|k| k.at <= time);
+        match (
+            next.checked_sub(1).map(|i| &self.keyframes[i]),
+            self.keyframes.get(next),
+        ) {
+            (None, None) => None,
+            (None, Some(first)) => Some(first.color),
+            (Some(last), None) => Some(last.color),
+            (Some(from), Some(to)) => {
+                let t = (time - from.at).as_secs_f32() / (to.at - from.at).as_secs_f32();
+                Some(from.color.lerp(to.color, to.easing.apply(t)))
+            }
+        }
+    }
+}
+
+/// A track for each LED that has one.  LEDs without a track keep the color they had when the
+/// animation started.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Animation {
+    tracks: [Option<Track>; 6],
+    looping: bool,
+}
+
+impl Animation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Plays `track` on `lights`, replacing any track they already had.
+    pub fn track(mut self, lights: BitFlags<Lights>, track: Track) -> Self {
+        lights
+            .iter()
+            .for_each(|light| self.tracks[light.index()] = Some(track.clone()));
+        self
+    }
+
+    /// Starts again from the beginning each time the animation ends, until it's cancelled.
+    /// Animations with no length to them don't loop.
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.looping && self.duration() > Duration::ZERO
+    }
+
+    /// How long the animation takes to play through once: the length of its longest track.
+    pub fn duration(&self) -> Duration {
+        self.tracks
+            .iter()
+            .flatten()
+            .map(Track::duration)
+            .max()
+            .unwrap_or(Duration::ZERO)
+    }
+
+    /// The frame to show `time` after the start of a pass through the animation, with LEDs that
+    /// have no track taken from `base`.
+    pub fn frame_at(&self, time: Duration, base: &Frame) -> Frame {
+        let mut frame = *base;
+        for (color, track) in frame.0.iter_mut().zip(&self.tracks) {
+            if let Some(track_color) = track.as_ref().and_then(|t| t.color_at(time)) {
+                *color = track_color;
+            }
+        }
+        frame
+    }
+
+    /// Shows the animation on `luxafor` at `fps` frames a second (at most `MAX_FPS`), returning
+    /// when it ends or `cancel` is cancelled.
+    ///
+    /// Frames are timed from the start of the animation rather than from each other, so the
+    /// animation keeps to time however long each write takes; if the device falls behind, frames
+    /// are skipped rather than played late.
+    pub fn play(
+        &self,
+        luxafor: &Luxafor,
+        fps: u32,
+        cancel: &CancelToken,
+    ) -> Result<(), LuxaforError> {
+        let duration = self.duration();
+        let looping = self.is_looping();
+        let base = Frame(
+            luxafor
+                .current_state()
+                .map(|state| state.color().unwrap_or_else(RgbColor::off)),
+        );
+        debug!(
+            "playing {:?} animation at {} fps{}",
+            duration,
+            fps,
+            if looping { ", looping" } else { "" }
+        );
+
+        run(luxafor, fps, cancel, |elapsed| {
+            if looping {
+                let time = Duration::from_nanos((elapsed.as_nanos() % duration.as_nanos()) as u64);
+                (self.frame_at(time, &base), false)
+            } else {
+                (
+                    self.frame_at(elapsed.min(duration), &base),
+                    elapsed >= duration,
+                )
+            }
+        })
+    }
+
+    /// Plays the animation from a background thread, which takes `luxafor` until it's finished
+    /// or stopped.
+    pub fn spawn(self, luxafor: Luxafor, fps: u32) -> Result<Playing, LuxaforError> {
+        Playing::start(luxafor, move |luxafor, cancel| {
+            self.play(luxafor, fps, cancel)
+        })
+    }
+}
+
+/// Shows the frames `render` works out from the time since starting, at `fps` frames a second,
+/// until it says it's shown the last one or `cancel` is cancelled.  `fps` is kept to between 1 and
+/// `MAX_FPS`.  This is the scheduler behind both `Animation::play` and `Effect::play`.
+pub(crate) fn run<F>(
+    luxafor: &Luxafor,
+    fps: u32,
+    cancel: &CancelToken,
+    mut render: F,
+) -> Result<(), LuxaforError>
+where
+    F: FnMut(Duration) -> (Frame, bool),
+{
+    let period = (Duration::from_secs(1) / fps.clamp(1, MAX_FPS)).as_nanos() as u64;
+    let start = Instant::now();
+    let mut frame_number: u64 = 0;
+    loop {
+        let elapsed = start.elapsed();
+        let (frame, last) = render(elapsed);
+        luxafor.show(&frame)?;
+        if last {
+            debug!("finished after {:?}", elapsed);
+            return Ok(());
+        }
+
+        let due = (elapsed.as_nanos() / u128::from(period)) as u64 + 1;
+        if due > frame_number + 1 {
+            trace!("skipping {} late frame(s)", due - frame_number - 1);
+        }
+        frame_number = due.max(frame_number + 1);
+        if cancel.wait_until(start + Duration::from_nanos(period.saturating_mul(frame_number))) {
+            debug!("cancelled after {:?}", start.elapsed());
+            return Ok(());
+        }
+    }
+}
+
+/// Stops an animation that's playing.  Clones share the same cancellation, so one can be handed
+/// to a signal handler or another thread while `Animation::play` blocks on this one.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    inner: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops the animation before its next frame.
+    pub fn cancel(&self) {
+        let (cancelled, wake) = &*self.inner;
+        *cancelled.lock().unwrap() = true;
+        wake.notify_all();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.inner.0.lock().unwrap()
+    }
+
+    /// Sleeps until `deadline`, waking early if cancelled.  Returns whether it was cancelled.
+    fn wait_until(&self, deadline: Instant) -> bool {
+        let (cancelled, wake) = &*self.inner;
+        let mut cancelled = cancelled.lock().unwrap();
+        while !*cancelled {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            cancelled = wake.wait_timeout(cancelled, deadline - now).unwrap().0;
+        }
+        *cancelled
+    }
+}
+
+/// An animation or effect playing on a background thread.  Dropping it stops the animation; use
+/// `stop` or `wait` to get the device back, which they hand back even if the animation failed.
+pub struct Playing {
+    cancel: CancelToken,
+    thread: Option<JoinHandle<(Luxafor, Result<(), LuxaforError>)>>,
+}
+
+impl Playing {
+    /// Runs `play` on a background thread, handing it `luxafor` and a token that `stop` cancels.
+    pub(crate) fn start<F>(luxafor: Luxafor, play: F) -> Result<Self, LuxaforError>
+    where
+        F: FnOnce(&Luxafor, &CancelToken) -> Result<(), LuxaforError> + Send + 'static,
+    {
+        let cancel = CancelToken::new();
+        let thread_cancel = cancel.clone();
+        let thread = thread::Builder::new()
+            .name(String::from("luxafor-animation"))
+            .spawn(move || {
+                let result = play(&luxafor, &thread_cancel);
+                (luxafor, result)
+            })
+            .map_err(|e| LuxaforError::Thread(format!("animation: {}", e)))?;
+
+        Ok(Self {
+            cancel,
+            thread: Some(thread),
+        })
+    }
+
+    /// A token that stops this animation when cancelled.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel.clone()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+
+    /// Stops the animation, leaving the LEDs as they are, and hands back the device along with how
+    /// the animation went.
+    pub fn stop(self) -> (Luxafor, Result<(), LuxaforError>) {
+        self.cancel.cancel();
+        self.wait()
+    }
+
+    /// Waits for the animation to end, which a looping animation never does unless it's
+    /// cancelled, and hands back the device along with how the animation went: it ends early,
+    /// with an error, if showing a frame fails.
+    pub fn wait(mut self) -> (Luxafor, Result<(), LuxaforError>) {
+        self.thread
+            .take()
+            .expect("animation thread already joined")
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+}
+
+impl Drop for Playing {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Shorthand for `Duration::from_millis`, for the many timings the tests spell out.
+#[cfg(test)]
+pub(crate) fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
@@ -0,0 +1,104 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Easing curves, which shape how a track moves from one keyframe to the next.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Maps how far through a transition we are in time (0 to 1) to how far through it the color
+/// should be.  The named curves match their CSS namesakes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Easing {
+    /// A constant rate of change.
+    #[default]
+    Linear,
+    /// Starts slowly and speeds up.
+    EaseIn,
+    /// Starts quickly and slows down.
+    EaseOut,
+    /// Starts and ends slowly.
+    EaseInOut,
+    /// A CSS-style cubic Bézier curve from (0, 0) to (1, 1) through the control points
+    /// (x1, y1) and (x2, y2).  The x coordinates are clamped to between 0 and 1.
+    CubicBezier(f32, f32, f32, f32),
+    /// Jumps in this many equal steps, each at the end of its interval, so the color only reaches
+    /// its target at the very end.  Zero steps holds the starting color throughout.
+    Steps(u32),
+}
+
+impl Easing {
+    /// Returns how far through the transition the color should be at time `t`, where both are
+    /// fractions from 0 to 1.  `t` is clamped to that range; the result can leave it for Bézier
+    /// curves that overshoot.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => cubic_bezier(0.42, 0.0, 1.0, 1.0, t),
+            Easing::EaseOut => cubic_bezier(0.0, 0.0, 0.58, 1.0, t),
+            Easing::EaseInOut => cubic_bezier(0.42, 0.0, 0.58, 1.0, t),
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                cubic_bezier(x1.clamp(0.0, 1.0), y1, x2.clamp(0.0, 1.0), y2, t)
+            }
+            Easing::Steps(0) => 0.0,
+            Easing::Steps(steps) => (t * steps as f32).floor() / steps as f32,
+        }
+    }
+}
+
+/// Evaluates the curve at the point whose x coordinate is `x`.  The curve's x is monotonic when
+/// the control points' x coordinates are within 0 to 1, so Newton's method finds the parameter
+/// quickly, with bisection as a fallback where the slope is too flat for it.
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    // The coordinates of a Bézier from (0, 0) to (1, 1) at parameter `s`, and the slope of x.
+    let coord = |a: f32, b: f32, s: f32| {
+        3.0 * a * s * (1.0 - s) * (1.0 - s) + 3.0 * b * s * s * (1.0 - s) + s * s * s
+    };
+    let slope = |a: f32, b: f32, s: f32| {
+        3.0 * a * (1.0 - s) * (1.0 - s) + 6.0 * (b - a) * s * (1.0 - s) + 3.0 * (1.0 - b) * s * s
+    };
+
+    let mut s = x;
+    for _ in 0..8 {
+        let error = coord(x1, x2, s) - x;
+        if error.abs() < 1e-5 {
+            return coord(y1, y2, s);
+        }
+        let d = slope(x1, x2, s);
+        if d.abs() < 1e-6 {
+            break;
+        }
+        s = (s - error / d).clamp(0.0, 1.0);
+    }
+
+    let (mut low, mut high) = (0.0, 1.0);
+    s = x;
+    for _ in 0..32 {
+        if coord(x1, x2, s) < x {
+            low = s;
+        } else {
+            high = s;
+        }
+        s = (low + high) / 2.0;
+    }
+    coord(y1, y2, s)
+}
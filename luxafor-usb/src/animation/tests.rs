@@ -0,0 +1,201 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{ms, Animation, CancelToken, Easing, Track};
+use crate::device::{LedState, Lights, Luxafor, RgbColor};
+use crate::frame::Frame;
+use crate::transport::{mock_luxafor, Transport};
+use crate::LuxaforError;
+use std::time::Instant;
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-3
+}
+
+#[test]
+fn easing_curves() {
+    for easing in [
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+        Easing::CubicBezier(0.1, 0.7, 0.1, 1.0),
+    ] {
+        assert!(close(easing.apply(0.0), 0.0), "{:?}", easing);
+        assert!(close(easing.apply(1.0), 1.0), "{:?}", easing);
+    }
+
+    assert!(close(Easing::Linear.apply(0.25), 0.25));
+    assert!(close(Easing::EaseInOut.apply(0.5), 0.5));
+    assert!(Easing::EaseIn.apply(0.25) < 0.25);
+    assert!(Easing::EaseOut.apply(0.25) > 0.25);
+    assert!(close(
+        Easing::CubicBezier(0.0, 0.0, 1.0, 1.0).apply(0.3),
+        0.3
+    ));
+    // CSS's `ease`.
+    assert!((Easing::CubicBezier(0.25, 0.1, 0.25, 1.0).apply(0.5) - 0.8024).abs() < 0.001);
+}
+
+#[test]
+fn steps() {
+    assert_eq!(Easing::Steps(4).apply(0.2), 0.0);
+    assert_eq!(Easing::Steps(4).apply(0.3), 0.25);
+    assert_eq!(Easing::Steps(4).apply(1.0), 1.0);
+    assert_eq!(Easing::Steps(0).apply(0.9), 0.0);
+}
+
+#[test]
+fn track_interpolates_between_keyframes() {
+    let track = Track::new()
+        .keyframe(ms(200), RgbColor(200, 0, 0), Easing::Linear)
+        .keyframe(ms(100), RgbColor(100, 0, 0), Easing::Linear);
+
+    assert_eq!(track.duration(), ms(200));
+    assert_eq!(track.color_at(ms(0)), Some(RgbColor(100, 0, 0)));
+    assert_eq!(track.color_at(ms(150)), Some(RgbColor(150, 0, 0)));
+    assert_eq!(track.color_at(ms(500)), Some(RgbColor(200, 0, 0)));
+    assert_eq!(Track::new().color_at(ms(0)), None);
+}
+
+#[test]
+fn keyframes_at_the_same_time_jump() {
+    let track = Track::new()
+        .keyframe(ms(0), RgbColor::red(), Easing::Linear)
+        .keyframe(ms(100), RgbColor::red(), Easing::Linear)
+        .keyframe(ms(100), RgbColor::blue(), Easing::Linear)
+        .keyframe(ms(200), RgbColor::blue(), Easing::Linear);
+
+    assert_eq!(track.color_at(ms(99)), Some(RgbColor::red()));
+    assert_eq!(track.color_at(ms(100)), Some(RgbColor::blue()));
+}
+
+#[test]
+fn untracked_leds_keep_the_base_color() {
+    let animation = Animation::new().track(
+        Lights::flag(),
+        Track::new().keyframe(ms(0), RgbColor::green(), Easing::Linear),
+    );
+    let base = Frame::solid(RgbColor::white());
+
+    assert_eq!(
+        animation.frame_at(ms(0), &base),
+        base.with(Lights::flag(), RgbColor::green())
+    );
+}
+
+#[test]
+fn play_ends_on_the_last_keyframe() {
+    let (luxafor, mock) = mock_luxafor();
+    let animation = Animation::new().track(
+        Lights::BackTop.into(),
+        Track::new()
+            .keyframe(ms(0), RgbColor::off(), Easing::Linear)
+            .keyframe(ms(60), RgbColor(0, 0, 90), Easing::Linear),
+    );
+
+    animation.play(&luxafor, 100, &CancelToken::new()).unwrap();
+
+    let state = luxafor.current_state();
+    assert_eq!(
+        state[Lights::BackTop.index()],
+        LedState::Color(RgbColor(0, 0, 90))
+    );
+    assert_eq!(
+        state[Lights::FlagTop.index()],
+        LedState::Color(RgbColor::off())
+    );
+    // One frame for every 10ms at most, plus the first.
+    assert!(mock.frames().len() <= 8, "{:?}", mock.frames());
+}
+
+#[test]
+fn cancel_stops_a_looping_animation() {
+    let (luxafor, mock) = mock_luxafor();
+    let animation = Animation::new()
+        .track(
+            Lights::all(),
+            Track::new()
+                .keyframe(ms(0), RgbColor::red(), Easing::Linear)
+                .keyframe(ms(50), RgbColor::blue(), Easing::EaseInOut),
+        )
+        .looping(true);
+
+    let playing = animation.spawn(luxafor, 50).unwrap();
+    std::thread::sleep(ms(120));
+    assert!(!playing.is_finished());
+
+    let stopping = Instant::now();
+    playing.stop().1.unwrap();
+    assert!(stopping.elapsed() < ms(100));
+    assert!(!mock.frames().is_empty());
+}
+
+#[test]
+fn absurd_frame_rates_are_capped() {
+    let (luxafor, _) = mock_luxafor();
+    let animation = Animation::new().track(
+        Lights::all(),
+        Track::new()
+            .keyframe(ms(0), RgbColor::off(), Easing::Linear)
+            .keyframe(ms(20), RgbColor::white(), Easing::Linear),
+    );
+
+    animation
+        .play(&luxafor, u32::MAX, &CancelToken::new())
+        .unwrap();
+
+    assert_eq!(
+        luxafor.current_state(),
+        [LedState::Color(RgbColor::white()); 6]
+    );
+}
+
+#[test]
+fn a_failed_animation_hands_back_the_device() {
+    struct UnpluggedTransport;
+    impl Transport for UnpluggedTransport {
+        fn write(&self, _data: &[u8]) -> Result<usize, LuxaforError> {
+            Err(LuxaforError::Disconnected)
+        }
+        fn read_timeout(&self, _buf: &mut [u8], _timeout: i32) -> Result<usize, LuxaforError> {
+            Err(LuxaforError::Disconnected)
+        }
+        fn manufacturer_string(&self) -> Result<Option<String>, LuxaforError> {
+            Ok(None)
+        }
+        fn product_string(&self) -> Result<Option<String>, LuxaforError> {
+            Ok(None)
+        }
+        fn serial_number_string(&self) -> Result<Option<String>, LuxaforError> {
+            Ok(None)
+        }
+    }
+
+    let luxafor = Luxafor::with_transport(Box::new(UnpluggedTransport));
+    let animation = Animation::new().track(
+        Lights::all(),
+        Track::new().keyframe(ms(0), RgbColor::red(), Easing::Linear),
+    );
+
+    let (luxafor, result) = animation.spawn(luxafor, 50).unwrap().wait();
+    assert!(matches!(result, Err(LuxaforError::Disconnected)));
+    // It's still the same device, which can be used again once it's back.
+    assert!(matches!(
+        luxafor.set_rgb_color(RgbColor::red(), Lights::all()),
+        Err(LuxaforError::Disconnected)
+    ));
+}
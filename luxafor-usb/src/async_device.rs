@@ -0,0 +1,253 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Driving a device from async code.
+//!
+//! Talking to a device blocks, so `AsyncLuxafor` hands the device to a thread of its own and
+//! sends it work over a channel, leaving the async side free to get on with other things.  This
+//! module is only available with the `async` feature.
+
+use crate::command::Command;
+use crate::device::{
+    DeviceResponse, LedState, Lights, Luxafor, PatternType, RgbColor, SimpleColor, WaveType,
+};
+use crate::frame::Frame;
+use crate::model::DeviceModel;
+use crate::operation::Operation;
+use crate::LuxaforError;
+use enumflags2::BitFlags;
+use log::{debug, trace};
+use std::{
+    sync::{
+        mpsc::{self, RecvTimeoutError, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::sync::{mpsc::UnboundedSender, oneshot};
+
+#[cfg(test)]
+mod tests;
+
+type Job = Box<dyn FnOnce(&Luxafor) + Send>;
+
+/// A `Luxafor` whose operations are `async fn`s.
+///
+/// The device lives on a dedicated I/O thread, which carries out operations one at a time, in
+/// the order they were started.  An operation that's already been handed to the device finishes
+/// even if its future is dropped, except for `sequence`, which stops at the next step.
+pub struct AsyncLuxafor {
+    jobs: UnboundedSender<Job>,
+    model: &'static DeviceModel,
+    state: Arc<Mutex<[LedState; 6]>>,
+}
+
+impl AsyncLuxafor {
+    /// Moves `luxafor` onto an I/O thread of its own.  The thread exits once the `AsyncLuxafor`
+    /// is dropped and any operations still queued have been carried out.
+    pub fn new(luxafor: Luxafor) -> Result<Self, LuxaforError> {
+        let (jobs, mut queue) = tokio::sync::mpsc::unbounded_channel::<Job>();
+        let model = luxafor.model;
+        let state = luxafor.state.clone();
+
+        thread::Builder::new()
+            .name(String::from("luxafor-io"))
+            .spawn(move || {
+                while let Some(job) = queue.blocking_recv() {
+                    job(&luxafor);
+                }
+                trace!("I/O thread stopped");
+            })
+            .map_err(|e| LuxaforError::Thread(format!("I/O thread: {}", e)))?;
+
+        Ok(Self { jobs, model, state })
+    }
+
+    /// Opens the first Luxafor device found.  See `Luxafor::new`.
+    pub async fn open() -> Result<Self, LuxaforError> {
+        Self::new(Self::blocking(Luxafor::new).await?)
+    }
+
+    /// Opens the Luxafor device with the given serial number.  See `Luxafor::open_serial`.
+    pub async fn open_serial(serial_number: &str) -> Result<Self, LuxaforError> {
+        let serial_number = serial_number.to_string();
+        Self::new(Self::blocking(move || Luxafor::open_serial(&serial_number)).await?)
+    }
+
+    /// Opens the Luxafor device at the given path.  See `Luxafor::open_path`.
+    pub async fn open_path(path: &str) -> Result<Self, LuxaforError> {
+        let path = path.to_string();
+        Self::new(Self::blocking(move || Luxafor::open_path(&path)).await?)
+    }
+
+    /// Runs `f` on a thread of its own, for the blocking work that happens before there's an I/O
+    /// thread to do it on.
+    async fn blocking<T, F>(f: F) -> Result<T, LuxaforError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T, LuxaforError> + Send + 'static,
+    {
+        let (reply, result) = oneshot::channel();
+        thread::Builder::new()
+            .name(String::from("luxafor-open"))
+            .spawn(move || {
+                let _ = reply.send(f());
+            })
+            .map_err(|e| LuxaforError::Thread(format!("opening thread: {}", e)))?;
+
+        result.await.map_err(|_| LuxaforError::IoThreadStopped)?
+    }
+
+    /// Hands `f` to the I/O thread and waits for its result.
+    async fn call<T, F>(&self, f: F) -> Result<T, LuxaforError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Luxafor) -> Result<T, LuxaforError> + Send + 'static,
+    {
+        let (reply, result) = oneshot::channel();
+        self.jobs
+            .send(Box::new(move |luxafor| {
+                // Nobody's waiting for the result if the future has been dropped; that's fine.
+                let _ = reply.send(f(luxafor));
+            }))
+            .map_err(|_| LuxaforError::IoThreadStopped)?;
+
+        result.await.map_err(|_| LuxaforError::IoThreadStopped)?
+    }
+
+    /// See `Luxafor::set_simple_color`.
+    pub async fn set_simple_color(&self, color: SimpleColor) -> Result<(), LuxaforError> {
+        self.call(move |l| l.set_simple_color(color)).await
+    }
+
+    /// See `Luxafor::set_rgb_color`.
+    pub async fn set_rgb_color(
+        &self,
+        color: RgbColor,
+        lights: BitFlags<Lights>,
+    ) -> Result<(), LuxaforError> {
+        self.call(move |l| l.set_rgb_color(color, lights)).await
+    }
+
+    /// See `Luxafor::fade_to_color`.
+    pub async fn fade_to_color(
+        &self,
+        color: RgbColor,
+        lights: BitFlags<Lights>,
+        fade_time: u8,
+    ) -> Result<(), LuxaforError> {
+        self.call(move |l| l.fade_to_color(color, lights, fade_time))
+            .await
+    }
+
+    /// See `Luxafor::strobe`.
+    pub async fn strobe(
+        &self,
+        color: RgbColor,
+        lights: BitFlags<Lights>,
+        speed: u8,
+        repeat: u8,
+    ) -> Result<(), LuxaforError> {
+        self.call(move |l| l.strobe(color, lights, speed, repeat))
+            .await
+    }
+
+    /// See `Luxafor::wave`.
+    pub async fn wave(
+        &self,
+        color: RgbColor,
+        wave_type: WaveType,
+        speed: u8,
+        repeat: u8,
+    ) -> Result<(), LuxaforError> {
+        self.call(move |l| l.wave(color, wave_type, speed, repeat))
+            .await
+    }
+
+    /// See `Luxafor::pattern`.
+    pub async fn pattern(&self, pattern_type: PatternType, repeat: u8) -> Result<(), LuxaforError> {
+        self.call(move |l| l.pattern(pattern_type, repeat)).await
+    }
+
+    /// See `Luxafor::execute`.
+    pub async fn execute(&self, command: Command) -> Result<(), LuxaforError> {
+        self.call(move |l| l.execute(command)).await
+    }
+
+    /// See `Luxafor::perform`.
+    pub async fn perform(&self, operation: Operation) -> Result<(), LuxaforError> {
+        self.call(move |l| l.perform(operation)).await
+    }
+
+    /// See `Luxafor::show`.
+    pub async fn show(&self, frame: &Frame) -> Result<(), LuxaforError> {
+        let frame = *frame;
+        self.call(move |l| l.show(&frame)).await
+    }
+
+    /// See `Luxafor::status`.
+    pub async fn status(&self) -> Result<DeviceResponse, LuxaforError> {
+        self.call(|l| l.status()).await
+    }
+
+    /// Plays a software animation: executes each command in turn, holding it for the duration
+    /// that goes with it before moving on to the next.  Timing is kept against the start of the
+    /// sequence, so it doesn't drift however long the commands take to send.
+    ///
+    /// Dropping the future (for instance, by losing a `tokio::select!`) stops the sequence before
+    /// its next step.  The LEDs are left showing whatever the last step sent.
+    pub async fn sequence(&self, steps: Vec<(Command, Duration)>) -> Result<(), LuxaforError> {
+        // Nothing is ever sent on this; the I/O thread notices it's been dropped along with the
+        // future.
+        let (cancel, cancelled) = mpsc::channel::<()>();
+
+        let result = self
+            .call(move |luxafor| {
+                let start = Instant::now();
+                let mut deadline = Duration::from_secs(0);
+                for (command, hold) in steps {
+                    if let Err(TryRecvError::Disconnected) = cancelled.try_recv() {
+                        debug!("sequence cancelled");
+                        return Ok(());
+                    }
+                    luxafor.execute(command)?;
+
+                    deadline += hold;
+                    let wait = deadline.checked_sub(start.elapsed()).unwrap_or_default();
+                    if let Err(RecvTimeoutError::Disconnected) = cancelled.recv_timeout(wait) {
+                        debug!("sequence cancelled");
+                        return Ok(());
+                    }
+                }
+                Ok(())
+            })
+            .await;
+        drop(cancel);
+
+        result
+    }
+
+    /// See `Luxafor::current_state`.  This doesn't wait for operations that are still queued.
+    pub fn current_state(&self) -> [LedState; 6] {
+        *self.state.lock().unwrap()
+    }
+
+    /// See `Luxafor::model`.
+    pub fn model(&self) -> &'static DeviceModel {
+        self.model
+    }
+}
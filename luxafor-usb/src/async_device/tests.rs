@@ -0,0 +1,127 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::async_device::AsyncLuxafor;
+use crate::command::Command;
+use crate::device::{LedState, Lights, Luxafor, RgbColor, SimpleColor, Target};
+use crate::frame::Frame;
+use crate::transport::MockTransport;
+use crate::LuxaforError;
+use std::time::{Duration, Instant};
+
+fn async_luxafor(product_string: &str) -> (AsyncLuxafor, MockTransport) {
+    let mock = MockTransport::with_product_string(product_string);
+    let luxafor = Luxafor::with_transport(Box::new(mock.clone()));
+    (AsyncLuxafor::new(luxafor).unwrap(), mock)
+}
+
+#[tokio::test]
+async fn operations_reach_the_device() {
+    let (luxafor, mock) = async_luxafor("LUXAFOR FLAG");
+
+    luxafor.set_simple_color(SimpleColor::Red).await.unwrap();
+    luxafor
+        .set_rgb_color(RgbColor(1, 2, 3), Lights::back())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        mock.frames(),
+        vec![vec![0, 0, b'R'], vec![0, 1, b'B', 1, 2, 3]]
+    );
+    assert_eq!(
+        luxafor.current_state()[Lights::BackTop.index()],
+        LedState::Color(RgbColor(1, 2, 3))
+    );
+}
+
+#[tokio::test]
+async fn frames_are_shown() {
+    let (luxafor, mock) = async_luxafor("LUXAFOR FLAG");
+    let frame = Frame::solid(RgbColor::red()).with(Lights::BackTop.into(), RgbColor::blue());
+
+    luxafor.show(&frame).await.unwrap();
+
+    assert_eq!(
+        mock.frames(),
+        vec![vec![0, 1, 255, 255, 0, 0], vec![0, 1, 6, 0, 0, 255]]
+    );
+}
+
+#[tokio::test]
+async fn errors_come_back() {
+    let (luxafor, mock) = async_luxafor("LUXAFOR ORB");
+
+    assert!(matches!(
+        luxafor.set_rgb_color(RgbColor::red(), Lights::back()).await,
+        Err(LuxaforError::Unsupported { .. })
+    ));
+    assert!(mock.frames().is_empty());
+}
+
+#[tokio::test]
+async fn sequence_plays_in_order() {
+    let (luxafor, mock) = async_luxafor("LUXAFOR FLAG");
+    let steps = vec![
+        (
+            Command::SimpleColor(SimpleColor::Red),
+            Duration::from_millis(20),
+        ),
+        (
+            Command::SimpleColor(SimpleColor::Off),
+            Duration::from_millis(20),
+        ),
+    ];
+
+    let start = Instant::now();
+    luxafor.sequence(steps).await.unwrap();
+
+    assert!(start.elapsed() >= Duration::from_millis(40));
+    assert_eq!(mock.frames(), vec![vec![0, 0, b'R'], vec![0, 0, b'O']]);
+}
+
+#[tokio::test]
+async fn dropping_a_sequence_cancels_it() {
+    let (luxafor, mock) = async_luxafor("LUXAFOR FLAG");
+    let steps = vec![
+        (
+            Command::RgbColor {
+                color: RgbColor::blue(),
+                target: Target::All,
+            },
+            Duration::from_secs(10),
+        ),
+        (
+            Command::SimpleColor(SimpleColor::Off),
+            Duration::from_secs(10),
+        ),
+    ];
+
+    let start = Instant::now();
+    assert!(
+        tokio::time::timeout(Duration::from_millis(50), luxafor.sequence(steps))
+            .await
+            .is_err()
+    );
+
+    // The I/O thread should be free again straight away, rather than waiting out the sequence.
+    luxafor.set_simple_color(SimpleColor::Green).await.unwrap();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(
+        mock.frames(),
+        vec![vec![0, 1, 0xFF, 0, 0, 255], vec![0, 0, b'G']]
+    );
+}
@@ -0,0 +1,58 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Finding Luxafor devices and opening them, which is the only part of talking to one that
+//! differs from platform to platform.
+//!
+//! There are two backends, chosen with cargo features.  `hidapi` (the default) goes through the
+//! hidapi C library, and works wherever it does.  `hidraw` talks to Linux's `/dev/hidraw*` nodes
+//! directly, so nothing but Rust is needed to build it and it can be linked statically.  If both
+//! are enabled, `hidraw` is used.
+
+use crate::device::DeviceIdentity;
+use crate::transport::Transport;
+
+#[cfg(all(feature = "hidapi", not(feature = "hidraw")))]
+mod hidapi;
+#[cfg(feature = "hidraw")]
+mod hidraw;
+
+#[cfg(all(feature = "hidapi", not(feature = "hidraw")))]
+pub(crate) use self::hidapi::{enumerate, open, NAME};
+#[cfg(feature = "hidraw")]
+pub(crate) use self::hidraw::{enumerate, open, NAME};
+
+#[cfg(not(any(feature = "hidapi", feature = "hidraw")))]
+compile_error!("luxafor-usb needs a backend: enable either the `hidapi` or the `hidraw` feature");
+#[cfg(all(feature = "hidraw", not(target_os = "linux")))]
+compile_error!("the `hidraw` backend only works on Linux");
+
+/// Which device `open` should open.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Selector<'a> {
+    /// The first Luxafor device found.
+    First,
+    Serial(&'a str),
+    Path(&'a str),
+}
+
+/// A device a backend has opened.
+pub(crate) struct Opened {
+    pub(crate) transport: Box<dyn Transport>,
+    /// What we need to recognise the device when it's plugged back in, if the backend could
+    /// find it among the connected devices.
+    pub(crate) identity: Option<DeviceIdentity>,
+}
@@ -0,0 +1,171 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! The backend built on the hidapi C library.
+
+use crate::backend::{Opened, Selector};
+use crate::device::{DeviceDescriptor, DeviceIdentity};
+use crate::model::DeviceModel;
+use crate::transport::Transport;
+use crate::usb::{LUXAFOR_PID, LUXAFOR_VID};
+use crate::LuxaforError;
+use ::hidapi::{DeviceInfo, HidApi, HidDevice, HidError};
+use log::debug;
+use std::{ffi::CString, fs::OpenOptions, io, sync::Mutex};
+
+pub(crate) const NAME: &str = "hidapi";
+
+/// hidapi only allows one `HidApi` to exist at a time, and every device opened through it keeps
+/// it alive, so a second `HidApi::new` fails for as long as a device is open.  We share one
+/// instance instead, refreshing its device list each time it's used.
+static HID_API: Mutex<Option<HidApi>> = Mutex::new(None);
+
+pub(crate) fn enumerate() -> Result<Vec<DeviceDescriptor>, LuxaforError> {
+    with_hid_api(|api| {
+        Ok(api
+            .device_list()
+            .filter_map(|d| {
+                DeviceModel::detect(d.vendor_id(), d.product_id(), d.product_string()).map(
+                    |model| DeviceDescriptor {
+                        path: d.path().to_string_lossy().into_owned(),
+                        serial_number: d.serial_number().map(String::from),
+                        product_string: d.product_string().map(String::from),
+                        release_number: d.release_number(),
+                        model,
+                    },
+                )
+            })
+            .collect())
+    })
+}
+
+pub(crate) fn open(selector: Selector) -> Result<Opened, LuxaforError> {
+    match selector {
+        Selector::First => with_hid_api(|api| match api.open(LUXAFOR_VID, LUXAFOR_PID) {
+            // hidapi opens the first matching device it enumerates, so that's the one we've got.
+            Ok(hid_device) => Ok(opened(
+                hid_device,
+                api.device_list()
+                    .find(|d| d.vendor_id() == LUXAFOR_VID && d.product_id() == LUXAFOR_PID),
+            )),
+            Err(e) => Err(diagnose_open_error(api, e, |_| true)),
+        }),
+        Selector::Serial(serial_number) => {
+            let wanted = |d: &DeviceInfo| d.serial_number() == Some(serial_number);
+            with_hid_api(
+                |api| match api.open_serial(LUXAFOR_VID, LUXAFOR_PID, serial_number) {
+                    Ok(hid_device) => Ok(opened(hid_device, api.device_list().find(|d| wanted(d)))),
+                    Err(e) => Err(diagnose_open_error(api, e, wanted)),
+                },
+            )
+        }
+        Selector::Path(path) => {
+            let c_path = match CString::new(path) {
+                Ok(p) => p,
+                Err(_) => return Err(LuxaforError::Open(format!("Invalid device path: {}", path))),
+            };
+
+            let wanted = |d: &DeviceInfo| d.path() == c_path.as_c_str();
+            with_hid_api(|api| match api.open_path(&c_path) {
+                Ok(hid_device) => Ok(opened(hid_device, api.device_list().find(|d| wanted(d)))),
+                Err(e) => Err(diagnose_open_error(api, e, wanted)),
+            })
+        }
+    }
+}
+
+fn with_hid_api<T, F>(f: F) -> Result<T, LuxaforError>
+where
+    F: FnOnce(&HidApi) -> Result<T, LuxaforError>,
+{
+    let mut api = HID_API.lock().unwrap_or_else(|e| e.into_inner());
+    match api.as_mut() {
+        Some(api) => api
+            .refresh_devices()
+            .map_err(|e| LuxaforError::Open(e.to_string()))?,
+        None => {
+            *api = Some(HidApi::new().map_err(|e| LuxaforError::Open(e.to_string()))?);
+        }
+    }
+
+    f(api.as_ref().unwrap())
+}
+
+fn opened(hid_device: HidDevice, info: Option<&DeviceInfo>) -> Opened {
+    Opened {
+        transport: Box::new(hid_device),
+        identity: info.map(|d| DeviceIdentity {
+            path: d.path().to_string_lossy().into_owned(),
+            serial_number: d
+                .serial_number()
+                .filter(|s| !s.is_empty())
+                .map(String::from),
+        }),
+    }
+}
+
+/// hidapi's errors from `open` don't say why the device couldn't be opened (and on Linux
+/// usually don't say anything at all), so work out whether the device was missing entirely
+/// or was there but couldn't be opened.  The latter is almost always a permissions problem
+/// with the `/dev/hidraw*` node.
+fn diagnose_open_error<P>(api: &HidApi, error: HidError, wanted: P) -> LuxaforError
+where
+    P: Fn(&DeviceInfo) -> bool,
+{
+    debug!("failed to open device: {}", error);
+
+    match api
+        .device_list()
+        .filter(|d| DeviceModel::is_known(d.vendor_id(), d.product_id()))
+        .find(|d| wanted(d))
+    {
+        None => LuxaforError::DeviceNotFound,
+        Some(device) => {
+            let path = device.path().to_string_lossy().into_owned();
+            match OpenOptions::new().read(true).write(true).open(&path) {
+                Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                    LuxaforError::PermissionDenied(path)
+                }
+                _ => LuxaforError::Open(error.to_string()),
+            }
+        }
+    }
+}
+
+impl Transport for HidDevice {
+    fn write(&self, data: &[u8]) -> Result<usize, LuxaforError> {
+        HidDevice::write(self, data).map_err(|e| LuxaforError::Write(e.to_string()))
+    }
+
+    fn read_timeout(&self, buf: &mut [u8], timeout: i32) -> Result<usize, LuxaforError> {
+        HidDevice::read_timeout(self, buf, timeout).map_err(|e| LuxaforError::Read(e.to_string()))
+    }
+
+    fn manufacturer_string(&self) -> Result<Option<String>, LuxaforError> {
+        self.get_manufacturer_string()
+            .map_err(|e| LuxaforError::Read(e.to_string()))
+    }
+
+    fn product_string(&self) -> Result<Option<String>, LuxaforError> {
+        self.get_product_string()
+            .map_err(|e| LuxaforError::Read(e.to_string()))
+    }
+
+    fn serial_number_string(&self) -> Result<Option<String>, LuxaforError> {
+        self.get_serial_number_string()
+            .map_err(|e| LuxaforError::Read(e.to_string()))
+    }
+}
@@ -0,0 +1,328 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! The backend that talks to Linux's `/dev/hidraw*` nodes directly.
+//!
+//! Devices are found through sysfs: each node's `device/uevent` gives its USB IDs, and walking up
+//! from there to the USB device gives the strings from its descriptors, just as hidapi's own
+//! hidraw backend does.  Frames go out with `write(2)` and reports come back with `poll(2)` and
+//! `read(2)`.
+
+use crate::backend::{Opened, Selector};
+use crate::device::{DeviceDescriptor, DeviceIdentity};
+use crate::linux::parse_hid_id;
+use crate::model::DeviceModel;
+use crate::transport::Transport;
+use crate::LuxaforError;
+use log::{debug, trace};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    mem,
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+};
+
+#[cfg(test)]
+mod tests;
+
+pub(crate) const NAME: &str = "hidraw";
+
+const SYSFS_HIDRAW: &str = "/sys/class/hidraw";
+const DEV: &str = "/dev";
+
+/// `struct hidraw_devinfo`, from `<linux/hidraw.h>`.
+#[repr(C)]
+#[derive(Debug, Default)]
+struct HidrawDevinfo {
+    bustype: u32,
+    vendor: i16,
+    product: i16,
+}
+
+/// The direction bits of an `_IOR` ioctl, which live in a different place on a few architectures.
+#[cfg(any(
+    target_arch = "mips",
+    target_arch = "mips64",
+    target_arch = "powerpc",
+    target_arch = "powerpc64",
+    target_arch = "sparc",
+    target_arch = "sparc64"
+))]
+const IOC_READ: u32 = 2 << 29;
+#[cfg(not(any(
+    target_arch = "mips",
+    target_arch = "mips64",
+    target_arch = "powerpc",
+    target_arch = "powerpc64",
+    target_arch = "sparc",
+    target_arch = "sparc64"
+)))]
+const IOC_READ: u32 = 2 << 30;
+
+/// `HIDIOCGRAWINFO`, which is `_IOR('H', 0x03, struct hidraw_devinfo)`.
+const HIDIOCGRAWINFO: u32 =
+    IOC_READ | (mem::size_of::<HidrawDevinfo>() as u32) << 16 | (b'H' as u32) << 8 | 0x03;
+
+/// What sysfs has to say about one hidraw node.
+#[derive(Clone, Debug, PartialEq)]
+struct Node {
+    path: String,
+    vendor_id: u16,
+    product_id: u16,
+    manufacturer: Option<String>,
+    product: Option<String>,
+    serial_number: Option<String>,
+    release_number: u16,
+}
+
+pub(crate) fn enumerate() -> Result<Vec<DeviceDescriptor>, LuxaforError> {
+    Ok(luxafor_nodes()?
+        .into_iter()
+        .filter_map(|node| {
+            DeviceModel::detect(node.vendor_id, node.product_id, node.product.as_deref()).map(
+                |model| DeviceDescriptor {
+                    path: node.path,
+                    serial_number: node.serial_number,
+                    product_string: node.product,
+                    release_number: node.release_number,
+                    model,
+                },
+            )
+        })
+        .collect())
+}
+
+pub(crate) fn open(selector: Selector) -> Result<Opened, LuxaforError> {
+    let node = match selector {
+        Selector::First => luxafor_nodes()?.into_iter().next(),
+        Selector::Serial(serial_number) => luxafor_nodes()?
+            .into_iter()
+            .find(|node| node.serial_number.as_deref() == Some(serial_number)),
+        Selector::Path(path) => {
+            // Open whatever we're given, as hidapi would; the ioctl in `open_node` makes sure
+            // it's a Luxafor.
+            let node = Path::new(path)
+                .file_name()
+                .and_then(|name| read_node(&Path::new(SYSFS_HIDRAW).join(name), path));
+            return open_node(path, node);
+        }
+    };
+
+    match node {
+        Some(node) => open_node(&node.path.clone(), Some(node)),
+        None => Err(LuxaforError::DeviceNotFound),
+    }
+}
+
+fn luxafor_nodes() -> Result<Vec<Node>, LuxaforError> {
+    let nodes = nodes(Path::new(SYSFS_HIDRAW), Path::new(DEV))
+        .map_err(|e| LuxaforError::Open(format!("Couldn't read {}: {}", SYSFS_HIDRAW, e)))?;
+
+    Ok(nodes
+        .into_iter()
+        .filter(|node| DeviceModel::is_known(node.vendor_id, node.product_id))
+        .collect())
+}
+
+/// Reads every hidraw node's details from `sysfs` (normally `/sys/class/hidraw`), in the order
+/// the kernel numbered them.  A missing `sysfs` directory means there aren't any.
+fn nodes(sysfs: &Path, dev: &Path) -> io::Result<Vec<Node>> {
+    let entries = match fs::read_dir(sysfs) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+
+    let mut nodes = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let path = dev.join(entry.file_name());
+            read_node(&entry.path(), &path.to_string_lossy())
+        })
+        .collect::<Vec<Node>>();
+    nodes.sort_by_key(|node| {
+        let number = node.path.trim_start_matches(|c: char| !c.is_ascii_digit());
+        (number.len(), number.to_string())
+    });
+    trace!("hidraw nodes: {:?}", nodes);
+
+    Ok(nodes)
+}
+
+/// Reads the details of the hidraw node whose sysfs directory is `class_dir` (e.g.
+/// `/sys/class/hidraw/hidraw3`).
+fn read_node(class_dir: &Path, path: &str) -> Option<Node> {
+    let hid_device = class_dir.join("device");
+    let uevent = fs::read_to_string(hid_device.join("uevent")).ok()?;
+    let (vendor_id, product_id) = parse_hid_id(&uevent)?;
+    let uevent_value = |key: &str| {
+        uevent
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+            .filter(|value| !value.is_empty())
+            .map(String::from)
+    };
+
+    // USB devices' descriptor strings live a few directories up, on the USB device itself.
+    // Anything else (a Bluetooth device, say) only has what the HID layer knows.
+    let node = match usb_device(&hid_device) {
+        Some(usb) => {
+            let attribute = |name: &str| {
+                fs::read_to_string(usb.join(name))
+                    .ok()
+                    .map(|value| value.trim_end().to_string())
+            };
+            Node {
+                path: path.to_string(),
+                vendor_id,
+                product_id,
+                manufacturer: attribute("manufacturer"),
+                product: attribute("product"),
+                serial_number: attribute("serial"),
+                release_number: attribute("bcdDevice")
+                    .and_then(|bcd| u16::from_str_radix(&bcd, 16).ok())
+                    .unwrap_or(0),
+            }
+        }
+        None => Node {
+            path: path.to_string(),
+            vendor_id,
+            product_id,
+            manufacturer: None,
+            product: uevent_value("HID_NAME"),
+            serial_number: uevent_value("HID_UNIQ"),
+            release_number: 0,
+        },
+    };
+
+    Some(node)
+}
+
+/// Finds the USB device a HID device belongs to: the nearest directory above it with an
+/// `idVendor` attribute.
+fn usb_device(hid_device: &Path) -> Option<PathBuf> {
+    let hid_device = fs::canonicalize(hid_device).ok()?;
+    hid_device
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join("idVendor").is_file())
+        .map(Path::to_path_buf)
+}
+
+fn open_node(path: &str, node: Option<Node>) -> Result<Opened, LuxaforError> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::PermissionDenied => LuxaforError::PermissionDenied(path.to_string()),
+            io::ErrorKind::NotFound => LuxaforError::DeviceNotFound,
+            _ => LuxaforError::Open(format!("{}: {}", path, e)),
+        })?;
+
+    let info = devinfo(&file).map_err(|e| LuxaforError::Open(format!("{}: {}", path, e)))?;
+    let (vendor_id, product_id) = (info.vendor as u16, info.product as u16);
+    if !DeviceModel::is_known(vendor_id, product_id) {
+        return Err(LuxaforError::Open(format!(
+            "{} isn't a Luxafor device (its USB ID is {:04x}:{:04x})",
+            path, vendor_id, product_id
+        )));
+    }
+    debug!("opened {} ({:?})", path, node);
+
+    let node = node.unwrap_or(Node {
+        path: path.to_string(),
+        vendor_id,
+        product_id,
+        manufacturer: None,
+        product: None,
+        serial_number: None,
+        release_number: 0,
+    });
+    Ok(Opened {
+        identity: Some(DeviceIdentity {
+            path: path.to_string(),
+            serial_number: node.serial_number.clone(),
+        }),
+        transport: Box::new(HidrawDevice { file, node }),
+    })
+}
+
+fn devinfo(file: &File) -> io::Result<HidrawDevinfo> {
+    let mut info = HidrawDevinfo::default();
+    // SAFETY: HIDIOCGRAWINFO writes a `struct hidraw_devinfo`, which `info` is laid out as.
+    let result = unsafe { libc::ioctl(file.as_raw_fd(), HIDIOCGRAWINFO as _, &mut info) };
+    if result < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(info)
+    }
+}
+
+/// An open hidraw node.  The descriptor strings are the ones sysfs gave when it was opened.
+struct HidrawDevice {
+    file: File,
+    node: Node,
+}
+
+impl Transport for HidrawDevice {
+    fn write(&self, data: &[u8]) -> Result<usize, LuxaforError> {
+        (&self.file)
+            .write(data)
+            .map_err(|e| LuxaforError::Write(e.to_string()))
+    }
+
+    fn read_timeout(&self, buf: &mut [u8], timeout: i32) -> Result<usize, LuxaforError> {
+        let mut fd = libc::pollfd {
+            fd: self.file.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        loop {
+            // SAFETY: `fd` is a single, valid `pollfd`.
+            match unsafe { libc::poll(&mut fd, 1, timeout) } {
+                0 => return Ok(0),
+                n if n > 0 => break,
+                _ => {
+                    let e = io::Error::last_os_error();
+                    if e.kind() != io::ErrorKind::Interrupted {
+                        return Err(LuxaforError::Read(e.to_string()));
+                    }
+                }
+            }
+        }
+        if fd.revents & (libc::POLLERR | libc::POLLHUP | libc::POLLNVAL) != 0 {
+            return Err(LuxaforError::Read(String::from("the device has gone away")));
+        }
+
+        (&self.file)
+            .read(buf)
+            .map_err(|e| LuxaforError::Read(e.to_string()))
+    }
+
+    fn manufacturer_string(&self) -> Result<Option<String>, LuxaforError> {
+        Ok(self.node.manufacturer.clone())
+    }
+
+    fn product_string(&self) -> Result<Option<String>, LuxaforError> {
+        Ok(self.node.product.clone())
+    }
+
+    fn serial_number_string(&self) -> Result<Option<String>, LuxaforError> {
+        Ok(self.node.serial_number.clone())
+    }
+}
@@ -0,0 +1,154 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::backend::hidraw::{nodes, HidrawDevice, Node};
+use crate::transport::Transport;
+use std::{
+    env, fs,
+    fs::File,
+    io::{Read, Write},
+    os::unix::{fs::symlink, net::UnixStream},
+    path::{Path, PathBuf},
+    process,
+};
+
+/// A scratch directory laid out like the parts of sysfs the backend reads, removed on drop.
+struct FakeSysfs {
+    root: PathBuf,
+}
+
+impl FakeSysfs {
+    fn new(name: &str) -> Self {
+        let root = env::temp_dir().join(format!("luxafor-usb-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("class/hidraw")).unwrap();
+        FakeSysfs { root }
+    }
+
+    /// Adds a hidraw node whose HID device is at `hid_device` (relative to `devices/`), with the
+    /// given `uevent`, and USB device attributes (on `hid_device`'s grandparent) if any.
+    fn add(&self, node: &str, hid_device: &str, uevent: &str, usb: &[(&str, &str)]) {
+        let hid_device = self.root.join("devices").join(hid_device);
+        fs::create_dir_all(&hid_device).unwrap();
+        fs::write(hid_device.join("uevent"), uevent).unwrap();
+        if !usb.is_empty() {
+            let usb_device = hid_device.parent().and_then(Path::parent).unwrap();
+            fs::write(usb_device.join("idVendor"), "04d8\n").unwrap();
+            for (name, value) in usb {
+                fs::write(usb_device.join(name), format!("{}\n", value)).unwrap();
+            }
+        }
+
+        let class_dir = self.root.join("class/hidraw").join(node);
+        fs::create_dir_all(&class_dir).unwrap();
+        symlink(&hid_device, class_dir.join("device")).unwrap();
+    }
+}
+
+impl Drop for FakeSysfs {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+#[test]
+fn nodes_are_read_from_sysfs() {
+    let sysfs = FakeSysfs::new("hidraw-nodes");
+    sysfs.add(
+        "hidraw10",
+        "usb1/1-2/1-2:1.0/0003:04D8:F372.000A",
+        "DRIVER=hid-generic\nHID_ID=0003:000004D8:0000F372\nHID_NAME=Microchip Technology Inc. LUXAFOR FLAG\nHID_UNIQ=\n",
+        &[
+            ("manufacturer", "Microchip Technology Inc."),
+            ("product", "LUXAFOR FLAG"),
+            ("serial", "0123"),
+            ("bcdDevice", "0100"),
+        ],
+    );
+    sysfs.add(
+        "hidraw2",
+        "bluetooth/hci0/0005:04D8:F372.0002",
+        "HID_ID=0005:000004D8:0000F372\nHID_NAME=Luxafor Bluetooth\nHID_UNIQ=aa:bb:cc:dd:ee:ff\n",
+        &[],
+    );
+
+    let nodes = nodes(&sysfs.root.join("class/hidraw"), Path::new("/dev")).unwrap();
+    assert_eq!(
+        nodes,
+        vec![
+            Node {
+                path: String::from("/dev/hidraw2"),
+                vendor_id: 0x04D8,
+                product_id: 0xF372,
+                manufacturer: None,
+                product: Some(String::from("Luxafor Bluetooth")),
+                serial_number: Some(String::from("aa:bb:cc:dd:ee:ff")),
+                release_number: 0,
+            },
+            Node {
+                path: String::from("/dev/hidraw10"),
+                vendor_id: 0x04D8,
+                product_id: 0xF372,
+                manufacturer: Some(String::from("Microchip Technology Inc.")),
+                product: Some(String::from("LUXAFOR FLAG")),
+                serial_number: Some(String::from("0123")),
+                release_number: 0x0100,
+            },
+        ]
+    );
+}
+
+#[test]
+fn missing_sysfs_means_no_nodes() {
+    let sysfs = FakeSysfs::new("hidraw-missing");
+    assert_eq!(
+        nodes(&sysfs.root.join("class/nothing"), Path::new("/dev")).unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn frames_and_reports_pass_through() {
+    let (ours, mut theirs) = UnixStream::pair().unwrap();
+    let device = HidrawDevice {
+        file: File::from(std::os::fd::OwnedFd::from(ours)),
+        node: Node {
+            path: String::from("/dev/hidraw0"),
+            vendor_id: 0x04D8,
+            product_id: 0xF372,
+            manufacturer: None,
+            product: Some(String::from("LUXAFOR FLAG")),
+            serial_number: None,
+            release_number: 0,
+        },
+    };
+
+    assert_eq!(device.write(&[0, 0, b'R']).unwrap(), 3);
+    let mut frame = [0u8; 3];
+    theirs.read_exact(&mut frame).unwrap();
+    assert_eq!(frame, [0, 0, b'R']);
+
+    let mut report = [0u8; 8];
+    assert_eq!(device.read_timeout(&mut report, 10).unwrap(), 0);
+    theirs.write_all(&[0x80, 0, 0, 0]).unwrap();
+    assert_eq!(device.read_timeout(&mut report, 10).unwrap(), 4);
+    assert_eq!(report[..4], [0x80, 0, 0, 0]);
+
+    assert_eq!(
+        device.product_string().unwrap().as_deref(),
+        Some("LUXAFOR FLAG")
+    );
+}
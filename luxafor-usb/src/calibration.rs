@@ -0,0 +1,253 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Real-time durations for the device's timing parameters.
+//!
+//! `fade_to_color`'s `fade_time` and the `speed` of `strobe` and `wave` are `u8`s on scales only
+//! the firmware knows.  A `Calibration` maps each of them to and from a `Duration`, so that a
+//! fade can be asked to take two seconds, or a strobe's finishing time worked out in advance.
+//! `Calibration::default()` holds rough figures for a Luxafor Flag; units vary, so for anything
+//! that needs to be accurate, measure a specific device (`luxide calibrate` does this) and load
+//! its profile with `Calibration::from_str`.
+
+use crate::firmware::repeats;
+use std::{fmt, str::FromStr, time::Duration};
+use thiserror::Error;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum CalibrationError {
+    #[error("A calibration curve needs at least two points")]
+    TooFewPoints,
+    #[error("The value {0} appears more than once in a calibration curve")]
+    DuplicateValue(u8),
+    #[error("Invalid duration \"{0}\": expected a number followed by ms, s or m, such as 1.5s")]
+    InvalidDuration(String),
+    #[error("Line {line} of the calibration profile is invalid: {message}")]
+    InvalidLine { line: usize, message: String },
+    #[error("The calibration profile has no {0} curve")]
+    MissingCurve(&'static str),
+}
+
+/// How one timing parameter relates to real time, as a table of measured points.  Values between
+/// the points are interpolated linearly, and values beyond them extrapolated from the nearest two.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Curve {
+    points: Vec<(u8, Duration)>,
+}
+
+impl Curve {
+    /// Builds a curve from `(value, duration)` pairs, in any order.
+    pub fn new<I>(points: I) -> Result<Curve, CalibrationError>
+    where
+        I: IntoIterator<Item = (u8, Duration)>,
+    {
+        let mut points: Vec<(u8, Duration)> = points.into_iter().collect();
+        points.sort_by_key(|&(value, _)| value);
+        if let Some(pair) = points.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(CalibrationError::DuplicateValue(pair[0].0));
+        }
+        if points.len() < 2 {
+            return Err(CalibrationError::TooFewPoints);
+        }
+
+        Ok(Curve { points })
+    }
+
+    /// The points the curve was built from, ordered by value.
+    pub fn points(&self) -> &[(u8, Duration)] {
+        &self.points
+    }
+
+    /// The real-time duration that `value` corresponds to.
+    pub fn duration(&self, value: u8) -> Duration {
+        let segment = self
+            .points
+            .windows(2)
+            .find(|pair| value <= pair[1].0)
+            .unwrap_or(&self.points[self.points.len() - 2..]);
+        let ((v0, d0), (v1, d1)) = (segment[0], segment[1]);
+
+        let t = (f64::from(value) - f64::from(v0)) / (f64::from(v1) - f64::from(v0));
+        let seconds = d0.as_secs_f64() + (d1.as_secs_f64() - d0.as_secs_f64()) * t;
+        Duration::from_secs_f64(seconds.max(0.0))
+    }
+
+    /// The value whose duration comes closest to `duration`.  Durations beyond what the device
+    /// can do give the nearest value it has.
+    pub fn value_for(&self, duration: Duration) -> u8 {
+        (0..=u8::MAX)
+            .min_by_key(|&value| self.duration(value).abs_diff(duration))
+            .unwrap()
+    }
+}
+
+/// The timing curves for one device.
+///
+/// - `fade` is the time a `fade_to_color` takes for each `fade_time`.
+/// - `strobe` is the time one flash (on and back off) of a `strobe` takes for each `speed`.
+/// - `wave` is the time one pass of a `wave` takes for each `speed`.
+///
+/// Displaying a `Calibration` gives the profile format `FromStr` reads: one point per line, as
+/// the curve's name, the value and the duration in milliseconds (`fade 128 2060ms`).  Blank lines
+/// and lines starting with `#` are ignored.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Calibration {
+    pub fade: Curve,
+    pub strobe: Curve,
+    pub wave: Curve,
+}
+
+const CURVE_NAMES: [&str; 3] = ["fade", "strobe", "wave"];
+
+impl Calibration {
+    /// The `fade_time` that makes a fade take about `duration`.
+    pub fn fade_time(&self, duration: Duration) -> u8 {
+        self.fade.value_for(duration)
+    }
+
+    /// The strobe `speed` that makes each flash take about `period`.
+    pub fn strobe_speed(&self, period: Duration) -> u8 {
+        self.strobe.value_for(period)
+    }
+
+    /// The wave `speed` that makes each pass take about `period`.
+    pub fn wave_speed(&self, period: Duration) -> u8 {
+        self.wave.value_for(period)
+    }
+
+    /// How long a strobe with this `speed` and `repeat` runs for.  Like the device, a `repeat` of
+    /// 0 counts as 1.
+    pub fn strobe_duration(&self, speed: u8, repeat: u8) -> Duration {
+        self.strobe.duration(speed) * repeats(repeat)
+    }
+
+    /// How long a wave with this `speed` and `repeat` runs for.  Like the device, a `repeat` of 0
+    /// counts as 1.
+    pub fn wave_duration(&self, speed: u8, repeat: u8) -> Duration {
+        self.wave.duration(speed) * repeats(repeat)
+    }
+
+    fn curve(&self, name: &str) -> &Curve {
+        match name {
+            "fade" => &self.fade,
+            "strobe" => &self.strobe,
+            _ => &self.wave,
+        }
+    }
+}
+
+/// Rough figures for a Luxafor Flag.  Run `luxide calibrate` to measure a particular unit.
+impl Default for Calibration {
+    fn default() -> Self {
+        let curve = |points: &[(u8, u64)]| {
+            Curve::new(
+                points
+                    .iter()
+                    .map(|&(value, ms)| (value, Duration::from_millis(ms))),
+            )
+            .unwrap()
+        };
+
+        Calibration {
+            fade: curve(&[(0, 0), (32, 520), (128, 2060), (255, 4100)]),
+            strobe: curve(&[(0, 45), (31, 530), (128, 2100), (255, 4150)]),
+            wave: curve(&[(0, 300), (31, 1400), (128, 5000), (255, 9800)]),
+        }
+    }
+}
+
+impl fmt::Display for Calibration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for name in CURVE_NAMES.iter() {
+            for (value, duration) in self.curve(name).points() {
+                writeln!(f, "{} {} {}ms", name, value, duration.as_millis())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Calibration {
+    type Err = CalibrationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut points: [Vec<(u8, Duration)>; 3] = Default::default();
+
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |message: String| CalibrationError::InvalidLine {
+                line: index + 1,
+                message,
+            };
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 3 {
+                return Err(invalid(String::from(
+                    "expected a curve name, a value and a duration",
+                )));
+            }
+            let curve = CURVE_NAMES
+                .iter()
+                .position(|&name| name == fields[0])
+                .ok_or_else(|| invalid(format!("unknown curve \"{}\"", fields[0])))?;
+            let value = u8::from_str(fields[1])
+                .map_err(|_| invalid(format!("\"{}\" is not a value from 0-255", fields[1])))?;
+            let duration = parse_duration(fields[2]).map_err(|e| invalid(e.to_string()))?;
+
+            points[curve].push((value, duration));
+        }
+
+        let [fade, strobe, wave] = points;
+        let curve = |name: &'static str, points: Vec<(u8, Duration)>| {
+            if points.is_empty() {
+                Err(CalibrationError::MissingCurve(name))
+            } else {
+                Curve::new(points)
+            }
+        };
+        Ok(Calibration {
+            fade: curve("fade", fade)?,
+            strobe: curve("strobe", strobe)?,
+            wave: curve("wave", wave)?,
+        })
+    }
+}
+
+/// Parses a duration written as a number and a unit: `ms`, `s` or `m`, as in `200ms` or `1.5s`.
+pub fn parse_duration(s: &str) -> Result<Duration, CalibrationError> {
+    let invalid = || CalibrationError::InvalidDuration(String::from(s));
+
+    let trimmed = s.trim();
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .ok_or_else(invalid)?;
+    let (number, unit) = trimmed.split_at(split);
+    let number = f64::from_str(number).map_err(|_| invalid())?;
+    let seconds = match unit.trim() {
+        "ms" => number / 1000.0,
+        "s" => number,
+        "m" => number * 60.0,
+        _ => return Err(invalid()),
+    };
+
+    Duration::try_from_secs_f64(seconds).map_err(|_| invalid())
+}
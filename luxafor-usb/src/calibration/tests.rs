@@ -0,0 +1,157 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::animation::ms;
+use crate::calibration::{parse_duration, Calibration, CalibrationError, Curve};
+use std::{str::FromStr, time::Duration};
+
+#[test]
+fn curves_interpolate_and_extrapolate() {
+    let curve = Curve::new(vec![(200, ms(2000)), (100, ms(1000)), (20, ms(300))]).unwrap();
+
+    assert_eq!(curve.points()[0], (20, ms(300)));
+    assert_eq!(curve.duration(100), ms(1000));
+    assert_eq!(curve.duration(150), ms(1500));
+    assert_eq!(curve.duration(60), ms(650));
+    // Beyond the points, the nearest segment carries on.
+    assert_eq!(curve.duration(0), ms(125));
+    assert_eq!(curve.duration(250), ms(2500));
+
+    // Durations never go negative.
+    let steep = Curve::new(vec![(100, ms(100)), (110, ms(1100))]).unwrap();
+    assert_eq!(steep.duration(0), ms(0));
+}
+
+#[test]
+fn curves_find_the_closest_value() {
+    let curve = Curve::new(vec![(0, ms(0)), (255, ms(2550))]).unwrap();
+
+    assert_eq!(curve.value_for(ms(1000)), 100);
+    assert_eq!(curve.value_for(ms(1004)), 100);
+    assert_eq!(curve.value_for(ms(1006)), 101);
+    assert_eq!(curve.value_for(Duration::from_secs(3600)), 255);
+    assert_eq!(curve.value_for(ms(0)), 0);
+}
+
+#[test]
+fn curves_need_distinct_points() {
+    assert_eq!(
+        Curve::new(vec![(1, ms(10))]),
+        Err(CalibrationError::TooFewPoints)
+    );
+    assert_eq!(
+        Curve::new(vec![(1, ms(10)), (5, ms(20)), (1, ms(15))]),
+        Err(CalibrationError::DuplicateValue(1))
+    );
+}
+
+#[test]
+fn default_calibration_is_consistent() {
+    let calibration = Calibration::default();
+
+    assert_eq!(calibration.fade_time(ms(0)), 0);
+    let fade_time = calibration.fade_time(Duration::from_secs(2));
+    assert!((120..=130).contains(&fade_time), "{}", fade_time);
+    assert_eq!(
+        calibration.strobe_duration(31, 5),
+        calibration.strobe.duration(31) * 5
+    );
+    assert_eq!(
+        calibration.strobe_duration(31, 0),
+        calibration.strobe.duration(31)
+    );
+    assert_eq!(
+        calibration.wave_duration(31, 0),
+        calibration.wave_duration(31, 1)
+    );
+    for value in 0..=u8::MAX {
+        assert!(calibration.wave.duration(value) > ms(0));
+        assert_eq!(
+            calibration.strobe_speed(calibration.strobe.duration(value)),
+            value
+        );
+    }
+}
+
+#[test]
+fn profiles_round_trip() {
+    let calibration = Calibration::default();
+    let profile = calibration.to_string();
+    assert!(
+        profile.starts_with("fade 0 0ms\nfade 32 520ms\n"),
+        "{}",
+        profile
+    );
+    assert_eq!(Calibration::from_str(&profile).unwrap(), calibration);
+
+    let profile = "# measured by hand\n\
+                   fade 0 0ms\nfade 255 3s\n\n\
+                   strobe 10 0.1s\nstrobe 200 2s\n\
+                   wave 10 500ms\nwave 200 10000ms\n";
+    let calibration = Calibration::from_str(profile).unwrap();
+    assert_eq!(calibration.fade.duration(255), ms(3000));
+    assert_eq!(calibration.strobe.points()[0], (10, ms(100)));
+}
+
+#[test]
+fn invalid_profiles_are_rejected() {
+    let invalid = |profile: &str| Calibration::from_str(profile).unwrap_err();
+
+    assert_eq!(
+        invalid("fade 0 0ms\nfade 255 3s\nstrobe 0 10ms\nstrobe 9 1s\n"),
+        CalibrationError::MissingCurve("wave")
+    );
+    assert!(matches!(
+        invalid("fade 0 0ms\nflash 1 1s\n"),
+        CalibrationError::InvalidLine { line: 2, .. }
+    ));
+    assert!(matches!(
+        invalid("fade 256 1s"),
+        CalibrationError::InvalidLine { line: 1, .. }
+    ));
+    assert!(matches!(
+        invalid("fade 1 1"),
+        CalibrationError::InvalidLine { line: 1, .. }
+    ));
+    assert!(matches!(
+        invalid("fade 1 1s extra"),
+        CalibrationError::InvalidLine { line: 1, .. }
+    ));
+}
+
+#[test]
+fn durations_need_a_unit() {
+    assert_eq!(parse_duration("200ms"), Ok(ms(200)));
+    assert_eq!(parse_duration("1.5s"), Ok(ms(1500)));
+    assert_eq!(parse_duration(" 2 s "), Ok(ms(2000)));
+    assert_eq!(parse_duration("0.5m"), Ok(ms(30_000)));
+    for invalid in &[
+        "",
+        "200",
+        "s",
+        "1.5.2s",
+        "-1s",
+        "1h",
+        "99999999999999999999999m",
+    ] {
+        assert_eq!(
+            parse_duration(invalid),
+            Err(CalibrationError::InvalidDuration(invalid.to_string())),
+            "{}",
+            invalid
+        );
+    }
+}
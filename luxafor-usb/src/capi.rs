@@ -0,0 +1,628 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! A C ABI, for programs that can't link against Rust directly.
+//!
+//! Devices are handed out as opaque `LuxaforDevice` pointers, which must be released with
+//! `luxafor_close`.  Every function that can fail returns a `LuxaforStatus`; when it isn't
+//! `LUXAFOR_STATUS_OK`, `luxafor_last_error_message` describes what went wrong.  Lights are passed
+//! as a bitmask of the `LUXAFOR_LIGHT_*` constants, and simple colors, wave types and patterns as
+//! the codes the device itself uses, which have `LUXAFOR_*` constants of their own.
+//!
+//! This module is only available with the `capi` feature; the luxafor-capi crate builds it into the
+//! `luxafor` shared and static libraries.  `include/luxafor.h` is generated from it by cbindgen;
+//! regenerate it after changing anything here with
+//! `cbindgen --config cbindgen.toml --output include/luxafor.h`.
+
+use crate::device::{
+    DeviceDescriptor, Lights, Luxafor, PatternType, RgbColor, SimpleColor, WaveType,
+};
+use crate::transport::MockTransport;
+use crate::LuxaforError;
+use enumflags2::BitFlags;
+use std::{
+    cell::RefCell,
+    convert::TryFrom,
+    ffi::{CStr, CString},
+    os::raw::c_char,
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+#[cfg(test)]
+mod tests;
+
+pub const LUXAFOR_LIGHT_FLAG_BOTTOM: u8 = 0b000001;
+pub const LUXAFOR_LIGHT_FLAG_MIDDLE: u8 = 0b000010;
+pub const LUXAFOR_LIGHT_FLAG_TOP: u8 = 0b000100;
+pub const LUXAFOR_LIGHT_BACK_BOTTOM: u8 = 0b001000;
+pub const LUXAFOR_LIGHT_BACK_MIDDLE: u8 = 0b010000;
+pub const LUXAFOR_LIGHT_BACK_TOP: u8 = 0b100000;
+pub const LUXAFOR_LIGHTS_FLAG: u8 = 0b000111;
+pub const LUXAFOR_LIGHTS_BACK: u8 = 0b111000;
+pub const LUXAFOR_LIGHTS_ALL: u8 = 0b111111;
+
+pub const LUXAFOR_SIMPLE_COLOR_RED: u8 = b'R';
+pub const LUXAFOR_SIMPLE_COLOR_GREEN: u8 = b'G';
+pub const LUXAFOR_SIMPLE_COLOR_BLUE: u8 = b'B';
+pub const LUXAFOR_SIMPLE_COLOR_CYAN: u8 = b'C';
+pub const LUXAFOR_SIMPLE_COLOR_MAGENTA: u8 = b'M';
+pub const LUXAFOR_SIMPLE_COLOR_YELLOW: u8 = b'Y';
+pub const LUXAFOR_SIMPLE_COLOR_WHITE: u8 = b'W';
+pub const LUXAFOR_SIMPLE_COLOR_OFF: u8 = b'O';
+
+pub const LUXAFOR_WAVE_SHORT: u8 = 1;
+pub const LUXAFOR_WAVE_LONG: u8 = 2;
+pub const LUXAFOR_WAVE_OVERLAPPING_SHORT: u8 = 3;
+pub const LUXAFOR_WAVE_OVERLAPPING_LONG: u8 = 4;
+
+pub const LUXAFOR_PATTERN_LUXAFOR: u8 = 1;
+pub const LUXAFOR_PATTERN_RANDOM1: u8 = 2;
+pub const LUXAFOR_PATTERN_RANDOM2: u8 = 3;
+pub const LUXAFOR_PATTERN_RANDOM3: u8 = 4;
+pub const LUXAFOR_PATTERN_POLICE: u8 = 5;
+pub const LUXAFOR_PATTERN_RANDOM4: u8 = 6;
+pub const LUXAFOR_PATTERN_RANDOM5: u8 = 7;
+pub const LUXAFOR_PATTERN_RAINBOW_WAVE: u8 = 8;
+
+/// What a C API call did.  Everything but `Ok` is a failure, with a message waiting in
+/// `luxafor_last_error_message`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LuxaforStatus {
+    Ok = 0,
+    /// A pointer argument was `NULL`.
+    NullPointer,
+    /// A string argument wasn't valid UTF-8.
+    InvalidString,
+    /// A lights mask, simple color, wave type or pattern wasn't one the device knows.
+    InvalidArgument,
+    /// `luxafor_parse_color` couldn't make sense of its input.
+    InvalidColor,
+    DeviceNotFound,
+    PermissionDenied,
+    UnexpectedProductString,
+    Open,
+    Write,
+    ShortWrite,
+    Read,
+    Disconnected,
+    Unsupported,
+    /// Something went wrong that the C API doesn't distinguish, including a bug in this library.
+    Other,
+}
+
+impl From<&LuxaforError> for LuxaforStatus {
+    fn from(e: &LuxaforError) -> Self {
+        match e {
+            LuxaforError::DeviceNotFound => LuxaforStatus::DeviceNotFound,
+            LuxaforError::PermissionDenied(_) => LuxaforStatus::PermissionDenied,
+            LuxaforError::UnexpectedProductString(_) => LuxaforStatus::UnexpectedProductString,
+            LuxaforError::Open(_) => LuxaforStatus::Open,
+            LuxaforError::Write(_) => LuxaforStatus::Write,
+            LuxaforError::ShortWrite { .. } => LuxaforStatus::ShortWrite,
+            LuxaforError::Read(_) => LuxaforStatus::Read,
+            LuxaforError::Disconnected => LuxaforStatus::Disconnected,
+            LuxaforError::Unsupported { .. } => LuxaforStatus::Unsupported,
+            _ => LuxaforStatus::Other,
+        }
+    }
+}
+
+/// An RGB color, as used by `luxafor_parse_color` and the operations that take one.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LuxaforColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl From<LuxaforColor> for RgbColor {
+    fn from(color: LuxaforColor) -> Self {
+        RgbColor(color.r, color.g, color.b)
+    }
+}
+
+impl From<RgbColor> for LuxaforColor {
+    fn from(RgbColor(r, g, b): RgbColor) -> Self {
+        LuxaforColor { r, g, b }
+    }
+}
+
+/// An open device.
+pub struct LuxaforDevice {
+    luxafor: Luxafor,
+    // Kept for `luxafor_mock_frame` when the device was opened with `luxafor_open_mock`.
+    mock: Option<MockTransport>,
+}
+
+/// The devices found by `luxafor_enumerate`.
+pub struct LuxaforDeviceList {
+    devices: Vec<ListedDevice>,
+}
+
+struct ListedDevice {
+    path: CString,
+    serial_number: Option<CString>,
+    model: CString,
+}
+
+impl From<DeviceDescriptor> for ListedDevice {
+    fn from(descriptor: DeviceDescriptor) -> Self {
+        ListedDevice {
+            path: c_string(descriptor.path),
+            serial_number: descriptor.serial_number.map(c_string),
+            model: c_string(descriptor.model.name.to_string()),
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Converts a string for C, dropping any NULs it contains rather than failing.
+fn c_string(s: String) -> CString {
+    CString::new(s.replace('\0', "")).unwrap()
+}
+
+fn fail(status: LuxaforStatus, message: impl ToString) -> LuxaforStatus {
+    let message = c_string(message.to_string());
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+    status
+}
+
+/// Runs the body of an API function, turning its error (or a panic, which mustn't unwind into C)
+/// into a status.
+fn call(f: impl FnOnce() -> Result<(), LuxaforStatus>) -> LuxaforStatus {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => LuxaforStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => fail(LuxaforStatus::Other, "luxafor-usb panicked"),
+    }
+}
+
+fn device_error(e: LuxaforError) -> LuxaforStatus {
+    fail(LuxaforStatus::from(&e), e)
+}
+
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> Result<&'a str, LuxaforStatus> {
+    if s.is_null() {
+        return Err(fail(
+            LuxaforStatus::NullPointer,
+            format!("{} is NULL", name),
+        ));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|e| fail(LuxaforStatus::InvalidString, format!("{}: {}", name, e)))
+}
+
+unsafe fn device_arg<'a>(device: *const LuxaforDevice) -> Result<&'a LuxaforDevice, LuxaforStatus> {
+    device
+        .as_ref()
+        .ok_or_else(|| fail(LuxaforStatus::NullPointer, "device is NULL"))
+}
+
+fn lights_arg(lights: u8) -> Result<BitFlags<Lights>, LuxaforStatus> {
+    BitFlags::from_bits(lights).map_err(|_| {
+        fail(
+            LuxaforStatus::InvalidArgument,
+            format!("{:#04x} isn't a valid set of lights", lights),
+        )
+    })
+}
+
+fn code_arg<T: TryFrom<u8>>(code: u8, what: &str) -> Result<T, LuxaforStatus> {
+    T::try_from(code).map_err(|_| {
+        fail(
+            LuxaforStatus::InvalidArgument,
+            format!("{} isn't a valid {}", code, what),
+        )
+    })
+}
+
+unsafe fn open_with(
+    out: *mut *mut LuxaforDevice,
+    open: impl FnOnce() -> Result<LuxaforDevice, LuxaforStatus>,
+) -> LuxaforStatus {
+    if out.is_null() {
+        return fail(LuxaforStatus::NullPointer, "out is NULL");
+    }
+    *out = ptr::null_mut();
+    call(|| {
+        *out = Box::into_raw(Box::new(open()?));
+        Ok(())
+    })
+}
+
+fn opened(result: Result<Luxafor, LuxaforError>) -> Result<LuxaforDevice, LuxaforStatus> {
+    result
+        .map(|luxafor| LuxaforDevice {
+            luxafor,
+            mock: None,
+        })
+        .map_err(device_error)
+}
+
+/// Describes the most recent failure on the calling thread, or returns `NULL` if nothing has
+/// failed yet.  The string belongs to the library, and stays valid until the next failure on
+/// the same thread.
+#[no_mangle]
+pub extern "C" fn luxafor_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Lists the connected devices.  On success, `*out` points to a list that must be released with
+/// `luxafor_device_list_free`.
+///
+/// # Safety
+///
+/// `out` must be `NULL` or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_enumerate(out: *mut *mut LuxaforDeviceList) -> LuxaforStatus {
+    if out.is_null() {
+        return fail(LuxaforStatus::NullPointer, "out is NULL");
+    }
+    *out = ptr::null_mut();
+    call(|| {
+        let devices = Luxafor::enumerate().map_err(device_error)?;
+        *out = Box::into_raw(Box::new(LuxaforDeviceList {
+            devices: devices.into_iter().map(ListedDevice::from).collect(),
+        }));
+        Ok(())
+    })
+}
+
+/// The number of devices in `list`.
+///
+/// # Safety
+///
+/// `list` must be `NULL` or a list returned by `luxafor_enumerate`.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_device_list_len(list: *const LuxaforDeviceList) -> usize {
+    list.as_ref().map_or(0, |list| list.devices.len())
+}
+
+/// The path of the `index`th device in `list`, to pass to `luxafor_open_path`, or `NULL` if
+/// `index` is out of range.  The string belongs to the list.
+///
+/// # Safety
+///
+/// `list` must be `NULL` or a list returned by `luxafor_enumerate`.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_device_list_path(
+    list: *const LuxaforDeviceList,
+    index: usize,
+) -> *const c_char {
+    listed(list, index).map_or(ptr::null(), |device| device.path.as_ptr())
+}
+
+/// The serial number of the `index`th device in `list`, or `NULL` if it doesn't report one or
+/// `index` is out of range.  The string belongs to the list.
+///
+/// # Safety
+///
+/// `list` must be `NULL` or a list returned by `luxafor_enumerate`.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_device_list_serial_number(
+    list: *const LuxaforDeviceList,
+    index: usize,
+) -> *const c_char {
+    listed(list, index)
+        .and_then(|device| device.serial_number.as_ref())
+        .map_or(ptr::null(), |serial_number| serial_number.as_ptr())
+}
+
+/// The model name of the `index`th device in `list` (e.g. "Luxafor Flag"), or `NULL` if `index`
+/// is out of range.  The string belongs to the list.
+///
+/// # Safety
+///
+/// `list` must be `NULL` or a list returned by `luxafor_enumerate`.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_device_list_model(
+    list: *const LuxaforDeviceList,
+    index: usize,
+) -> *const c_char {
+    listed(list, index).map_or(ptr::null(), |device| device.model.as_ptr())
+}
+
+/// Releases a list returned by `luxafor_enumerate`.  Does nothing if `list` is `NULL`.
+///
+/// # Safety
+///
+/// `list` must be `NULL` or a list returned by `luxafor_enumerate` that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_device_list_free(list: *mut LuxaforDeviceList) {
+    if !list.is_null() {
+        drop(Box::from_raw(list));
+    }
+}
+
+unsafe fn listed<'a>(list: *const LuxaforDeviceList, index: usize) -> Option<&'a ListedDevice> {
+    list.as_ref().and_then(|list| list.devices.get(index))
+}
+
+/// Opens the first device found.  On success, `*out` points to the device, which must be
+/// released with `luxafor_close`.
+///
+/// # Safety
+///
+/// `out` must be `NULL` or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_open(out: *mut *mut LuxaforDevice) -> LuxaforStatus {
+    open_with(out, || opened(Luxafor::new()))
+}
+
+/// Opens the device at `path`, as returned by `luxafor_device_list_path`.
+///
+/// # Safety
+///
+/// `path` must be `NULL` or a NUL-terminated string, and `out` `NULL` or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_open_path(
+    path: *const c_char,
+    out: *mut *mut LuxaforDevice,
+) -> LuxaforStatus {
+    open_with(out, || opened(Luxafor::open_path(str_arg(path, "path")?)))
+}
+
+/// Opens the device with the given serial number.
+///
+/// # Safety
+///
+/// `serial_number` must be `NULL` or a NUL-terminated string, and `out` `NULL` or valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_open_serial(
+    serial_number: *const c_char,
+    out: *mut *mut LuxaforDevice,
+) -> LuxaforStatus {
+    open_with(out, || {
+        opened(Luxafor::open_serial(str_arg(
+            serial_number,
+            "serial_number",
+        )?))
+    })
+}
+
+/// Opens a pretend Luxafor Flag that records the frames written to it instead of lighting
+/// anything, for testing code that uses this library.  See `luxafor_mock_frame`.
+///
+/// # Safety
+///
+/// `out` must be `NULL` or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_open_mock(out: *mut *mut LuxaforDevice) -> LuxaforStatus {
+    open_with(out, || {
+        let mock = MockTransport::new();
+        Ok(LuxaforDevice {
+            luxafor: Luxafor::with_transport(Box::new(mock.clone())),
+            mock: Some(mock),
+        })
+    })
+}
+
+/// Closes a device.  Does nothing if `device` is `NULL`.
+///
+/// # Safety
+///
+/// `device` must be `NULL` or a device returned by one of the `luxafor_open` functions that
+/// hasn't been closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_close(device: *mut LuxaforDevice) {
+    if !device.is_null() {
+        drop(Box::from_raw(device));
+    }
+}
+
+/// The number of frames written so far to a device opened with `luxafor_open_mock`; always 0
+/// for a real device.
+///
+/// # Safety
+///
+/// `device` must be `NULL` or an open device.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_mock_frame_count(device: *const LuxaforDevice) -> usize {
+    device
+        .as_ref()
+        .and_then(|device| device.mock.as_ref())
+        .map_or(0, |mock| mock.frames().len())
+}
+
+/// Copies as much of the `index`th frame written to a device opened with `luxafor_open_mock` as
+/// fits into `buf`, and returns the frame's full length, or 0 if there's no such frame.
+///
+/// # Safety
+///
+/// `device` must be `NULL` or an open device, and `buf` valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_mock_frame(
+    device: *const LuxaforDevice,
+    index: usize,
+    buf: *mut u8,
+    len: usize,
+) -> usize {
+    let frame = device
+        .as_ref()
+        .and_then(|device| device.mock.as_ref())
+        .and_then(|mock| mock.frames().into_iter().nth(index));
+    match frame {
+        Some(frame) => {
+            if !buf.is_null() {
+                ptr::copy_nonoverlapping(frame.as_ptr(), buf, frame.len().min(len));
+            }
+            frame.len()
+        }
+        None => 0,
+    }
+}
+
+/// Sets every light to one of the `LUXAFOR_SIMPLE_COLOR_*` colors.
+///
+/// # Safety
+///
+/// `device` must be `NULL` or an open device.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_set_simple_color(
+    device: *const LuxaforDevice,
+    color: u8,
+) -> LuxaforStatus {
+    call(|| {
+        let color: SimpleColor = code_arg(color, "simple color")?;
+        device_arg(device)?
+            .luxafor
+            .set_simple_color(color)
+            .map_err(device_error)
+    })
+}
+
+/// Sets `lights`, a mask of `LUXAFOR_LIGHT_*` values, to `color`.
+///
+/// # Safety
+///
+/// `device` must be `NULL` or an open device.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_set_rgb_color(
+    device: *const LuxaforDevice,
+    color: LuxaforColor,
+    lights: u8,
+) -> LuxaforStatus {
+    call(|| {
+        let lights = lights_arg(lights)?;
+        device_arg(device)?
+            .luxafor
+            .set_rgb_color(color.into(), lights)
+            .map_err(device_error)
+    })
+}
+
+/// Fades `lights` to `color`.  `fade_time` runs from 0 (instant) to 255 (very slow).
+///
+/// # Safety
+///
+/// `device` must be `NULL` or an open device.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_fade_to_color(
+    device: *const LuxaforDevice,
+    color: LuxaforColor,
+    lights: u8,
+    fade_time: u8,
+) -> LuxaforStatus {
+    call(|| {
+        let lights = lights_arg(lights)?;
+        device_arg(device)?
+            .luxafor
+            .fade_to_color(color.into(), lights, fade_time)
+            .map_err(device_error)
+    })
+}
+
+/// Flashes `lights` in `color` `repeat` times.  `speed` runs from 0 (very fast) to 255 (very
+/// slow).
+///
+/// # Safety
+///
+/// `device` must be `NULL` or an open device.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_strobe(
+    device: *const LuxaforDevice,
+    color: LuxaforColor,
+    lights: u8,
+    speed: u8,
+    repeat: u8,
+) -> LuxaforStatus {
+    call(|| {
+        let lights = lights_arg(lights)?;
+        device_arg(device)?
+            .luxafor
+            .strobe(color.into(), lights, speed, repeat)
+            .map_err(device_error)
+    })
+}
+
+/// Plays one of the `LUXAFOR_WAVE_*` waves in `color` `repeat` times.
+///
+/// # Safety
+///
+/// `device` must be `NULL` or an open device.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_wave(
+    device: *const LuxaforDevice,
+    color: LuxaforColor,
+    wave_type: u8,
+    speed: u8,
+    repeat: u8,
+) -> LuxaforStatus {
+    call(|| {
+        let wave_type: WaveType = code_arg(wave_type, "wave type")?;
+        device_arg(device)?
+            .luxafor
+            .wave(color.into(), wave_type, speed, repeat)
+            .map_err(device_error)
+    })
+}
+
+/// Plays one of the `LUXAFOR_PATTERN_*` patterns `repeat` times.
+///
+/// # Safety
+///
+/// `device` must be `NULL` or an open device.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_pattern(
+    device: *const LuxaforDevice,
+    pattern_type: u8,
+    repeat: u8,
+) -> LuxaforStatus {
+    call(|| {
+        let pattern_type: PatternType = code_arg(pattern_type, "pattern")?;
+        device_arg(device)?
+            .luxafor
+            .pattern(pattern_type, repeat)
+            .map_err(device_error)
+    })
+}
+
+/// Parses a CSS color (such as "tomato", "#ff8000" or "hsl(30 100% 50%)"), "R,G,B" in decimal or
+/// "0xRRGGBB".  Names always mean their CSS colors, so "green" is #008000; luxide instead sends the
+/// eight Luxafor color names as simple colors, where green is #00ff00.
+///
+/// # Safety
+///
+/// `s` must be `NULL` or a NUL-terminated string, and `out` `NULL` or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn luxafor_parse_color(
+    s: *const c_char,
+    out: *mut LuxaforColor,
+) -> LuxaforStatus {
+    call(|| {
+        let s = str_arg(s, "s")?;
+        if out.is_null() {
+            return Err(fail(LuxaforStatus::NullPointer, "out is NULL"));
+        }
+        let color: RgbColor = s
+            .parse()
+            .map_err(|e| fail(LuxaforStatus::InvalidColor, e))?;
+        *out = color.into();
+        Ok(())
+    })
+}
@@ -0,0 +1,132 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::capi::*;
+use std::{ffi::CStr, ptr};
+
+fn last_error() -> String {
+    let message = luxafor_last_error_message();
+    assert!(!message.is_null());
+    unsafe { CStr::from_ptr(message) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[test]
+fn operations_reach_the_device() {
+    unsafe {
+        let mut device = ptr::null_mut();
+        assert_eq!(luxafor_open_mock(&mut device), LuxaforStatus::Ok);
+        assert!(!device.is_null());
+
+        let red = LuxaforColor { r: 255, g: 0, b: 0 };
+        assert_eq!(
+            luxafor_set_simple_color(device, LUXAFOR_SIMPLE_COLOR_GREEN),
+            LuxaforStatus::Ok
+        );
+        assert_eq!(
+            luxafor_set_rgb_color(device, red, LUXAFOR_LIGHTS_BACK),
+            LuxaforStatus::Ok
+        );
+        assert_eq!(luxafor_mock_frame_count(device), 2);
+
+        let mut frame = [0u8; 8];
+        assert_eq!(luxafor_mock_frame(device, 0, frame.as_mut_ptr(), 8), 3);
+        assert_eq!(&frame[..3], &[0, 0, b'G']);
+        assert_eq!(luxafor_mock_frame(device, 1, frame.as_mut_ptr(), 8), 6);
+        assert_eq!(&frame[..6], &[0, 1, b'B', 255, 0, 0]);
+        assert_eq!(luxafor_mock_frame(device, 2, frame.as_mut_ptr(), 8), 0);
+
+        luxafor_close(device);
+    }
+}
+
+#[test]
+fn invalid_arguments_are_reported() {
+    unsafe {
+        let mut device = ptr::null_mut();
+        assert_eq!(luxafor_open_mock(&mut device), LuxaforStatus::Ok);
+
+        assert_eq!(
+            luxafor_set_simple_color(device, b'X'),
+            LuxaforStatus::InvalidArgument
+        );
+        assert_eq!(last_error(), "88 isn't a valid simple color");
+        assert_eq!(
+            luxafor_set_rgb_color(device, LuxaforColor::default(), 0x40),
+            LuxaforStatus::InvalidArgument
+        );
+        assert_eq!(last_error(), "0x40 isn't a valid set of lights");
+        assert_eq!(
+            luxafor_pattern(ptr::null(), LUXAFOR_PATTERN_POLICE, 1),
+            LuxaforStatus::NullPointer
+        );
+        assert_eq!(luxafor_mock_frame_count(device), 0);
+
+        luxafor_close(device);
+    }
+}
+
+#[test]
+fn colors_are_parsed() {
+    unsafe {
+        let mut color = LuxaforColor::default();
+        assert_eq!(
+            luxafor_parse_color(b"tomato\0".as_ptr().cast(), &mut color),
+            LuxaforStatus::Ok
+        );
+        assert_eq!(
+            color,
+            LuxaforColor {
+                r: 255,
+                g: 99,
+                b: 71
+            }
+        );
+
+        assert_eq!(
+            luxafor_parse_color(b"#ff80fg\0".as_ptr().cast(), &mut color),
+            LuxaforStatus::InvalidColor
+        );
+        assert_eq!(
+            last_error(),
+            "Invalid color \"#ff80fg\": 'g' isn't a hex digit at character 7"
+        );
+        assert_eq!(
+            luxafor_parse_color(b"\xff\0".as_ptr().cast(), &mut color),
+            LuxaforStatus::InvalidString
+        );
+    }
+}
+
+#[test]
+fn device_errors_map_to_statuses() {
+    assert_eq!(
+        LuxaforStatus::from(&LuxaforError::DeviceNotFound),
+        LuxaforStatus::DeviceNotFound
+    );
+    assert_eq!(
+        LuxaforStatus::from(&LuxaforError::Unsupported {
+            model: "Luxafor Mute",
+            command: String::from("Strobing"),
+        }),
+        LuxaforStatus::Unsupported
+    );
+    assert_eq!(
+        LuxaforStatus::from(&LuxaforError::Recording(String::from("oops"))),
+        LuxaforStatus::Other
+    );
+}
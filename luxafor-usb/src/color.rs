@@ -0,0 +1,252 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Color arithmetic for `RgbColor`: conversions to and from other color spaces, blending, and
+//! gamma correction, for effects that work out their colors on the host.
+
+use crate::device::RgbColor;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Mul};
+
+pub(crate) mod css;
+#[cfg(test)]
+mod tests;
+
+/// A color as hue (in degrees, from 0 up to 360), saturation and value (both from 0 to 1).
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Hsv {
+    pub hue: f32,
+    pub saturation: f32,
+    pub value: f32,
+}
+
+/// A color as hue (in degrees, from 0 up to 360), saturation and lightness (both from 0 to 1).
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Hsl {
+    pub hue: f32,
+    pub saturation: f32,
+    pub lightness: f32,
+}
+
+/// Maps a perceived brightness to the PWM level that produces it, for a gamma of 2.2.  The Flag's
+/// LEDs are visibly on even at a level of 1, so every nonzero level stays nonzero; otherwise
+/// fades snap to off well before they're meant to.
+const GAMMA: [u8; 256] = [
+    0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2,
+    3, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 6, 6, 6, 6, 7, 7, 7, 8, 8, 8, 9, 9, 9, 10, 10, 11, 11,
+    11, 12, 12, 13, 13, 13, 14, 14, 15, 15, 16, 16, 17, 17, 18, 18, 19, 19, 20, 20, 21, 22, 22, 23,
+    23, 24, 25, 25, 26, 26, 27, 28, 28, 29, 30, 30, 31, 32, 33, 33, 34, 35, 35, 36, 37, 38, 39, 39,
+    40, 41, 42, 43, 43, 44, 45, 46, 47, 48, 49, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61,
+    62, 63, 64, 65, 66, 67, 68, 69, 70, 71, 73, 74, 75, 76, 77, 78, 79, 81, 82, 83, 84, 85, 87, 88,
+    89, 90, 91, 93, 94, 95, 97, 98, 99, 100, 102, 103, 105, 106, 107, 109, 110, 111, 113, 114, 116,
+    117, 119, 120, 121, 123, 124, 126, 127, 129, 130, 132, 133, 135, 137, 138, 140, 141, 143, 145,
+    146, 148, 149, 151, 153, 154, 156, 158, 159, 161, 163, 165, 166, 168, 170, 172, 173, 175, 177,
+    179, 181, 182, 184, 186, 188, 190, 192, 194, 196, 197, 199, 201, 203, 205, 207, 209, 211, 213,
+    215, 217, 219, 221, 223, 225, 227, 229, 231, 234, 236, 238, 240, 242, 244, 246, 248, 251, 253,
+    255,
+];
+
+impl RgbColor {
+    pub fn from_hsv(hsv: Hsv) -> RgbColor {
+        let chroma = hsv.value.clamp(0.0, 1.0) * hsv.saturation.clamp(0.0, 1.0);
+        from_hue_and_chroma(hsv.hue, chroma, hsv.value.clamp(0.0, 1.0) - chroma)
+    }
+
+    pub fn to_hsv(self) -> Hsv {
+        let (hue, max, min) = self.hue_max_min();
+        Hsv {
+            hue,
+            saturation: if max == 0.0 { 0.0 } else { (max - min) / max },
+            value: max,
+        }
+    }
+
+    pub fn from_hsl(hsl: Hsl) -> RgbColor {
+        let lightness = hsl.lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * hsl.saturation.clamp(0.0, 1.0);
+        from_hue_and_chroma(hsl.hue, chroma, lightness - chroma / 2.0)
+    }
+
+    pub fn to_hsl(self) -> Hsl {
+        let (hue, max, min) = self.hue_max_min();
+        let lightness = (max + min) / 2.0;
+        Hsl {
+            hue,
+            saturation: if max == min {
+                0.0
+            } else {
+                (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+            },
+            lightness,
+        }
+    }
+
+    /// Approximates the color of a black body at the given temperature, from 1000K (deep orange)
+    /// through about 6600K (white) to 40000K (pale blue).  Temperatures outside that range are
+    /// clamped to it.
+    ///
+    /// This uses Tanner Helland's curve fit to the CIE 1964 data, which is plenty for picking a
+    /// warm or cool white.
+    pub fn from_kelvin(kelvin: u32) -> RgbColor {
+        let temp = kelvin.clamp(1000, 40000) as f64 / 100.0;
+
+        let red = if temp <= 66.0 {
+            255.0
+        } else {
+            329.698_727_446 * (temp - 60.0).powf(-0.133_204_759_2)
+        };
+        let green = if temp <= 66.0 {
+            99.470_802_586_1 * temp.ln() - 161.119_568_166_1
+        } else {
+            288.122_169_528_3 * (temp - 60.0).powf(-0.075_514_849_2)
+        };
+        let blue = if temp >= 66.0 {
+            255.0
+        } else if temp <= 19.0 {
+            0.0
+        } else {
+            138.517_731_223_1 * (temp - 10.0).ln() - 305.044_792_730_7
+        };
+
+        let channel = |c: f64| c.round().clamp(0.0, 255.0) as u8;
+        RgbColor(channel(red), channel(green), channel(blue))
+    }
+
+    /// Interpolates linearly between this color (when `t` is 0) and `other` (when `t` is 1).
+    pub fn lerp(self, other: RgbColor, t: f32) -> RgbColor {
+        let t = t.clamp(0.0, 1.0);
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        RgbColor(
+            channel(self.0, other.0),
+            channel(self.1, other.1),
+            channel(self.2, other.2),
+        )
+    }
+
+    /// Dims (or, above 1, brightens) the color, keeping its hue.  Channels saturate at 255.
+    pub fn scale(self, brightness: f32) -> RgbColor {
+        let brightness = brightness.max(0.0);
+        let channel = |c: u8| (c as f32 * brightness).round().min(255.0) as u8;
+        RgbColor(channel(self.0), channel(self.1), channel(self.2))
+    }
+
+    /// Maps each channel through the gamma table, so that evenly spaced colors (as from `lerp`
+    /// or `scale`) look evenly spaced on the LEDs.  Apply this last, just before sending.
+    pub fn gamma_correct(self) -> RgbColor {
+        RgbColor(
+            GAMMA[self.0 as usize],
+            GAMMA[self.1 as usize],
+            GAMMA[self.2 as usize],
+        )
+    }
+
+    /// Returns the hue in degrees and the largest and smallest channels, scaled to 0–1.
+    fn hue_max_min(self) -> (f32, f32, f32) {
+        let (r, g, b) = (
+            self.0 as f32 / 255.0,
+            self.1 as f32 / 255.0,
+            self.2 as f32 / 255.0,
+        );
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+
+        let hue = if chroma == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / chroma + 2.0)
+        } else {
+            60.0 * ((r - g) / chroma + 4.0)
+        };
+
+        (hue, max, min)
+    }
+}
+
+/// The part of the HSV and HSL conversions they have in common: `m` is added to every channel
+/// to get the lightness right.
+fn from_hue_and_chroma(hue: f32, chroma: f32, m: f32) -> RgbColor {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    let channel = |c: f32| ((c + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+    RgbColor(channel(r), channel(g), channel(b))
+}
+
+impl From<Hsv> for RgbColor {
+    fn from(hsv: Hsv) -> Self {
+        RgbColor::from_hsv(hsv)
+    }
+}
+
+impl From<RgbColor> for Hsv {
+    fn from(color: RgbColor) -> Self {
+        color.to_hsv()
+    }
+}
+
+impl From<Hsl> for RgbColor {
+    fn from(hsl: Hsl) -> Self {
+        RgbColor::from_hsl(hsl)
+    }
+}
+
+impl From<RgbColor> for Hsl {
+    fn from(color: RgbColor) -> Self {
+        color.to_hsl()
+    }
+}
+
+/// Additive blending, as when two lights shine on the same spot.  Channels saturate at 255.
+impl Add for RgbColor {
+    type Output = RgbColor;
+
+    fn add(self, other: RgbColor) -> RgbColor {
+        RgbColor(
+            self.0.saturating_add(other.0),
+            self.1.saturating_add(other.1),
+            self.2.saturating_add(other.2),
+        )
+    }
+}
+
+/// Multiplicative blending, as when light passes through a filter: white leaves a color
+/// unchanged, and black turns anything black.
+impl Mul for RgbColor {
+    type Output = RgbColor;
+
+    fn mul(self, other: RgbColor) -> RgbColor {
+        let channel = |a: u8, b: u8| ((a as u16 * b as u16 + 127) / 255) as u8;
+        RgbColor(
+            channel(self.0, other.0),
+            channel(self.1, other.1),
+            channel(self.2, other.2),
+        )
+    }
+}
@@ -0,0 +1,600 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Parsing colors written the way CSS Color Module Level 4 writes them: named colors, hex colors,
+//! and the `rgb()`, `hsl()` and `hwb()` functions.  The LEDs can't be partly transparent, so an
+//! alpha value dims the color instead.
+//!
+//! Two notations that aren't CSS are accepted as well, since they're what luxide has always
+//! taken: `R,G,B` in decimal, and `0xRRGGBB`.
+
+use crate::color::Hsl;
+use crate::device::{ColorErrorKind, RgbColor, RgbColorParseError};
+use log::{debug, trace};
+
+#[cfg(test)]
+mod tests;
+
+/// Every CSS named color, in alphabetical order so it can be binary searched.
+pub(crate) const NAMED_COLORS: [(&str, RgbColor); 148] = [
+    ("aliceblue", RgbColor(240, 248, 255)),
+    ("antiquewhite", RgbColor(250, 235, 215)),
+    ("aqua", RgbColor(0, 255, 255)),
+    ("aquamarine", RgbColor(127, 255, 212)),
+    ("azure", RgbColor(240, 255, 255)),
+    ("beige", RgbColor(245, 245, 220)),
+    ("bisque", RgbColor(255, 228, 196)),
+    ("black", RgbColor(0, 0, 0)),
+    ("blanchedalmond", RgbColor(255, 235, 205)),
+    ("blue", RgbColor(0, 0, 255)),
+    ("blueviolet", RgbColor(138, 43, 226)),
+    ("brown", RgbColor(165, 42, 42)),
+    ("burlywood", RgbColor(222, 184, 135)),
+    ("cadetblue", RgbColor(95, 158, 160)),
+    ("chartreuse", RgbColor(127, 255, 0)),
+    ("chocolate", RgbColor(210, 105, 30)),
+    ("coral", RgbColor(255, 127, 80)),
+    ("cornflowerblue", RgbColor(100, 149, 237)),
+    ("cornsilk", RgbColor(255, 248, 220)),
+    ("crimson", RgbColor(220, 20, 60)),
+    ("cyan", RgbColor(0, 255, 255)),
+    ("darkblue", RgbColor(0, 0, 139)),
+    ("darkcyan", RgbColor(0, 139, 139)),
+    ("darkgoldenrod", RgbColor(184, 134, 11)),
+    ("darkgray", RgbColor(169, 169, 169)),
+    ("darkgreen", RgbColor(0, 100, 0)),
+    ("darkgrey", RgbColor(169, 169, 169)),
+    ("darkkhaki", RgbColor(189, 183, 107)),
+    ("darkmagenta", RgbColor(139, 0, 139)),
+    ("darkolivegreen", RgbColor(85, 107, 47)),
+    ("darkorange", RgbColor(255, 140, 0)),
+    ("darkorchid", RgbColor(153, 50, 204)),
+    ("darkred", RgbColor(139, 0, 0)),
+    ("darksalmon", RgbColor(233, 150, 122)),
+    ("darkseagreen", RgbColor(143, 188, 143)),
+    ("darkslateblue", RgbColor(72, 61, 139)),
+    ("darkslategray", RgbColor(47, 79, 79)),
+    ("darkslategrey", RgbColor(47, 79, 79)),
+    ("darkturquoise", RgbColor(0, 206, 209)),
+    ("darkviolet", RgbColor(148, 0, 211)),
+    ("deeppink", RgbColor(255, 20, 147)),
+    ("deepskyblue", RgbColor(0, 191, 255)),
+    ("dimgray", RgbColor(105, 105, 105)),
+    ("dimgrey", RgbColor(105, 105, 105)),
+    ("dodgerblue", RgbColor(30, 144, 255)),
+    ("firebrick", RgbColor(178, 34, 34)),
+    ("floralwhite", RgbColor(255, 250, 240)),
+    ("forestgreen", RgbColor(34, 139, 34)),
+    ("fuchsia", RgbColor(255, 0, 255)),
+    ("gainsboro", RgbColor(220, 220, 220)),
+    ("ghostwhite", RgbColor(248, 248, 255)),
+    ("gold", RgbColor(255, 215, 0)),
+    ("goldenrod", RgbColor(218, 165, 32)),
+    ("gray", RgbColor(128, 128, 128)),
+    ("green", RgbColor(0, 128, 0)),
+    ("greenyellow", RgbColor(173, 255, 47)),
+    ("grey", RgbColor(128, 128, 128)),
+    ("honeydew", RgbColor(240, 255, 240)),
+    ("hotpink", RgbColor(255, 105, 180)),
+    ("indianred", RgbColor(205, 92, 92)),
+    ("indigo", RgbColor(75, 0, 130)),
+    ("ivory", RgbColor(255, 255, 240)),
+    ("khaki", RgbColor(240, 230, 140)),
+    ("lavender", RgbColor(230, 230, 250)),
+    ("lavenderblush", RgbColor(255, 240, 245)),
+    ("lawngreen", RgbColor(124, 252, 0)),
+    ("lemonchiffon", RgbColor(255, 250, 205)),
+    ("lightblue", RgbColor(173, 216, 230)),
+    ("lightcoral", RgbColor(240, 128, 128)),
+    ("lightcyan", RgbColor(224, 255, 255)),
+    ("lightgoldenrodyellow", RgbColor(250, 250, 210)),
+    ("lightgray", RgbColor(211, 211, 211)),
+    ("lightgreen", RgbColor(144, 238, 144)),
+    ("lightgrey", RgbColor(211, 211, 211)),
+    ("lightpink", RgbColor(255, 182, 193)),
+    ("lightsalmon", RgbColor(255, 160, 122)),
+    ("lightseagreen", RgbColor(32, 178, 170)),
+    ("lightskyblue", RgbColor(135, 206, 250)),
+    ("lightslategray", RgbColor(119, 136, 153)),
+    ("lightslategrey", RgbColor(119, 136, 153)),
+    ("lightsteelblue", RgbColor(176, 196, 222)),
+    ("lightyellow", RgbColor(255, 255, 224)),
+    ("lime", RgbColor(0, 255, 0)),
+    ("limegreen", RgbColor(50, 205, 50)),
+    ("linen", RgbColor(250, 240, 230)),
+    ("magenta", RgbColor(255, 0, 255)),
+    ("maroon", RgbColor(128, 0, 0)),
+    ("mediumaquamarine", RgbColor(102, 205, 170)),
+    ("mediumblue", RgbColor(0, 0, 205)),
+    ("mediumorchid", RgbColor(186, 85, 211)),
+    ("mediumpurple", RgbColor(147, 112, 219)),
+    ("mediumseagreen", RgbColor(60, 179, 113)),
+    ("mediumslateblue", RgbColor(123, 104, 238)),
+    ("mediumspringgreen", RgbColor(0, 250, 154)),
+    ("mediumturquoise", RgbColor(72, 209, 204)),
+    ("mediumvioletred", RgbColor(199, 21, 133)),
+    ("midnightblue", RgbColor(25, 25, 112)),
+    ("mintcream", RgbColor(245, 255, 250)),
+    ("mistyrose", RgbColor(255, 228, 225)),
+    ("moccasin", RgbColor(255, 228, 181)),
+    ("navajowhite", RgbColor(255, 222, 173)),
+    ("navy", RgbColor(0, 0, 128)),
+    ("oldlace", RgbColor(253, 245, 230)),
+    ("olive", RgbColor(128, 128, 0)),
+    ("olivedrab", RgbColor(107, 142, 35)),
+    ("orange", RgbColor(255, 165, 0)),
+    ("orangered", RgbColor(255, 69, 0)),
+    ("orchid", RgbColor(218, 112, 214)),
+    ("palegoldenrod", RgbColor(238, 232, 170)),
+    ("palegreen", RgbColor(152, 251, 152)),
+    ("paleturquoise", RgbColor(175, 238, 238)),
+    ("palevioletred", RgbColor(219, 112, 147)),
+    ("papayawhip", RgbColor(255, 239, 213)),
+    ("peachpuff", RgbColor(255, 218, 185)),
+    ("peru", RgbColor(205, 133, 63)),
+    ("pink", RgbColor(255, 192, 203)),
+    ("plum", RgbColor(221, 160, 221)),
+    ("powderblue", RgbColor(176, 224, 230)),
+    ("purple", RgbColor(128, 0, 128)),
+    ("rebeccapurple", RgbColor(102, 51, 153)),
+    ("red", RgbColor(255, 0, 0)),
+    ("rosybrown", RgbColor(188, 143, 143)),
+    ("royalblue", RgbColor(65, 105, 225)),
+    ("saddlebrown", RgbColor(139, 69, 19)),
+    ("salmon", RgbColor(250, 128, 114)),
+    ("sandybrown", RgbColor(244, 164, 96)),
+    ("seagreen", RgbColor(46, 139, 87)),
+    ("seashell", RgbColor(255, 245, 238)),
+    ("sienna", RgbColor(160, 82, 45)),
+    ("silver", RgbColor(192, 192, 192)),
+    ("skyblue", RgbColor(135, 206, 235)),
+    ("slateblue", RgbColor(106, 90, 205)),
+    ("slategray", RgbColor(112, 128, 144)),
+    ("slategrey", RgbColor(112, 128, 144)),
+    ("snow", RgbColor(255, 250, 250)),
+    ("springgreen", RgbColor(0, 255, 127)),
+    ("steelblue", RgbColor(70, 130, 180)),
+    ("tan", RgbColor(210, 180, 140)),
+    ("teal", RgbColor(0, 128, 128)),
+    ("thistle", RgbColor(216, 191, 216)),
+    ("tomato", RgbColor(255, 99, 71)),
+    ("turquoise", RgbColor(64, 224, 208)),
+    ("violet", RgbColor(238, 130, 238)),
+    ("wheat", RgbColor(245, 222, 179)),
+    ("white", RgbColor(255, 255, 255)),
+    ("whitesmoke", RgbColor(245, 245, 245)),
+    ("yellow", RgbColor(255, 255, 0)),
+    ("yellowgreen", RgbColor(154, 205, 50)),
+];
+
+pub(crate) fn parse(input: &str) -> Result<RgbColor, RgbColorParseError> {
+    trace!("attempting to parse \"{}\" to RGB color", input);
+
+    let mut parser = Parser::new(input);
+    let color = parser.color();
+    match &color {
+        Ok(color) => debug!("parsed \"{}\" to {:?}", input, color),
+        Err(e) => debug!("couldn't parse \"{}\": {}", input, e),
+    }
+
+    color
+}
+
+/// A single value inside a color function, such as the `50%` in `hsl(120 50% 50%)`.
+struct Argument {
+    value: f64,
+    unit: Unit,
+    /// Where the argument starts, for error messages.
+    position: usize,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Unit {
+    Number,
+    Percent,
+    /// An angle, which has already been converted to degrees.  Holds the unit it was written in.
+    Angle(&'static str),
+}
+
+impl Argument {
+    /// An RGB channel, from 0 to 255.  Out-of-range values are clamped, as in CSS.
+    fn channel(&self) -> Result<f64, ColorErrorKind> {
+        match self.unit {
+            Unit::Number => Ok(self.value.clamp(0.0, 255.0)),
+            Unit::Percent => Ok((self.value / 100.0 * 255.0).clamp(0.0, 255.0)),
+            Unit::Angle(unit) => Err(ColorErrorKind::InvalidUnit(unit)),
+        }
+    }
+
+    /// A hue, in degrees.
+    fn hue(&self) -> Result<f64, ColorErrorKind> {
+        match self.unit {
+            Unit::Number | Unit::Angle(_) => Ok(self.value),
+            Unit::Percent => Err(ColorErrorKind::InvalidUnit("%")),
+        }
+    }
+
+    /// A saturation, lightness, whiteness or blackness, from 0 to 1.  CSS allows these to be
+    /// written as plain numbers, which mean the same as percentages.
+    fn fraction(&self) -> Result<f64, ColorErrorKind> {
+        match self.unit {
+            Unit::Number | Unit::Percent => Ok((self.value / 100.0).clamp(0.0, 1.0)),
+            Unit::Angle(unit) => Err(ColorErrorKind::InvalidUnit(unit)),
+        }
+    }
+
+    /// An alpha value, from 0 to 1.
+    fn alpha(&self) -> Result<f64, ColorErrorKind> {
+        match self.unit {
+            Unit::Number => Ok(self.value.clamp(0.0, 1.0)),
+            Unit::Percent => Ok((self.value / 100.0).clamp(0.0, 1.0)),
+            Unit::Angle(unit) => Err(ColorErrorKind::InvalidUnit(unit)),
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    chars: Vec<char>,
+    /// The index (in characters, not bytes) of the next character.
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            chars: input.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn error(&self, position: usize, kind: ColorErrorKind) -> RgbColorParseError {
+        RgbColorParseError {
+            input: self.input.to_string(),
+            position,
+            kind,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    /// Fails with the appropriate error for whatever is at the current position, which isn't
+    /// what we wanted.
+    fn unexpected<T>(&self) -> Result<T, RgbColorParseError> {
+        Err(self.error(
+            self.pos,
+            match self.peek() {
+                Some(c) => ColorErrorKind::Unexpected(c),
+                None => ColorErrorKind::UnexpectedEnd,
+            },
+        ))
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), RgbColorParseError> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            self.unexpected()
+        }
+    }
+
+    /// Skips any whitespace, returning whether there was any.
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    fn color(&mut self) -> Result<RgbColor, RgbColorParseError> {
+        self.skip_whitespace();
+        let color = match self.peek() {
+            None => return Err(self.error(self.pos, ColorErrorKind::Empty)),
+            Some('#') => {
+                self.pos += 1;
+                self.hex(&[3, 4, 6, 8])?
+            }
+            Some('0') if matches!(self.peek_at(1), Some('x') | Some('X')) => {
+                self.pos += 2;
+                self.hex(&[6])?
+            }
+            Some(c) if c.is_ascii_digit() => self.decimal_rgb()?,
+            Some(c) if c.is_alphabetic() => self.name_or_function()?,
+            Some(_) => return self.unexpected(),
+        };
+
+        self.skip_whitespace();
+        match self.peek() {
+            None => Ok(color),
+            Some(_) => self.unexpected(),
+        }
+    }
+
+    /// Parses the digits of a hex color, which may have any of the given numbers of digits.  With
+    /// four or eight digits, the last one or two are alpha.
+    fn hex(&mut self, lengths: &[usize]) -> Result<RgbColor, RgbColorParseError> {
+        let start = self.pos;
+        let mut digits = vec![];
+        while let Some(c) = self.peek().filter(|c| c.is_alphanumeric()) {
+            match c.to_digit(16) {
+                Some(digit) => digits.push(digit as u8),
+                None => return Err(self.error(self.pos, ColorErrorKind::InvalidHexDigit(c))),
+            }
+            self.pos += 1;
+        }
+
+        if !lengths.contains(&digits.len()) {
+            return Err(self.error(start, ColorErrorKind::InvalidHexLength(digits.len())));
+        }
+
+        let channels = if digits.len() <= 4 {
+            // CSS shorthand: #b0b is #bb00bb.
+            digits.iter().map(|d| d * 17).collect::<Vec<u8>>()
+        } else {
+            digits
+                .chunks(2)
+                .map(|pair| pair[0] * 16 + pair[1])
+                .collect::<Vec<u8>>()
+        };
+
+        let color = RgbColor(channels[0], channels[1], channels[2]);
+        Ok(match channels.get(3) {
+            Some(&alpha) => color.scale(alpha as f32 / 255.0),
+            None => color,
+        })
+    }
+
+    /// Parses luxide's `R,G,B` notation, where each channel is a whole number from 0 to 255.
+    fn decimal_rgb(&mut self) -> Result<RgbColor, RgbColorParseError> {
+        let mut channels = [0u8; 3];
+        for (i, channel) in channels.iter_mut().enumerate() {
+            if i > 0 {
+                self.skip_whitespace();
+                self.expect(',')?;
+                self.skip_whitespace();
+            }
+
+            let start = self.pos;
+            let value = self.number()?;
+            if value.fract() != 0.0 || !(0.0..=255.0).contains(&value) {
+                return Err(self.error(start, ColorErrorKind::OutOfRange));
+            }
+            *channel = value as u8;
+        }
+
+        Ok(RgbColor(channels[0], channels[1], channels[2]))
+    }
+
+    fn identifier(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .to_lowercase()
+    }
+
+    fn name_or_function(&mut self) -> Result<RgbColor, RgbColorParseError> {
+        let start = self.pos;
+        let name = self.identifier();
+
+        if self.peek() == Some('(') {
+            return self.function(&name, start);
+        }
+
+        if name == "transparent" {
+            return Ok(RgbColor::off());
+        }
+        match NAMED_COLORS.binary_search_by_key(&name.as_str(), |&(n, _)| n) {
+            Ok(i) => Ok(NAMED_COLORS[i].1),
+            Err(_) => Err(self.error(start, ColorErrorKind::UnknownName(name))),
+        }
+    }
+
+    fn function(&mut self, name: &str, start: usize) -> Result<RgbColor, RgbColorParseError> {
+        let name = match name {
+            "rgb" | "rgba" => "rgb",
+            "hsl" | "hsla" => "hsl",
+            "hwb" => "hwb",
+            _ => return Err(self.error(start, ColorErrorKind::UnknownFunction(name.to_string()))),
+        };
+        self.expect('(')?;
+
+        let (args, alpha) = self.arguments(name != "hwb")?;
+        if args.len() != 3 {
+            let position = args.get(3).map_or(self.pos - 1, |a| a.position);
+            return Err(self.error(
+                position,
+                ColorErrorKind::WrongArgumentCount {
+                    function: name,
+                    found: args.len(),
+                },
+            ));
+        }
+
+        let at = |arg: &Argument, result: Result<f64, ColorErrorKind>| {
+            result.map_err(|kind| self.error(arg.position, kind))
+        };
+        let color = match name {
+            "rgb" => {
+                let channel = |arg: &Argument| at(arg, arg.channel()).map(|c| c.round() as u8);
+                RgbColor(channel(&args[0])?, channel(&args[1])?, channel(&args[2])?)
+            }
+            "hsl" => RgbColor::from_hsl(Hsl {
+                hue: at(&args[0], args[0].hue())? as f32,
+                saturation: at(&args[1], args[1].fraction())? as f32,
+                lightness: at(&args[2], args[2].fraction())? as f32,
+            }),
+            _ => hwb(
+                at(&args[0], args[0].hue())?,
+                at(&args[1], args[1].fraction())?,
+                at(&args[2], args[2].fraction())?,
+            ),
+        };
+
+        Ok(match alpha {
+            Some(alpha) => color.scale(at(&alpha, alpha.alpha())? as f32),
+            None => color,
+        })
+    }
+
+    /// Parses the arguments of a color function, up to and including the closing parenthesis.
+    /// They may be separated by spaces, with an optional alpha after a `/`, or (if
+    /// `commas_allowed`) by commas, in which case a fourth argument is the alpha.
+    fn arguments(
+        &mut self,
+        commas_allowed: bool,
+    ) -> Result<(Vec<Argument>, Option<Argument>), RgbColorParseError> {
+        let mut args = vec![];
+        let mut commas = None;
+
+        loop {
+            self.skip_whitespace();
+            args.push(self.argument()?);
+            let spaced = self.skip_whitespace();
+
+            match self.peek() {
+                Some(')') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(',') if commas_allowed && commas != Some(false) => {
+                    commas = Some(true);
+                    self.pos += 1;
+                }
+                Some('/') if commas != Some(true) => {
+                    self.pos += 1;
+                    self.skip_whitespace();
+                    let alpha = self.argument()?;
+                    self.skip_whitespace();
+                    self.expect(')')?;
+                    return Ok((args, Some(alpha)));
+                }
+                Some(_) if spaced && commas != Some(true) => commas = Some(false),
+                _ => return self.unexpected(),
+            }
+        }
+
+        let alpha = if commas == Some(true) && args.len() == 4 {
+            args.pop()
+        } else {
+            None
+        };
+        Ok((args, alpha))
+    }
+
+    fn argument(&mut self) -> Result<Argument, RgbColorParseError> {
+        let position = self.pos;
+        if self.peek().is_some_and(char::is_alphabetic) {
+            return match self.identifier().as_str() {
+                // CSS 4's "missing component", which is treated as 0.
+                "none" => Ok(Argument {
+                    value: 0.0,
+                    unit: Unit::Number,
+                    position,
+                }),
+                _ => Err(self.error(position, ColorErrorKind::ExpectedNumber)),
+            };
+        }
+
+        let value = self.number()?;
+        let unit_position = self.pos;
+        let (value, unit) = if self.peek() == Some('%') {
+            self.pos += 1;
+            (value, Unit::Percent)
+        } else if self.peek().is_some_and(char::is_alphabetic) {
+            match self.identifier().as_str() {
+                "deg" => (value, Unit::Angle("deg")),
+                "grad" => (value * 0.9, Unit::Angle("grad")),
+                "rad" => (value.to_degrees(), Unit::Angle("rad")),
+                "turn" => (value * 360.0, Unit::Angle("turn")),
+                unit => {
+                    return Err(
+                        self.error(unit_position, ColorErrorKind::UnknownUnit(unit.to_string()))
+                    )
+                }
+            }
+        } else {
+            (value, Unit::Number)
+        };
+
+        Ok(Argument {
+            value,
+            unit,
+            position,
+        })
+    }
+
+    /// Parses a decimal number, which may have a sign and a fractional part.
+    fn number(&mut self) -> Result<f64, RgbColorParseError> {
+        let start = self.pos;
+        if matches!(self.peek(), Some('+') | Some('-')) {
+            self.pos += 1;
+        }
+
+        let mut digits = 0;
+        while let Some(c) = self.peek() {
+            match c {
+                '0'..='9' => digits += 1,
+                '.' => (),
+                _ => break,
+            }
+            self.pos += 1;
+        }
+
+        let text = self.chars[start..self.pos].iter().collect::<String>();
+        match text.parse::<f64>() {
+            Ok(value) if digits > 0 => Ok(value),
+            _ => {
+                self.pos = start;
+                Err(self.error(start, ColorErrorKind::ExpectedNumber))
+            }
+        }
+    }
+}
+
+/// Converts a CSS `hwb()` color.  Whiteness and blackness are mixed into the pure hue, and if
+/// they add up to more than 1 they're scaled down to make a gray.
+fn hwb(hue: f64, whiteness: f64, blackness: f64) -> RgbColor {
+    if whiteness + blackness >= 1.0 {
+        let gray = (whiteness / (whiteness + blackness) * 255.0).round() as u8;
+        return RgbColor(gray, gray, gray);
+    }
+
+    let pure = RgbColor::from_hsl(Hsl {
+        hue: hue as f32,
+        saturation: 1.0,
+        lightness: 0.5,
+    });
+    let channel = |c: u8| {
+        ((c as f64 / 255.0 * (1.0 - whiteness - blackness) + whiteness) * 255.0).round() as u8
+    };
+    RgbColor(channel(pure.0), channel(pure.1), channel(pure.2))
+}
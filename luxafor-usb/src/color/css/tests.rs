@@ -0,0 +1,132 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::color::css::NAMED_COLORS;
+use crate::device::{ColorErrorKind, RgbColor};
+use std::str::FromStr;
+
+fn parse(s: &str) -> RgbColor {
+    RgbColor::from_str(s).unwrap_or_else(|e| panic!("{}", e))
+}
+
+fn error(s: &str) -> (usize, ColorErrorKind) {
+    let e = RgbColor::from_str(s).unwrap_err();
+    (e.position, e.kind)
+}
+
+#[test]
+fn named_colors() {
+    assert_eq!(NAMED_COLORS.len(), 148);
+    assert!(NAMED_COLORS.windows(2).all(|w| w[0].0 < w[1].0));
+
+    assert_eq!(parse("orange"), RgbColor(255, 165, 0));
+    assert_eq!(parse("RebeccaPurple"), RgbColor(102, 51, 153));
+    assert_eq!(parse("green"), RgbColor(0, 128, 0));
+    assert_eq!(parse(" lime "), RgbColor::green());
+    assert_eq!(parse("transparent"), RgbColor::off());
+}
+
+#[test]
+fn hex_colors() {
+    assert_eq!(parse("#b0b"), RgbColor(0xbb, 0, 0xbb));
+    assert_eq!(parse("#123456"), RgbColor(0x12, 0x34, 0x56));
+    assert_eq!(parse("0xFF8000"), RgbColor(255, 128, 0));
+    // Alpha dims the color.
+    assert_eq!(parse("#ffffff80"), RgbColor(128, 128, 128));
+    assert_eq!(parse("#f008"), RgbColor(136, 0, 0));
+}
+
+#[test]
+fn decimal_colors() {
+    assert_eq!(parse("1,2,3"), RgbColor(1, 2, 3));
+    assert_eq!(parse("255, 128 ,0"), RgbColor(255, 128, 0));
+}
+
+#[test]
+fn rgb_function() {
+    assert_eq!(parse("rgb(255, 128, 0)"), RgbColor(255, 128, 0));
+    assert_eq!(parse("rgb(255 128 0)"), RgbColor(255, 128, 0));
+    assert_eq!(parse("RGB(100% 50% 0%)"), RgbColor(255, 128, 0));
+    assert_eq!(parse("rgb(300 -5 none)"), RgbColor(255, 0, 0));
+    assert_eq!(parse("rgba(200, 100, 0, 0.5)"), RgbColor(100, 50, 0));
+    assert_eq!(parse("rgb(200 100 0 / 50%)"), RgbColor(100, 50, 0));
+}
+
+#[test]
+fn hsl_function() {
+    assert_eq!(parse("hsl(270 50% 40%)"), RgbColor(102, 51, 153));
+    assert_eq!(parse("hsl(270deg, 50%, 40%)"), RgbColor(102, 51, 153));
+    assert_eq!(parse("hsla(0.75turn 50 40 / 1)"), RgbColor(102, 51, 153));
+    assert_eq!(parse("hsl(120 100% 50%)"), RgbColor::green());
+}
+
+#[test]
+fn hwb_function() {
+    assert_eq!(parse("hwb(0 0% 0%)"), RgbColor::red());
+    assert_eq!(parse("hwb(120 20% 20%)"), RgbColor(51, 204, 51));
+    assert_eq!(parse("hwb(0 60% 60%)"), RgbColor(128, 128, 128));
+}
+
+#[test]
+fn errors_point_at_the_problem() {
+    assert_eq!(error(""), (0, ColorErrorKind::Empty));
+    assert_eq!(
+        error("oragne"),
+        (0, ColorErrorKind::UnknownName(String::from("oragne")))
+    );
+    assert_eq!(error("#12g456"), (3, ColorErrorKind::InvalidHexDigit('g')));
+    assert_eq!(error("#12345"), (1, ColorErrorKind::InvalidHexLength(5)));
+    assert_eq!(error("1,x,3"), (2, ColorErrorKind::ExpectedNumber));
+    assert_eq!(error("1,256,3"), (2, ColorErrorKind::OutOfRange));
+    assert_eq!(error("1,2"), (3, ColorErrorKind::UnexpectedEnd));
+    assert_eq!(error("1,2,3,4"), (5, ColorErrorKind::Unexpected(',')));
+    assert_eq!(error("rgb(1, x, 3)"), (7, ColorErrorKind::ExpectedNumber));
+    assert_eq!(error("rgb(1 2, 3)"), (7, ColorErrorKind::Unexpected(',')));
+    assert_eq!(
+        error("hsl(10% 50% 50%)"),
+        (4, ColorErrorKind::InvalidUnit("%"))
+    );
+    assert_eq!(
+        error("hsl(10foo 50% 50%)"),
+        (6, ColorErrorKind::UnknownUnit(String::from("foo")))
+    );
+    assert_eq!(
+        error("lab(50 10 10)"),
+        (0, ColorErrorKind::UnknownFunction(String::from("lab")))
+    );
+    assert_eq!(
+        error("rgb(1 2)"),
+        (
+            7,
+            ColorErrorKind::WrongArgumentCount {
+                function: "rgb",
+                found: 2
+            }
+        )
+    );
+    assert_eq!(error("red blue"), (4, ColorErrorKind::Unexpected('b')));
+
+    assert_eq!(
+        RgbColor::from_str("rgb(1, x, 3)").unwrap_err().to_string(),
+        "Invalid color \"rgb(1, x, 3)\": expected a number at character 8"
+    );
+}
+
+#[test]
+fn display_round_trips() {
+    let color = RgbColor(18, 52, 86);
+    assert_eq!(parse(&color.to_string()), color);
+}
@@ -0,0 +1,146 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::color::{Hsl, Hsv};
+use crate::device::RgbColor;
+
+fn hsv(hue: f32, saturation: f32, value: f32) -> Hsv {
+    Hsv {
+        hue,
+        saturation,
+        value,
+    }
+}
+
+#[test]
+fn hsv_to_rgb() {
+    assert_eq!(RgbColor::from_hsv(hsv(0.0, 1.0, 1.0)), RgbColor::red());
+    assert_eq!(RgbColor::from_hsv(hsv(120.0, 1.0, 1.0)), RgbColor::green());
+    assert_eq!(RgbColor::from_hsv(hsv(240.0, 1.0, 1.0)), RgbColor::blue());
+    assert_eq!(
+        RgbColor::from_hsv(hsv(300.0, 1.0, 1.0)),
+        RgbColor::magenta()
+    );
+    assert_eq!(RgbColor::from_hsv(hsv(360.0, 1.0, 1.0)), RgbColor::red());
+    assert_eq!(
+        RgbColor::from_hsv(hsv(-60.0, 1.0, 1.0)),
+        RgbColor::magenta()
+    );
+    assert_eq!(
+        RgbColor::from_hsv(hsv(30.0, 1.0, 1.0)),
+        RgbColor(255, 128, 0)
+    );
+    assert_eq!(
+        RgbColor::from_hsv(hsv(0.0, 0.0, 0.5)),
+        RgbColor(128, 128, 128)
+    );
+}
+
+#[test]
+fn hsv_round_trips() {
+    for &color in &[
+        RgbColor(255, 128, 0),
+        RgbColor(12, 34, 56),
+        RgbColor(200, 200, 200),
+        RgbColor::off(),
+    ] {
+        assert_eq!(RgbColor::from(color.to_hsv()), color);
+    }
+}
+
+#[test]
+fn hsl_to_rgb() {
+    let hsl = |hue, saturation, lightness| Hsl {
+        hue,
+        saturation,
+        lightness,
+    };
+
+    assert_eq!(RgbColor::from_hsl(hsl(0.0, 1.0, 0.5)), RgbColor::red());
+    assert_eq!(RgbColor::from_hsl(hsl(180.0, 1.0, 0.5)), RgbColor::cyan());
+    assert_eq!(RgbColor::from_hsl(hsl(0.0, 1.0, 1.0)), RgbColor::white());
+    assert_eq!(RgbColor::from_hsl(hsl(0.0, 0.0, 0.0)), RgbColor::off());
+    // CSS's rebeccapurple is hsl(270, 50%, 40%).
+    assert_eq!(
+        RgbColor::from_hsl(hsl(270.0, 0.5, 0.4)),
+        RgbColor(102, 51, 153)
+    );
+}
+
+#[test]
+fn hsl_round_trips() {
+    for &color in &[
+        RgbColor(102, 51, 153),
+        RgbColor(1, 2, 3),
+        RgbColor::yellow(),
+    ] {
+        assert_eq!(RgbColor::from(color.to_hsl()), color);
+    }
+}
+
+#[test]
+fn kelvin() {
+    assert_eq!(RgbColor::from_kelvin(6600), RgbColor::white());
+    assert_eq!(RgbColor::from_kelvin(1000), RgbColor::from_kelvin(500));
+
+    // Warm whites are orange, cool whites are blue.
+    let warm = RgbColor::from_kelvin(2700);
+    assert!(warm.0 == 255 && warm.2 < warm.1);
+    let cool = RgbColor::from_kelvin(10000);
+    assert!(cool.2 == 255 && cool.0 < cool.2);
+}
+
+#[test]
+fn lerp() {
+    let from = RgbColor(0, 100, 200);
+    let to = RgbColor(100, 100, 0);
+
+    assert_eq!(from.lerp(to, 0.0), from);
+    assert_eq!(from.lerp(to, 1.0), to);
+    assert_eq!(from.lerp(to, 0.5), RgbColor(50, 100, 100));
+    assert_eq!(from.lerp(to, 2.0), to);
+}
+
+#[test]
+fn scale() {
+    assert_eq!(RgbColor(100, 50, 0).scale(0.5), RgbColor(50, 25, 0));
+    assert_eq!(RgbColor(200, 50, 0).scale(2.0), RgbColor(255, 100, 0));
+    assert_eq!(RgbColor::white().scale(-1.0), RgbColor::off());
+}
+
+#[test]
+fn blending() {
+    assert_eq!(RgbColor::red() + RgbColor::blue(), RgbColor::magenta());
+    assert_eq!(
+        RgbColor(200, 0, 0) + RgbColor(100, 0, 0),
+        RgbColor(255, 0, 0)
+    );
+    assert_eq!(
+        RgbColor(12, 34, 56) * RgbColor::white(),
+        RgbColor(12, 34, 56)
+    );
+    assert_eq!(RgbColor(12, 34, 56) * RgbColor::off(), RgbColor::off());
+    assert_eq!(RgbColor::yellow() * RgbColor::cyan(), RgbColor::green());
+}
+
+#[test]
+fn gamma_correction() {
+    assert_eq!(RgbColor::off().gamma_correct(), RgbColor::off());
+    assert_eq!(RgbColor::white().gamma_correct(), RgbColor::white());
+    // Dim colors stay lit rather than rounding down to off.
+    assert_eq!(RgbColor(1, 2, 3).gamma_correct(), RgbColor(1, 1, 1));
+    assert_eq!(RgbColor(128, 0, 0).gamma_correct(), RgbColor(56, 0, 0));
+}
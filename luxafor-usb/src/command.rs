@@ -0,0 +1,280 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::device::{PatternType, RgbColor, SimpleColor, Target, WaveType};
+use crate::protocol::{self, commands};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, fmt};
+use thiserror::Error;
+
+#[cfg(test)]
+mod tests;
+
+/// A single command frame, as sent to the device.  Every frame the library writes can be decoded
+/// into one of these and encoded back to the same bytes, which makes this handy for
+/// picking apart USB captures and trace logs.
+///
+/// ```
+/// use luxafor_usb::command::Command;
+/// use luxafor_usb::device::{RgbColor, Target};
+///
+/// let command = Command::decode(&[0, 1, 0xFF, 255, 0, 0]).unwrap();
+/// assert_eq!(
+///     command,
+///     Command::RgbColor {
+///         color: RgbColor::red(),
+///         target: Target::All
+///     }
+/// );
+/// assert_eq!(command.encode(), vec![0, 1, 0xFF, 255, 0, 0]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Command {
+    SimpleColor(SimpleColor),
+    RgbColor {
+        color: RgbColor,
+        target: Target,
+    },
+    FadeToColor {
+        color: RgbColor,
+        target: Target,
+        fade_time: u8,
+    },
+    Strobe {
+        color: RgbColor,
+        target: Target,
+        speed: u8,
+        repeat: u8,
+    },
+    Wave {
+        color: RgbColor,
+        wave_type: WaveType,
+        speed: u8,
+        repeat: u8,
+    },
+    Pattern {
+        pattern_type: PatternType,
+        repeat: u8,
+    },
+    GetStatus,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum DecodeError {
+    #[error("The frame is empty")]
+    Empty,
+    #[error("The frame starts with report ID {0:#04x}, but commands are sent with report ID 0")]
+    InvalidReportId(u8),
+    #[error("The frame ends before the command byte")]
+    MissingCommand,
+    #[error("Unknown command {0:#04x}")]
+    UnknownCommand(u8),
+    #[error("A \"{command}\" frame should be {expected} bytes long, but this one is {actual}")]
+    InvalidLength {
+        command: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("Invalid {field} {value:#04x} at byte {index}")]
+    InvalidValue {
+        field: &'static str,
+        index: usize,
+        value: u8,
+    },
+    #[error("Byte {index} is unused and should be 0, but is {value:#04x}")]
+    NonZeroPadding { index: usize, value: u8 },
+}
+
+impl Command {
+    /// Encodes the command into the frame that's written to the device.
+    pub fn encode(&self) -> Vec<u8> {
+        match *self {
+            Command::SimpleColor(color) => protocol::set_simple_color(color).to_vec(),
+            Command::RgbColor { color, target } => protocol::set_rgb_color(color, target).to_vec(),
+            Command::FadeToColor {
+                color,
+                target,
+                fade_time,
+            } => protocol::fade_to_color(color, target, fade_time).to_vec(),
+            Command::Strobe {
+                color,
+                target,
+                speed,
+                repeat,
+            } => protocol::strobe(color, target, speed, repeat).to_vec(),
+            Command::Wave {
+                color,
+                wave_type,
+                speed,
+                repeat,
+            } => protocol::wave(color, wave_type, speed, repeat).to_vec(),
+            Command::Pattern {
+                pattern_type,
+                repeat,
+            } => protocol::pattern(pattern_type, repeat).to_vec(),
+            Command::GetStatus => protocol::get_status().to_vec(),
+        }
+    }
+
+    /// Decodes a frame (including the leading report ID) into the command it encodes.  Decoding
+    /// is strict: the frame must be as long as `encode` would make it, and unused bytes must be
+    /// zero, so that decoding and re-encoding always gives back the original bytes.  The one
+    /// allowance is for USB captures, which pad every report out to its full size: zeros after
+    /// the end of the command, up to a full report, are ignored.
+    pub fn decode(frame: &[u8]) -> Result<Command, DecodeError> {
+        match frame.first() {
+            None => return Err(DecodeError::Empty),
+            Some(0) => (),
+            Some(&id) => return Err(DecodeError::InvalidReportId(id)),
+        }
+
+        let command = *frame.get(1).ok_or(DecodeError::MissingCommand)?;
+        let expected = match command {
+            commands::SIMPLE_COLOR => 3,
+            commands::RGB_COLOR => 6,
+            commands::FADE_TO_COLOR => 7,
+            commands::STROBE => 9,
+            commands::SET_WAVE => 9,
+            commands::SET_PATTERN => 4,
+            commands::GET_STATUS => 9,
+            c => return Err(DecodeError::UnknownCommand(c)),
+        };
+        if frame.len() < expected || frame.len() > protocol::REPORT_SIZE + 1 {
+            return Err(DecodeError::InvalidLength {
+                command: protocol::command_name(command).unwrap(),
+                expected,
+                actual: frame.len(),
+            });
+        }
+
+        if let Some(index) = (expected..frame.len()).find(|&index| frame[index] != 0) {
+            return Err(DecodeError::NonZeroPadding {
+                index,
+                value: frame[index],
+            });
+        }
+
+        let color = || RgbColor(frame[3], frame[4], frame[5]);
+        let target = || field(frame, 2, "target");
+        let padding = |index: usize| match frame[index] {
+            0 => Ok(()),
+            value => Err(DecodeError::NonZeroPadding { index, value }),
+        };
+
+        match command {
+            commands::SIMPLE_COLOR => Ok(Command::SimpleColor(field(frame, 2, "simple color")?)),
+            commands::RGB_COLOR => Ok(Command::RgbColor {
+                color: color(),
+                target: target()?,
+            }),
+            commands::FADE_TO_COLOR => Ok(Command::FadeToColor {
+                color: color(),
+                target: target()?,
+                fade_time: frame[6],
+            }),
+            commands::STROBE => {
+                padding(7)?;
+                Ok(Command::Strobe {
+                    color: color(),
+                    target: target()?,
+                    speed: frame[6],
+                    repeat: frame[8],
+                })
+            }
+            commands::SET_WAVE => {
+                padding(6)?;
+                Ok(Command::Wave {
+                    color: color(),
+                    wave_type: field(frame, 2, "wave type")?,
+                    speed: frame[8],
+                    repeat: frame[7],
+                })
+            }
+            commands::SET_PATTERN => Ok(Command::Pattern {
+                pattern_type: field(frame, 2, "pattern type")?,
+                repeat: frame[3],
+            }),
+            commands::GET_STATUS => {
+                (2..frame.len()).try_for_each(padding)?;
+                Ok(Command::GetStatus)
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn field<T: TryFrom<u8, Error = u8>>(
+    frame: &[u8],
+    index: usize,
+    name: &'static str,
+) -> Result<T, DecodeError> {
+    T::try_from(frame[index]).map_err(|value| DecodeError::InvalidValue {
+        field: name,
+        index,
+        value,
+    })
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::SimpleColor(color) => write!(f, "set simple color {:?}", color),
+            Command::RgbColor { color, target } => {
+                write!(f, "set RGB color {} on {:?}", color, target)
+            }
+            Command::FadeToColor {
+                color,
+                target,
+                fade_time,
+            } => write!(
+                f,
+                "fade {:?} to {} (fade time {})",
+                target, color, fade_time
+            ),
+            Command::Strobe {
+                color,
+                target,
+                speed,
+                repeat,
+            } => write!(
+                f,
+                "strobe {:?} with {} (speed {}, repeat {})",
+                target, color, speed, repeat
+            ),
+            Command::Wave {
+                color,
+                wave_type,
+                speed,
+                repeat,
+            } => write!(
+                f,
+                "wave {:?} with {} (speed {}, repeat {})",
+                wave_type, color, speed, repeat
+            ),
+            Command::Pattern {
+                pattern_type,
+                repeat,
+            } => write!(f, "pattern {:?} (repeat {})", pattern_type, repeat),
+            Command::GetStatus => write!(f, "get status"),
+        }
+    }
+}
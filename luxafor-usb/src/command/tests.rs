@@ -0,0 +1,126 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::command::{Command, DecodeError};
+use crate::device::{Lights, PatternType, RgbColor, SimpleColor, Target, WaveType};
+use crate::transport::mock_luxafor;
+
+#[test]
+fn round_trips_every_frame_the_device_sends() {
+    let (luxafor, mock) = mock_luxafor();
+    let color = RgbColor(1, 2, 3);
+
+    luxafor.set_simple_color(SimpleColor::Magenta).unwrap();
+    luxafor.set_rgb_color(color, Lights::all()).unwrap();
+    luxafor
+        .set_rgb_color(color, Lights::flag() | Lights::BackMiddle)
+        .unwrap();
+    luxafor.fade_to_color(color, Lights::back(), 200).unwrap();
+    luxafor.strobe(color, Lights::flag(), 10, 20).unwrap();
+    luxafor
+        .wave(color, WaveType::OverlappingLong, 30, 40)
+        .unwrap();
+    luxafor.pattern(PatternType::RainbowWave, 50).unwrap();
+    luxafor.status().unwrap();
+
+    for frame in mock.frames() {
+        let command = Command::decode(&frame).unwrap();
+        assert_eq!(command.encode(), frame, "{} didn't round-trip", command);
+    }
+}
+
+#[test]
+fn decodes_fields() {
+    assert_eq!(
+        Command::decode(&[0, 3, 6, 10, 20, 30, 40, 0, 5]),
+        Ok(Command::Strobe {
+            color: RgbColor(10, 20, 30),
+            target: Target::BackTopLed,
+            speed: 40,
+            repeat: 5,
+        })
+    );
+    assert_eq!(
+        Command::decode(&[0, 4, 2, 10, 20, 30, 0, 5, 40]),
+        Ok(Command::Wave {
+            color: RgbColor(10, 20, 30),
+            wave_type: WaveType::Long,
+            speed: 40,
+            repeat: 5,
+        })
+    );
+}
+
+#[test]
+fn decodes_padded_captures() {
+    assert_eq!(
+        Command::decode(&[0, 1, 0xFF, 0, 0, 0xFF, 0, 0, 0]),
+        Ok(Command::RgbColor {
+            color: RgbColor::blue(),
+            target: Target::All
+        })
+    );
+    assert_eq!(
+        Command::decode(&[0, 0, b'G', 0, 0]),
+        Ok(Command::SimpleColor(SimpleColor::Green))
+    );
+}
+
+#[test]
+fn rejects_malformed_frames() {
+    assert_eq!(Command::decode(&[]), Err(DecodeError::Empty));
+    assert_eq!(
+        Command::decode(&[1, 0, b'R']),
+        Err(DecodeError::InvalidReportId(1))
+    );
+    assert_eq!(Command::decode(&[0]), Err(DecodeError::MissingCommand));
+    assert_eq!(
+        Command::decode(&[0, 9, 0]),
+        Err(DecodeError::UnknownCommand(9))
+    );
+    assert_eq!(
+        Command::decode(&[0, 1, 0xFF, 0, 0]),
+        Err(DecodeError::InvalidLength {
+            command: "set RGB color",
+            expected: 6,
+            actual: 5
+        })
+    );
+    assert_eq!(
+        Command::decode(&[0, 1, 0xFF, 0, 0, 0xFF, 0, 0, 0, 0]),
+        Err(DecodeError::InvalidLength {
+            command: "set RGB color",
+            expected: 6,
+            actual: 10
+        })
+    );
+    assert_eq!(
+        Command::decode(&[0, 1, 0xFF, 0, 0, 0xFF, 0, 7, 0]),
+        Err(DecodeError::NonZeroPadding { index: 7, value: 7 })
+    );
+    assert_eq!(
+        Command::decode(&[0, 0, b'X']),
+        Err(DecodeError::InvalidValue {
+            field: "simple color",
+            index: 2,
+            value: b'X'
+        })
+    );
+    assert_eq!(
+        Command::decode(&[0, 3, 0xFF, 0, 0, 0, 0, 1, 0]),
+        Err(DecodeError::NonZeroPadding { index: 7, value: 1 })
+    );
+}
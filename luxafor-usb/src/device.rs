@@ -14,25 +14,58 @@
    along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+use crate::backend::{self, Selector};
+use crate::color::css;
+use crate::model::{self, DeviceModel};
+use crate::protocol;
+use crate::transport::Transport;
 use crate::usb::{LUXAFOR_PID, LUXAFOR_VID};
+use crate::watcher::DeviceWatcher;
+use crate::LuxaforError;
 pub use enumflags2::BitFlags;
-use hidapi::{HidApi, HidDevice};
-use log::{debug, trace};
+use log::debug;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::{
     convert::TryFrom,
     fmt,
     fmt::{Debug, Formatter},
     str::FromStr,
+    sync::{Arc, Mutex},
 };
 use thiserror::Error;
 
+#[cfg(test)]
+mod tests;
+
 pub struct Luxafor {
-    pub(crate) hid_device: HidDevice,
+    pub(crate) transport: Box<dyn Transport>,
+    pub(crate) model: &'static DeviceModel,
+    // Shared so that a `DeviceWatcher` can put the LEDs back the way they were when the device
+    // is plugged back in.
+    pub(crate) state: Arc<Mutex<[LedState; 6]>>,
+    pub(crate) identity: Option<DeviceIdentity>,
+    // Only held so that watching stops when the device is dropped.
+    #[allow(dead_code)]
+    pub(crate) watcher: Option<DeviceWatcher>,
+}
+
+/// What we need to recognise a device when it's plugged back in.
+#[derive(Clone, Debug)]
+pub(crate) struct DeviceIdentity {
+    pub(crate) path: String,
+    pub(crate) serial_number: Option<String>,
 }
 
-#[derive(Clone, Copy, Debug)]
+/// The LED or group of LEDs a targeted command applies to, as encoded on the wire.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
 #[repr(u8)]
-pub(crate) enum Target {
+pub enum Target {
     All = 0xFF,
     Back = 'B' as u8,
     Tab = 'A' as u8,
@@ -56,6 +89,12 @@ pub enum Lights {
 }
 
 impl Lights {
+    /// The position of this light in the arrays returned by `Luxafor::current_state`: 0-2 are the
+    /// flag LEDs from bottom to top, and 3-5 the back LEDs from bottom to top.
+    pub fn index(self) -> usize {
+        (self as u8).trailing_zeros() as usize
+    }
+
     pub fn all() -> BitFlags<Lights> {
         Lights::FlagBottom
             | Lights::FlagMiddle
@@ -74,7 +113,7 @@ impl Lights {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
 pub enum SimpleColor {
     Red = 'R' as u8,
@@ -87,7 +126,7 @@ pub enum SimpleColor {
     Off = 'O' as u8,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
 pub enum WaveType {
     Short = 1,
@@ -96,7 +135,7 @@ pub enum WaveType {
     OverlappingLong = 4,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
 pub enum PatternType {
     Luxafor = 1,
@@ -109,9 +148,412 @@ pub enum PatternType {
     RainbowWave = 8,
 }
 
-#[derive(Clone, Copy, Debug)]
+impl Target {
+    /// The lights this target addresses.
+    pub fn lights(self) -> BitFlags<Lights> {
+        match self {
+            Target::All => Lights::all(),
+            Target::Back => Lights::back(),
+            Target::Tab => Lights::flag(),
+            Target::TabBottomLed => Lights::FlagBottom.into(),
+            Target::TabMiddleLed => Lights::FlagMiddle.into(),
+            Target::TabTopLed => Lights::FlagTop.into(),
+            Target::BackBottomLed => Lights::BackBottom.into(),
+            Target::BackMiddleLed => Lights::BackMiddle.into(),
+            Target::BackTopLed => Lights::BackTop.into(),
+        }
+    }
+}
+
+// These decode the wire values above, handing back the offending byte if it isn't one of them.
+
+impl TryFrom<u8> for Target {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0xFF => Ok(Target::All),
+            b'B' => Ok(Target::Back),
+            b'A' => Ok(Target::Tab),
+            1 => Ok(Target::TabBottomLed),
+            2 => Ok(Target::TabMiddleLed),
+            3 => Ok(Target::TabTopLed),
+            4 => Ok(Target::BackBottomLed),
+            5 => Ok(Target::BackMiddleLed),
+            6 => Ok(Target::BackTopLed),
+            v => Err(v),
+        }
+    }
+}
+
+impl TryFrom<u8> for SimpleColor {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            b'R' => Ok(SimpleColor::Red),
+            b'G' => Ok(SimpleColor::Green),
+            b'B' => Ok(SimpleColor::Blue),
+            b'C' => Ok(SimpleColor::Cyan),
+            b'M' => Ok(SimpleColor::Magenta),
+            b'Y' => Ok(SimpleColor::Yellow),
+            b'W' => Ok(SimpleColor::White),
+            b'O' => Ok(SimpleColor::Off),
+            v => Err(v),
+        }
+    }
+}
+
+impl TryFrom<u8> for WaveType {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(WaveType::Short),
+            2 => Ok(WaveType::Long),
+            3 => Ok(WaveType::OverlappingShort),
+            4 => Ok(WaveType::OverlappingLong),
+            v => Err(v),
+        }
+    }
+}
+
+impl TryFrom<u8> for PatternType {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(PatternType::Luxafor),
+            2 => Ok(PatternType::Random1),
+            3 => Ok(PatternType::Random2),
+            4 => Ok(PatternType::Random3),
+            5 => Ok(PatternType::Police),
+            6 => Ok(PatternType::Random4),
+            7 => Ok(PatternType::Random5),
+            8 => Ok(PatternType::RainbowWave),
+            v => Err(v),
+        }
+    }
+}
+
+/// A name (or number) that couldn't be parsed as a `SimpleColor`, `WaveType`, `PatternType`,
+/// `Lights` or `LightSelection`.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum ParseNameError {
+    #[error("Unknown {what} \"{value}\"; expected {expected}")]
+    Unknown {
+        what: &'static str,
+        value: String,
+        expected: &'static str,
+    },
+    #[error("\"{0}\" isn't a range of lights; ranges count up from 1 to 6, as in 1-3")]
+    InvalidRange(String),
+    #[error("No lights were given")]
+    Empty,
+}
+
+impl ParseNameError {
+    fn unknown(what: &'static str, value: &str, expected: &'static str) -> Self {
+        ParseNameError::Unknown {
+            what,
+            value: value.to_string(),
+            expected,
+        }
+    }
+}
+
+const SIMPLE_COLOR_NAMES: &str = "red, green, blue, cyan, magenta, yellow, white or off";
+const WAVE_TYPE_NAMES: &str = "1-4, short, long, overlapping-short or overlapping-long";
+const PATTERN_TYPE_NAMES: &str = "1-8, luxafor, police, random1-random5 or rainbow-wave";
+const LIGHT_NAMES: &str = "flag-bottom, flag-middle, flag-top, back-bottom, back-middle, \
+                           back-top or 1-6";
+const LIGHT_SELECTION_NAMES: &str = "all, flag, back, none, flag-bottom, flag-middle, \
+                                     flag-top, back-bottom, back-middle, back-top, 1-6 or a \
+                                     range such as 1-3";
+
+impl fmt::Display for SimpleColor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SimpleColor::Red => "red",
+            SimpleColor::Green => "green",
+            SimpleColor::Blue => "blue",
+            SimpleColor::Cyan => "cyan",
+            SimpleColor::Magenta => "magenta",
+            SimpleColor::Yellow => "yellow",
+            SimpleColor::White => "white",
+            SimpleColor::Off => "off",
+        })
+    }
+}
+
+impl FromStr for SimpleColor {
+    type Err = ParseNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "red" => Ok(SimpleColor::Red),
+            "green" => Ok(SimpleColor::Green),
+            "blue" => Ok(SimpleColor::Blue),
+            "cyan" => Ok(SimpleColor::Cyan),
+            "magenta" => Ok(SimpleColor::Magenta),
+            "yellow" => Ok(SimpleColor::Yellow),
+            "white" => Ok(SimpleColor::White),
+            "off" => Ok(SimpleColor::Off),
+            _ => Err(ParseNameError::unknown("color", s, SIMPLE_COLOR_NAMES)),
+        }
+    }
+}
+
+impl fmt::Display for WaveType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            WaveType::Short => "short",
+            WaveType::Long => "long",
+            WaveType::OverlappingShort => "overlapping-short",
+            WaveType::OverlappingLong => "overlapping-long",
+        })
+    }
+}
+
+/// Accepts the wave type's number (1-4) as well as its name.
+impl FromStr for WaveType {
+    type Err = ParseNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "1" | "short" => Ok(WaveType::Short),
+            "2" | "long" => Ok(WaveType::Long),
+            "3" | "overlapping-short" => Ok(WaveType::OverlappingShort),
+            "4" | "overlapping-long" => Ok(WaveType::OverlappingLong),
+            _ => Err(ParseNameError::unknown("wave type", s, WAVE_TYPE_NAMES)),
+        }
+    }
+}
+
+/// Displays the pattern by the name the Luxafor software gives it.
+impl fmt::Display for PatternType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PatternType::Luxafor => "luxafor",
+            PatternType::Police => "police",
+            PatternType::Random1 => "random1",
+            PatternType::Random2 => "random2",
+            PatternType::Random3 => "random3",
+            PatternType::Random4 => "random4",
+            PatternType::Random5 => "random5",
+            PatternType::RainbowWave => "rainbow-wave",
+        })
+    }
+}
+
+/// Accepts the pattern's number (1-8, as the protocol numbers them) as well as its name.
+impl FromStr for PatternType {
+    type Err = ParseNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "1" | "luxafor" => Ok(PatternType::Luxafor),
+            "2" | "random1" => Ok(PatternType::Random1),
+            "3" | "random2" => Ok(PatternType::Random2),
+            "4" | "random3" => Ok(PatternType::Random3),
+            "5" | "police" => Ok(PatternType::Police),
+            "6" | "random4" => Ok(PatternType::Random4),
+            "7" | "random5" => Ok(PatternType::Random5),
+            "8" | "rainbow-wave" | "rainbow" => Ok(PatternType::RainbowWave),
+            _ => Err(ParseNameError::unknown("pattern", s, PATTERN_TYPE_NAMES)),
+        }
+    }
+}
+
+impl Lights {
+    /// Returns the light with the given number, counting from 1 (the bottom flag LED) to 6 (the
+    /// top back LED).
+    pub fn numbered(number: u8) -> Option<Lights> {
+        match number {
+            1 => Some(Lights::FlagBottom),
+            2 => Some(Lights::FlagMiddle),
+            3 => Some(Lights::FlagTop),
+            4 => Some(Lights::BackBottom),
+            5 => Some(Lights::BackMiddle),
+            6 => Some(Lights::BackTop),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Lights {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Lights::FlagBottom => "flag-bottom",
+            Lights::FlagMiddle => "flag-middle",
+            Lights::FlagTop => "flag-top",
+            Lights::BackBottom => "back-bottom",
+            Lights::BackMiddle => "back-middle",
+            Lights::BackTop => "back-top",
+        })
+    }
+}
+
+/// Accepts the light's number (see `Lights::numbered`) as well as its name.
+impl FromStr for Lights {
+    type Err = ParseNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "1" | "flag-bottom" => Ok(Lights::FlagBottom),
+            "2" | "flag-middle" => Ok(Lights::FlagMiddle),
+            "3" | "flag-top" => Ok(Lights::FlagTop),
+            "4" | "back-bottom" => Ok(Lights::BackBottom),
+            "5" | "back-middle" => Ok(Lights::BackMiddle),
+            "6" | "back-top" => Ok(Lights::BackTop),
+            _ => Err(ParseNameError::unknown("light", s, LIGHT_NAMES)),
+        }
+    }
+}
+
+/// A set of lights, written as a comma-separated list of light names (`flag-top`), groups
+/// (`all`, `flag`, `back`, `none`), light numbers (`4`) and ranges of numbers (`1-3`).  An item
+/// starting with `!` is taken out of the set instead of being added; if the first item does, it's
+/// taken out of all the lights, so `!back-middle` is every light but that one.
+///
+/// Displaying a `LightSelection` writes it as briefly as it can, in a form that parses back to
+/// the same lights.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightSelection(pub BitFlags<Lights>);
+
+impl From<BitFlags<Lights>> for LightSelection {
+    fn from(lights: BitFlags<Lights>) -> Self {
+        LightSelection(lights)
+    }
+}
+
+impl From<LightSelection> for BitFlags<Lights> {
+    fn from(selection: LightSelection) -> Self {
+        selection.0
+    }
+}
+
+impl fmt::Display for LightSelection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let lights = self.0;
+        if lights == Lights::all() {
+            return write!(f, "all");
+        } else if lights.is_empty() {
+            return write!(f, "none");
+        } else if lights.bits().count_ones() == 5 {
+            return write!(f, "!{}", (Lights::all() & !lights).iter().next().unwrap());
+        }
+
+        let mut items = vec![];
+        let mut rest = lights;
+        if lights.contains(Lights::flag()) {
+            items.push(String::from("flag"));
+            rest &= !Lights::flag();
+        }
+        if lights.contains(Lights::back()) {
+            items.push(String::from("back"));
+            rest &= !Lights::back();
+        }
+        items.extend(rest.iter().map(|light| light.to_string()));
+
+        write!(f, "{}", items.join(","))
+    }
+}
+
+impl FromStr for LightSelection {
+    type Err = ParseNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Err(ParseNameError::Empty);
+        }
+
+        let mut lights = BitFlags::empty();
+        for (i, item) in s.split(',').map(str::trim).enumerate() {
+            match item.strip_prefix('!') {
+                Some(item) => {
+                    if i == 0 {
+                        lights = Lights::all();
+                    }
+                    lights &= !parse_light_group(item.trim())?;
+                }
+                None => lights |= parse_light_group(item)?,
+            }
+        }
+
+        Ok(LightSelection(lights))
+    }
+}
+
+/// Parses one item of a `LightSelection`.
+fn parse_light_group(item: &str) -> Result<BitFlags<Lights>, ParseNameError> {
+    let unknown = || ParseNameError::unknown("light", item, LIGHT_SELECTION_NAMES);
+
+    match item.to_lowercase().as_str() {
+        "all" => Ok(Lights::all()),
+        "f" | "flag" | "tab" => Ok(Lights::flag()),
+        "b" | "back" => Ok(Lights::back()),
+        "none" => Ok(BitFlags::empty()),
+        lower => match lower.split_once('-') {
+            Some((first, last))
+                if first.chars().all(|c| c.is_ascii_digit())
+                    && last.chars().all(|c| c.is_ascii_digit()) =>
+            {
+                let number = |n: &str| n.parse::<u8>().ok().filter(|n| (1..=6).contains(n));
+                match (number(first), number(last)) {
+                    (Some(first), Some(last)) if first <= last => Ok((first..=last)
+                        .filter_map(Lights::numbered)
+                        .fold(BitFlags::empty(), |lights, light| lights | light)),
+                    _ => Err(ParseNameError::InvalidRange(item.to_string())),
+                }
+            }
+            _ => Lights::from_str(lower)
+                .map(BitFlags::from)
+                .map_err(|_| unknown()),
+        },
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct RgbColor(pub u8, pub u8, pub u8);
 
+/// A report sent back by the device, as returned by `Luxafor::status`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum DeviceResponse {
+    /// The device didn't reply.  Many firmware revisions never send anything back, so this
+    /// doesn't necessarily indicate a problem.
+    Unknown,
+    /// The device has finished executing `command`.
+    Done { command: u8 },
+    /// The device is still executing `command` (e.g. a strobe or pattern that hasn't finished).
+    Busy { command: u8 },
+    /// The device sent a report that doesn't follow the documented format.
+    Unrecognized(Vec<u8>),
+}
+
+impl fmt::Display for DeviceResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = |command: &u8| match protocol::command_name(*command) {
+            Some(name) => name.to_string(),
+            None => format!("command {:#04x}", command),
+        };
+
+        match self {
+            DeviceResponse::Unknown => write!(f, "unknown (the device did not reply)"),
+            DeviceResponse::Done { command } => write!(f, "idle (finished {})", name(command)),
+            DeviceResponse::Busy { command } => write!(f, "busy ({})", name(command)),
+            DeviceResponse::Unrecognized(report) => {
+                write!(f, "unrecognized report {:02x?}", report)
+            }
+        }
+    }
+}
+
 impl RgbColor {
     pub fn red() -> RgbColor {
         RgbColor(255, 0, 0)
@@ -146,141 +588,220 @@ impl RgbColor {
     }
 }
 
-#[derive(Error, Debug)]
-pub enum RgbColorParseError {
-    #[error("The hex color was invalid. Either it was the incorrect length or contained non-hex digits.")]
-    InvalidHexColor(String),
-    #[error("The R,G,B color was invalid. Either it was composed of the wrong number of parts, or contained a value over 255.")]
-    InvalidNumericColor(String),
+/// Formats the color as an HTML-style `#rrggbb` hex color, which `RgbColor::try_from` accepts.
+impl fmt::Display for RgbColor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+impl From<SimpleColor> for RgbColor {
+    fn from(color: SimpleColor) -> Self {
+        match color {
+            SimpleColor::Red => RgbColor::red(),
+            SimpleColor::Green => RgbColor::green(),
+            SimpleColor::Blue => RgbColor::blue(),
+            SimpleColor::Cyan => RgbColor::cyan(),
+            SimpleColor::Magenta => RgbColor::magenta(),
+            SimpleColor::Yellow => RgbColor::yellow(),
+            SimpleColor::White => RgbColor::white(),
+            SimpleColor::Off => RgbColor::off(),
+        }
+    }
+}
+
+/// What we believe a single LED is showing.  The hardware can't be asked, so this is worked out
+/// from the commands that have been sent to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum LedState {
+    /// Nothing has been sent to the LED since the device was opened, so it could be showing
+    /// anything.
+    Unknown,
+    /// The LED is showing (or, during a fade, heading towards) this color.
+    Color(RgbColor),
+    /// The LED is running a hardware effect (strobe, wave or pattern), so its color changes
+    /// from moment to moment and where it ends up is up to the firmware.
+    Indeterminate,
+}
+
+impl LedState {
+    /// Returns the LED's color, if it's known.
+    pub fn color(self) -> Option<RgbColor> {
+        match self {
+            LedState::Color(color) => Some(color),
+            _ => None,
+        }
+    }
+}
+
+/// What was wrong with a color that couldn't be parsed.  See `RgbColorParseError`.
+#[derive(Error, Clone, Debug, PartialEq)]
+pub enum ColorErrorKind {
+    #[error("no color was given")]
+    Empty,
+    #[error("unknown color name \"{0}\"")]
+    UnknownName(String),
+    #[error("unknown color function \"{0}()\"")]
+    UnknownFunction(String),
+    #[error("'{0}' isn't a hex digit")]
+    InvalidHexDigit(char),
+    #[error("a hex color can't have {0} digits")]
+    InvalidHexLength(usize),
+    #[error("expected a number")]
+    ExpectedNumber,
+    #[error("expected a whole number from 0 to 255")]
+    OutOfRange,
+    #[error("\"{0}\" can't be used here")]
+    InvalidUnit(&'static str),
+    #[error("unknown unit \"{0}\"")]
+    UnknownUnit(String),
+    #[error("unexpected '{0}'")]
+    Unexpected(char),
+    #[error("the color ends too soon")]
+    UnexpectedEnd,
+    #[error("{function}() takes 3 values and an optional alpha, not {found}")]
+    WrongArgumentCount {
+        function: &'static str,
+        found: usize,
+    },
+}
+
+/// A color that couldn't be parsed, and whereabouts in it the problem is.
+#[derive(Error, Clone, Debug, PartialEq)]
+#[error("Invalid color \"{input}\": {kind} at character {}", .position + 1)]
+pub struct RgbColorParseError {
+    pub input: String,
+    /// The index, in characters and counting from 0, of the first character that couldn't be
+    /// made sense of.
+    pub position: usize,
+    pub kind: ColorErrorKind,
+}
+
+/// Parses a color as CSS would (`orange`, `#ff8000`, `rgb(255 128 0)`, `hsl(30deg 100% 50%)`
+/// and so on), or in luxide's `R,G,B` or `0xRRGGBB` notation.  Alpha dims the color, since the
+/// LEDs can't be see-through.
+///
+/// Note that the CSS names mean what they do in CSS: `green` is `#008000`, not the Luxafor's
+/// `SimpleColor::Green`, which is `lime`.
+impl FromStr for RgbColor {
+    type Err = RgbColorParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        css::parse(s)
+    }
 }
 
 impl TryFrom<&str> for RgbColor {
     type Error = RgbColorParseError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        trace!("attempting to parse \"{}\" to RGB color", value);
-
-        if value.starts_with("#") {
-            trace!("found possible hex color");
-
-            // I didn't want to make these mutable, I wanted to just initialize them in the match
-            // below, but rustc is (by design) not clever enough to see that they're only used if
-            // they get initialized.
-            let mut r: u8 = 0;
-            let mut g: u8 = 0;
-            let mut b: u8 = 0;
-
-            let from_hex = |s: &str| -> Result<u8, RgbColorParseError> {
-                match u8::from_str_radix(s, 16) {
-                    Ok(i) => Ok(i),
-                    Err(e) => Err(RgbColorParseError::InvalidHexColor(e.to_string())),
-                }
-            };
-
-            let res = match value.len() {
-                4 => {
-                    trace!("found possible CSS shorthand-style color");
-                    r = from_hex(&value[1..2])? * 16 + from_hex(&value[1..2])?;
-                    g = from_hex(&value[2..3])? * 16 + from_hex(&value[2..3])?;
-                    b = from_hex(&value[3..4])? * 16 + from_hex(&value[3..4])?;
-                    Ok(())
-                }
-                7 => {
-                    trace!("found possible HTML color");
-                    r = from_hex(&value[1..3])?;
-                    g = from_hex(&value[3..5])?;
-                    b = from_hex(&value[5..7])?;
-                    Ok(())
-                }
-                l => {
-                    let msg = format!("Invalid length for a hex color ({})", l);
-
-                    trace!("{}", msg);
-                    Err(msg)
-                }
-            };
-
-            if res.is_ok() {
-                let color = RgbColor(r, g, b);
-                debug!("parsed \"{}\" to {:?}", value, color);
-                Ok(color)
-            } else {
-                debug!("couldn't parse \"{}\" to RgbColor", value);
-                Err(RgbColorParseError::InvalidHexColor(res.unwrap_err()))
-            }
-        } else {
-            let from_dec = |s: &str| -> Result<u8, RgbColorParseError> {
-                match u8::from_str(s) {
-                    Ok(i) => Ok(i),
-                    Err(e) => Err(RgbColorParseError::InvalidNumericColor(e.to_string())),
-                }
-            };
-
-            trace!("not a hex color - attempting to parse as r,g,b");
-            let parts = value.split(',').collect::<Vec<&str>>();
-            if parts.len() != 3 {
-                let msg = format!(
-                    "{} parts ({})",
-                    if parts.len() < 3 {
-                        "not enough"
-                    } else {
-                        "too many"
-                    },
-                    parts.len()
-                );
-                debug!("couldn't parse \"{}\" as r,g,b: {}", value, msg);
-                Err(RgbColorParseError::InvalidNumericColor(msg))
-            } else {
-                let rgb = parts
-                    .iter()
-                    .filter_map(|s| from_dec(&s.trim()).ok())
-                    .collect::<Vec<u8>>();
-                if rgb.len() == 3 {
-                    let res = RgbColor(rgb[0], rgb[1], rgb[2]);
-                    debug!("parsed \"{}\" to {:?}", value, res);
-                    Ok(res)
-                } else {
-                    let msg = format!(
-                        "couldn't parse \"{}\" to RgbColor - parsed {} segments, expected 3",
-                        value,
-                        rgb.len()
-                    );
-                    debug!("{}", msg);
-                    Err(RgbColorParseError::InvalidNumericColor(msg))
-                }
-            }
-        }
+        css::parse(value)
     }
 }
 
+/// Identifies one connected Luxafor device.  See `Luxafor::enumerate`.  With the `serde` feature,
+/// the model is written out in full and read back by its name.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DeviceDescriptor {
+    /// The platform-specific device path (e.g. `/dev/hidraw3` on Linux), which can be passed to
+    /// `Luxafor::open_path`.
+    pub path: String,
+    pub serial_number: Option<String>,
+    pub product_string: Option<String>,
+    /// The device release number in binary-coded decimal (e.g. `0x0100` for 1.00).
+    pub release_number: u16,
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::serialization::model::deserialize")
+    )]
+    pub model: &'static DeviceModel,
+}
+
 impl Luxafor {
-    pub fn new() -> Result<Self, String> {
-        match HidApi::new() {
-            // TODO: Theoretically a user could have multiple Luxafors...
-            Ok(api) => match api.open(LUXAFOR_VID, LUXAFOR_PID) {
-                Ok(hid_device) => match hid_device.get_product_string() {
-                    Ok(result) => match result {
-                        Some(str) => {
-                            if !str.to_lowercase().contains("luxafor") {
-                                Err(format!("Unexpected product string: {}", str))
-                            } else {
-                                Ok(Self { hid_device })
-                            }
-                        }
-                        None => Ok(Self { hid_device }),
-                    },
-                    Err(e) => Err(String::from(e.to_string())),
-                },
-                Err(e) => {
-                    let err = e.to_string();
-                    Err(err)
-                }
-            },
-            Err(e) => {
-                let err = e.to_string();
-                Err(err)
+    /// Opens the first Luxafor device found.  If more than one is connected, use `enumerate` to
+    /// find the one you want, then `open_serial` or `open_path` to open it.
+    pub fn new() -> Result<Self, LuxaforError> {
+        Self::open(Selector::First)
+    }
+
+    /// Opens the Luxafor device with the given serial number.
+    pub fn open_serial(serial_number: &str) -> Result<Self, LuxaforError> {
+        Self::open(Selector::Serial(serial_number))
+    }
+
+    /// Opens the Luxafor device at the given path, as reported by `enumerate`.
+    pub fn open_path(path: &str) -> Result<Self, LuxaforError> {
+        Self::open(Selector::Path(path))
+    }
+
+    /// Lists all connected Luxafor devices.
+    pub fn enumerate() -> Result<Vec<DeviceDescriptor>, LuxaforError> {
+        let devices = backend::enumerate()?;
+        debug!("found {} Luxafor device(s): {:?}", devices.len(), devices);
+
+        Ok(devices)
+    }
+
+    fn open(selector: Selector) -> Result<Self, LuxaforError> {
+        let opened = backend::open(selector)?;
+        debug!("opened {:?}", opened.identity);
+
+        if let Some(str) = opened.transport.product_string()? {
+            if !str.to_lowercase().contains("luxafor") {
+                return Err(LuxaforError::UnexpectedProductString(str));
             }
         }
+
+        Ok(Self {
+            identity: opened.identity,
+            ..Self::with_transport(opened.transport)
+        })
+    }
+
+    /// Creates a `Luxafor` that sends its commands over the given transport rather than opening
+    /// a USB device.  This is mostly useful for testing; see `transport::MockTransport`.
+    ///
+    /// The device model is worked out from the transport's product string.
+    pub fn with_transport(transport: Box<dyn Transport>) -> Self {
+        let product_string = transport.product_string().unwrap_or(None);
+        let model = DeviceModel::detect(LUXAFOR_VID, LUXAFOR_PID, product_string.as_deref())
+            .unwrap_or(&model::FLAG);
+        debug!("product string {:?} is a {}", product_string, model);
+
+        Self {
+            transport,
+            model,
+            state: Arc::new(Mutex::new([LedState::Unknown; 6])),
+            identity: None,
+            watcher: None,
+        }
+    }
+
+    /// Returns what each LED is currently showing, indexed as in `Lights::index`.
+    ///
+    /// This is a record of what's been sent rather than something read back from the device, so
+    /// it won't reflect changes made by other programs, and LEDs start out `LedState::Unknown`.
+    pub fn current_state(&self) -> [LedState; 6] {
+        *self.state.lock().unwrap()
+    }
+
+    /// Returns the model of the device, which determines which commands it supports.
+    pub fn model(&self) -> &'static DeviceModel {
+        self.model
+    }
+
+    /// Returns the device's serial number, if it has one.  Devices created with `with_transport`
+    /// never do.
+    pub fn serial_number(&self) -> Option<&str> {
+        self.identity
+            .as_ref()
+            .and_then(|identity| identity.serial_number.as_deref())
     }
 }
 
@@ -295,7 +816,7 @@ impl Debug for Luxafor {
         // exactly best practice.  But it strikes me as preferable (however
         // minimally) to having only the inner matches with each arm having
         // its own match write!(...) {...}.
-        match match self.hid_device.get_manufacturer_string() {
+        match match self.transport.manufacturer_string() {
             Ok(Some(mfr)) => write!(f, ", manufacturer \"{}\"", mfr),
             Ok(None) => write!(f, " <manufacturer unknown>"),
             Err(_) => write!(f, " <error getting manufacturer string>"),
@@ -304,7 +825,7 @@ impl Debug for Luxafor {
             Err(e) => return Err(e),
         };
 
-        match match self.hid_device.get_product_string() {
+        match match self.transport.product_string() {
             Ok(Some(prod)) => write!(f, ", product \"{}\"", prod),
             Ok(None) => write!(f, " <product unknown>"),
             Err(_) => write!(f, " <error getting product string>"),
@@ -313,7 +834,7 @@ impl Debug for Luxafor {
             Err(e) => return Err(e),
         };
 
-        match match self.hid_device.get_serial_number_string() {
+        match match self.transport.serial_number_string() {
             Ok(Some(ser)) => write!(f, ", s/n: {}", ser),
             Ok(None) => write!(f, ", <serial number unknown>"),
             Err(_) => write!(f, ""),
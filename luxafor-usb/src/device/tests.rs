@@ -0,0 +1,138 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::device::{
+    BitFlags, LightSelection, Lights, ParseNameError, PatternType, SimpleColor, WaveType,
+};
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+fn select(s: &str) -> BitFlags<Lights> {
+    LightSelection::from_str(s)
+        .unwrap_or_else(|e| panic!("{}", e))
+        .0
+}
+
+#[test]
+fn simple_colors_round_trip() {
+    for byte in b"RGBCMYWO" {
+        let color = SimpleColor::try_from(*byte).unwrap();
+        assert_eq!(SimpleColor::from_str(&color.to_string()).unwrap(), color);
+    }
+    assert_eq!(SimpleColor::from_str(" Red ").unwrap(), SimpleColor::Red);
+    assert!(matches!(
+        SimpleColor::from_str("purple"),
+        Err(ParseNameError::Unknown { what: "color", .. })
+    ));
+}
+
+#[test]
+fn wave_and_pattern_types_round_trip() {
+    for number in 1..=4u8 {
+        let wave_type = WaveType::try_from(number).unwrap();
+        assert_eq!(WaveType::from_str(&number.to_string()).unwrap(), wave_type);
+        assert_eq!(
+            WaveType::from_str(&wave_type.to_string()).unwrap(),
+            wave_type
+        );
+    }
+    assert!(WaveType::from_str("5").is_err());
+
+    for number in 1..=8u8 {
+        let pattern_type = PatternType::try_from(number).unwrap();
+        assert_eq!(
+            PatternType::from_str(&number.to_string()).unwrap(),
+            pattern_type
+        );
+        assert_eq!(
+            PatternType::from_str(&pattern_type.to_string()).unwrap(),
+            pattern_type
+        );
+    }
+    assert_eq!(
+        PatternType::from_str("rainbow").unwrap(),
+        PatternType::RainbowWave
+    );
+}
+
+#[test]
+fn lights_round_trip() {
+    for number in 1..=6 {
+        let light = Lights::numbered(number).unwrap();
+        assert_eq!(Lights::from_str(&light.to_string()).unwrap(), light);
+        assert_eq!(Lights::from_str(&number.to_string()).unwrap(), light);
+    }
+    assert_eq!(Lights::from_str("back-top").unwrap(), Lights::BackTop);
+    assert!(Lights::from_str("back_top").is_err());
+}
+
+#[test]
+fn light_selection_grammar() {
+    assert_eq!(select("all"), Lights::all());
+    assert_eq!(select("1-3"), Lights::flag());
+    assert_eq!(
+        select("2-5"),
+        Lights::all() & !(Lights::FlagBottom | Lights::BackTop)
+    );
+    assert_eq!(select("flag,back-top"), Lights::flag() | Lights::BackTop);
+    assert_eq!(select("f, 6"), Lights::flag() | Lights::BackTop);
+    assert_eq!(select("!back-middle"), Lights::all() & !Lights::BackMiddle);
+    assert_eq!(select("back,!5"), Lights::BackBottom | Lights::BackTop);
+    assert_eq!(select("none"), BitFlags::empty());
+}
+
+#[test]
+fn light_selection_errors() {
+    assert_eq!(
+        LightSelection::from_str("flag,back_top"),
+        Err(ParseNameError::Unknown {
+            what: "light",
+            value: String::from("back_top"),
+            expected: super::LIGHT_SELECTION_NAMES,
+        })
+    );
+    assert_eq!(
+        LightSelection::from_str("3-1"),
+        Err(ParseNameError::InvalidRange(String::from("3-1")))
+    );
+    assert_eq!(
+        LightSelection::from_str("1-7"),
+        Err(ParseNameError::InvalidRange(String::from("1-7")))
+    );
+    assert_eq!(LightSelection::from_str(" "), Err(ParseNameError::Empty));
+}
+
+#[test]
+fn every_light_selection_round_trips() {
+    for bits in 0..64u8 {
+        let selection = LightSelection(BitFlags::from_bits(bits).unwrap());
+        assert_eq!(
+            LightSelection::from_str(&selection.to_string()).unwrap(),
+            selection,
+            "{} didn't round-trip",
+            selection
+        );
+    }
+
+    assert_eq!(
+        LightSelection(Lights::flag() | Lights::BackTop).to_string(),
+        "flag,back-top"
+    );
+    assert_eq!(
+        LightSelection(Lights::all() & !Lights::BackMiddle).to_string(),
+        "!back-middle"
+    );
+}
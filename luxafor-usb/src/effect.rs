@@ -0,0 +1,469 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Software effects: colors worked out on the host from the time alone, for looks the firmware's
+//! own effects can't do.
+//!
+//! Anything that implements `Effect` can be played with `Effect::play` or `Effect::spawn`, so new
+//! effects need nothing but a function of time.  Most of the ones here run until they're
+//! stopped; wrap one with `Effect::limit` to stop it after a while, or `Effect::only` to keep it
+//! to some of the lights.
+
+use crate::animation::{self, CancelToken, Playing};
+use crate::color::Hsv;
+use crate::device::{Lights, Luxafor, PatternType, RgbColor, WaveType};
+use crate::firmware::{self, PATTERN_PERIOD};
+use crate::frame::Frame;
+use crate::LuxaforError;
+use enumflags2::BitFlags;
+use log::debug;
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests;
+
+/// Something to show on the LEDs that changes over time.
+pub trait Effect: Send {
+    /// The color of each LED, indexed like `Lights::index`, `time` after the effect started.
+    fn frame(&self, time: Duration) -> [RgbColor; 6];
+
+    /// Whether the effect is over at `time`, once the frame for that time has been shown.
+    /// Effects that loop forever, which is the default, never are.
+    fn is_finished(&self, _time: Duration) -> bool {
+        false
+    }
+
+    /// Shows the effect on `luxafor` at `fps` frames a second, returning when it finishes or
+    /// `cancel` is cancelled.  Frames are scheduled as for `Animation::play`.
+    fn play(&self, luxafor: &Luxafor, fps: u32, cancel: &CancelToken) -> Result<(), LuxaforError> {
+        debug!("playing effect at {} fps", fps);
+        animation::run(luxafor, fps, cancel, |time| {
+            (Frame(self.frame(time)), self.is_finished(time))
+        })
+    }
+
+    /// Plays the effect from a background thread, which takes `luxafor` until it's finished or
+    /// stopped.
+    fn spawn(self, luxafor: Luxafor, fps: u32) -> Result<Playing, LuxaforError>
+    where
+        Self: Sized + 'static,
+    {
+        Playing::start(luxafor, move |luxafor, cancel| {
+            self.play(luxafor, fps, cancel)
+        })
+    }
+
+    /// Shows the effect on `lights` only, with the rest turned off.
+    fn only(self, lights: BitFlags<Lights>) -> Only<Self>
+    where
+        Self: Sized,
+    {
+        Only {
+            effect: self,
+            lights,
+        }
+    }
+
+    /// Finishes the effect after `duration`, if it hasn't already.
+    fn limit(self, duration: Duration) -> Limit<Self>
+    where
+        Self: Sized,
+    {
+        Limit {
+            effect: self,
+            duration,
+        }
+    }
+}
+
+impl<E: Effect + ?Sized> Effect for Box<E> {
+    fn frame(&self, time: Duration) -> [RgbColor; 6] {
+        (**self).frame(time)
+    }
+
+    fn is_finished(&self, time: Duration) -> bool {
+        (**self).is_finished(time)
+    }
+}
+
+/// An effect kept to some of the lights.  See `Effect::only`.
+#[derive(Clone, Debug)]
+pub struct Only<E> {
+    pub effect: E,
+    pub lights: BitFlags<Lights>,
+}
+
+impl<E: Effect> Effect for Only<E> {
+    fn frame(&self, time: Duration) -> [RgbColor; 6] {
+        let mut frame = self.effect.frame(time);
+        (!self.lights)
+            .iter()
+            .for_each(|light| frame[light.index()] = RgbColor::off());
+        frame
+    }
+
+    fn is_finished(&self, time: Duration) -> bool {
+        self.effect.is_finished(time)
+    }
+}
+
+/// An effect cut short.  See `Effect::limit`.
+#[derive(Clone, Debug)]
+pub struct Limit<E> {
+    pub effect: E,
+    pub duration: Duration,
+}
+
+impl<E: Effect> Effect for Limit<E> {
+    fn frame(&self, time: Duration) -> [RgbColor; 6] {
+        self.effect.frame(time)
+    }
+
+    fn is_finished(&self, time: Duration) -> bool {
+        time >= self.duration || self.effect.is_finished(time)
+    }
+}
+
+/// Fades all the lights up to `color` and back down again, once every `period`.
+#[derive(Clone, Debug)]
+pub struct Breathe {
+    pub color: RgbColor,
+    pub period: Duration,
+}
+
+impl Default for Breathe {
+    fn default() -> Self {
+        Breathe {
+            color: RgbColor::white(),
+            period: Duration::from_secs(4),
+        }
+    }
+}
+
+impl Effect for Breathe {
+    fn frame(&self, time: Duration) -> [RgbColor; 6] {
+        let level = (1.0 - (TAU * phase(time, self.period)).cos()) / 2.0;
+        [self.color.scale(level).gamma_correct(); 6]
+    }
+}
+
+/// Cycles through the hues once every `period`, with each LED `spread` degrees further round the
+/// color wheel than the one below it.
+#[derive(Clone, Debug)]
+pub struct Rainbow {
+    pub period: Duration,
+    pub spread: f32,
+    pub saturation: f32,
+    pub value: f32,
+}
+
+impl Default for Rainbow {
+    fn default() -> Self {
+        Rainbow {
+            period: Duration::from_secs(6),
+            spread: 30.0,
+            saturation: 1.0,
+            value: 1.0,
+        }
+    }
+}
+
+impl Effect for Rainbow {
+    fn frame(&self, time: Duration) -> [RgbColor; 6] {
+        let hue = 360.0 * phase(time, self.period);
+        let mut frame = [RgbColor::off(); 6];
+        for (i, color) in frame.iter_mut().enumerate() {
+            *color = RgbColor::from_hsv(Hsv {
+                hue: (hue + self.spread * height(i)).rem_euclid(360.0),
+                saturation: self.saturation,
+                value: self.value,
+            });
+        }
+        frame
+    }
+}
+
+/// Sweeps a gradient from `from` to `to` up both sides of the flag, once every `period`.
+#[derive(Clone, Debug)]
+pub struct Gradient {
+    pub from: RgbColor,
+    pub to: RgbColor,
+    pub period: Duration,
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Gradient {
+            from: RgbColor::blue(),
+            to: RgbColor::magenta(),
+            period: Duration::from_secs(2),
+        }
+    }
+}
+
+impl Effect for Gradient {
+    fn frame(&self, time: Duration) -> [RgbColor; 6] {
+        let phase = phase(time, self.period);
+        let mut frame = [RgbColor::off(); 6];
+        for (i, color) in frame.iter_mut().enumerate() {
+            // Each LED follows the one below it a third of a period later.
+            let mix = (1.0 - (TAU * (phase - height(i) / 3.0)).cos()) / 2.0;
+            *color = self.from.lerp(self.to, mix);
+        }
+        frame
+    }
+}
+
+/// A double beat of `color` once every `period`.
+#[derive(Clone, Debug)]
+pub struct Heartbeat {
+    pub color: RgbColor,
+    pub period: Duration,
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Heartbeat {
+            color: RgbColor::red(),
+            period: Duration::from_millis(1200),
+        }
+    }
+}
+
+impl Effect for Heartbeat {
+    fn frame(&self, time: Duration) -> [RgbColor; 6] {
+        let phase = phase(time, self.period);
+        // Each beat jumps straight to full strength and dies away over a few percent of the
+        // period; the second is a little weaker than the first.
+        let beat = |at: f32, strength: f32| {
+            if phase < at {
+                0.0
+            } else {
+                strength * (-(phase - at) / 0.06).exp()
+            }
+        };
+        let level = beat(0.0, 1.0).max(beat(0.2, 0.7));
+        [self.color.scale(level).gamma_correct(); 6]
+    }
+}
+
+/// Flickers each LED independently like a candle flame, drifting to a new brightness every
+/// `period`.
+#[derive(Clone, Debug)]
+pub struct Candle {
+    pub color: RgbColor,
+    pub period: Duration,
+}
+
+impl Default for Candle {
+    fn default() -> Self {
+        Candle {
+            color: RgbColor::from_kelvin(1900),
+            period: Duration::from_millis(100),
+        }
+    }
+}
+
+impl Effect for Candle {
+    fn frame(&self, time: Duration) -> [RgbColor; 6] {
+        let steps = if self.period.is_zero() {
+            0.0
+        } else {
+            time.as_secs_f64() / self.period.as_secs_f64()
+        };
+        let step = steps.floor() as u64;
+        let t = steps.fract() as f32;
+        let t = t * t * (3.0 - 2.0 * t);
+
+        let mut frame = [RgbColor::off(); 6];
+        for (i, color) in frame.iter_mut().enumerate() {
+            let from = noise(i as u64, step);
+            let to = noise(i as u64, step + 1);
+            let level = 0.55 + 0.45 * (from + (to - from) * t);
+            *color = self.color.scale(level).gamma_correct();
+        }
+        frame
+    }
+}
+
+/// A point of `color` with a fading tail `tail` LEDs long, running up the flag and down the back
+/// once every `period`.
+#[derive(Clone, Debug)]
+pub struct Comet {
+    pub color: RgbColor,
+    pub period: Duration,
+    pub tail: f32,
+}
+
+impl Default for Comet {
+    fn default() -> Self {
+        Comet {
+            color: RgbColor::cyan(),
+            period: Duration::from_millis(1200),
+            tail: 2.5,
+        }
+    }
+}
+
+impl Effect for Comet {
+    fn frame(&self, time: Duration) -> [RgbColor; 6] {
+        // The order the comet visits the LEDs in, by `Lights::index`.
+        const PATH: [usize; 6] = [0, 1, 2, 5, 4, 3];
+
+        let head = PATH.len() as f32 * phase(time, self.period);
+        let mut frame = [RgbColor::off(); 6];
+        for (position, &i) in PATH.iter().enumerate() {
+            let behind = (head - position as f32).rem_euclid(PATH.len() as f32);
+            if behind < self.tail {
+                let level = 1.0 - behind / self.tail;
+                frame[i] = self.color.scale(level * level).gamma_correct();
+            }
+        }
+        frame
+    }
+}
+
+/// Double flashes of `first` on the flag, then of `second` on the back, once every `period`.
+#[derive(Clone, Debug)]
+pub struct Police {
+    pub first: RgbColor,
+    pub second: RgbColor,
+    pub period: Duration,
+}
+
+impl Default for Police {
+    fn default() -> Self {
+        Police {
+            first: RgbColor::red(),
+            second: RgbColor::blue(),
+            period: Duration::from_secs(1),
+        }
+    }
+}
+
+impl Effect for Police {
+    fn frame(&self, time: Duration) -> [RgbColor; 6] {
+        let phase = phase(time, self.period);
+        let flash = (phase * 2.0).fract();
+        let on = flash < 0.35 || (0.5..0.85).contains(&flash);
+
+        let mut frame = Frame::default();
+        if on && phase < 0.5 {
+            frame.set(Lights::flag(), self.first);
+        } else if on {
+            frame.set(Lights::back(), self.second);
+        }
+        frame.0
+    }
+}
+
+// The firmware's own effects, for when the device can't play them itself (see `planner`).  They
+// follow `firmware`'s account of how the device plays them, as the simulator does, and finish
+// after `repeat` runs.
+
+/// Flashes `color` on and off, each flash taking `period`, like `Luxafor::strobe`.
+#[derive(Clone, Debug)]
+pub struct Strobe {
+    pub color: RgbColor,
+    pub period: Duration,
+    pub repeat: u8,
+}
+
+impl Effect for Strobe {
+    fn frame(&self, time: Duration) -> [RgbColor; 6] {
+        if phase(time, self.period) < 0.5 {
+            [self.color; 6]
+        } else {
+            [RgbColor::off(); 6]
+        }
+    }
+
+    fn is_finished(&self, time: Duration) -> bool {
+        time >= self.period * firmware::repeats(self.repeat)
+    }
+}
+
+/// Runs a wave of `color` over the lights, each pass taking `period`, like `Luxafor::wave`.  The
+/// overlapping waves start from off rather than the colors already showing.
+#[derive(Clone, Debug)]
+pub struct Wave {
+    pub color: RgbColor,
+    pub wave_type: WaveType,
+    pub period: Duration,
+    pub repeat: u8,
+}
+
+impl Effect for Wave {
+    fn frame(&self, time: Duration) -> [RgbColor; 6] {
+        let phase = phase(time, self.period);
+        let mut frame = [RgbColor::off(); 6];
+        for (i, color) in frame.iter_mut().enumerate() {
+            *color =
+                RgbColor::off().lerp(self.color, firmware::wave_level(self.wave_type, phase, i));
+        }
+        frame
+    }
+
+    fn is_finished(&self, time: Duration) -> bool {
+        time >= self.period * firmware::repeats(self.repeat)
+    }
+}
+
+/// An approximation of one of the built-in patterns, like `Luxafor::pattern`.
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    pub pattern_type: PatternType,
+    pub repeat: u8,
+}
+
+impl Effect for Pattern {
+    fn frame(&self, time: Duration) -> [RgbColor; 6] {
+        let phase = phase(time, PATTERN_PERIOD);
+        let mut frame = [RgbColor::off(); 6];
+        for (i, color) in frame.iter_mut().enumerate() {
+            *color = firmware::pattern_color(self.pattern_type, phase, i);
+        }
+        frame
+    }
+
+    fn is_finished(&self, time: Duration) -> bool {
+        time >= PATTERN_PERIOD * firmware::repeats(self.repeat)
+    }
+}
+
+/// How far through the current `period` we are at `time`, from 0 up to 1.
+fn phase(time: Duration, period: Duration) -> f32 {
+    if period.is_zero() {
+        0.0
+    } else {
+        (time.as_secs_f64() / period.as_secs_f64()).fract() as f32
+    }
+}
+
+/// How far up its side an LED is, by `Lights::index`: 0 at the bottom, 2 at the top.
+fn height(index: usize) -> f32 {
+    (index % 3) as f32
+}
+
+/// A repeatable pseudo-random number from 0 up to 1 for each LED and step, from SplitMix64.
+fn noise(led: u64, step: u64) -> f32 {
+    let mut z = (led << 56 ^ step).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 40) as f32 / (1u64 << 24) as f32
+}
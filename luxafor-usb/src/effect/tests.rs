@@ -0,0 +1,149 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{Breathe, Candle, Comet, Effect, Gradient, Heartbeat, Police, Rainbow};
+use crate::animation::{ms, CancelToken};
+use crate::device::{LedState, Lights, RgbColor};
+use crate::transport::mock_luxafor;
+
+#[test]
+fn breathe() {
+    let breathe = Breathe {
+        color: RgbColor::green(),
+        period: ms(1000),
+    };
+
+    assert_eq!(breathe.frame(ms(0)), [RgbColor::off(); 6]);
+    assert_eq!(breathe.frame(ms(500)), [RgbColor::green(); 6]);
+    assert_eq!(breathe.frame(ms(1500)), [RgbColor::green(); 6]);
+    assert!(!breathe.is_finished(ms(100_000)));
+}
+
+#[test]
+fn rainbow() {
+    let rainbow = Rainbow {
+        period: ms(600),
+        spread: 120.0,
+        ..Rainbow::default()
+    };
+
+    let frame = rainbow.frame(ms(0));
+    assert_eq!(frame[Lights::FlagBottom.index()], RgbColor::red());
+    assert_eq!(frame[Lights::FlagMiddle.index()], RgbColor::green());
+    assert_eq!(frame[Lights::BackTop.index()], RgbColor::blue());
+    assert_eq!(rainbow.frame(ms(200))[0], RgbColor::green());
+}
+
+#[test]
+fn gradient_moves_up() {
+    let gradient = Gradient {
+        from: RgbColor::off(),
+        to: RgbColor::white(),
+        period: ms(900),
+    };
+
+    // Each LED is at its brightest a third of a period after the one below it.
+    assert_eq!(gradient.frame(ms(450))[0], RgbColor::white());
+    assert_eq!(gradient.frame(ms(750))[1], RgbColor::white());
+    assert_eq!(gradient.frame(ms(750))[4], RgbColor::white());
+    assert_eq!(gradient.frame(ms(0))[0], RgbColor::off());
+}
+
+#[test]
+fn heartbeat() {
+    let heartbeat = Heartbeat {
+        color: RgbColor::red(),
+        period: ms(1000),
+    };
+
+    assert_eq!(heartbeat.frame(ms(0)), [RgbColor::red(); 6]);
+    let second = heartbeat.frame(ms(200))[0];
+    assert!(second.0 > 80 && second.0 < 200, "{:?}", second);
+    assert!(heartbeat.frame(ms(900))[0].0 < 10);
+}
+
+#[test]
+fn candle_flickers_repeatably() {
+    let candle = Candle::default();
+
+    let frame = candle.frame(ms(1234));
+    assert_eq!(frame, candle.frame(ms(1234)));
+    assert_ne!(frame, candle.frame(ms(1534)));
+    assert!(frame.iter().any(|&c| c != frame[0]));
+    let brightest = candle.color.gamma_correct();
+    assert!(frame.iter().all(|c| c.0 <= brightest.0 && c.0 > 0));
+}
+
+#[test]
+fn comet() {
+    let comet = Comet {
+        color: RgbColor::white(),
+        period: ms(600),
+        tail: 2.0,
+    };
+
+    let frame = comet.frame(ms(0));
+    assert_eq!(frame[Lights::FlagBottom.index()], RgbColor::white());
+    assert_eq!(frame[Lights::FlagMiddle.index()], RgbColor::off());
+    // The back bottom LED is the last stop before the flag bottom, so the tail is there.
+    let tail = frame[Lights::BackBottom.index()];
+    assert!(tail.0 > 0 && tail.0 < 255, "{:?}", tail);
+
+    assert_eq!(
+        comet.frame(ms(300))[Lights::BackTop.index()],
+        RgbColor::white()
+    );
+}
+
+#[test]
+fn police() {
+    let police = Police {
+        first: RgbColor::red(),
+        second: RgbColor::blue(),
+        period: ms(1000),
+    };
+
+    let frame = police.frame(ms(0));
+    assert_eq!(frame[Lights::FlagTop.index()], RgbColor::red());
+    assert_eq!(frame[Lights::BackTop.index()], RgbColor::off());
+    assert_eq!(police.frame(ms(200)), [RgbColor::off(); 6]);
+
+    let frame = police.frame(ms(750));
+    assert_eq!(frame[Lights::FlagTop.index()], RgbColor::off());
+    assert_eq!(frame[Lights::BackTop.index()], RgbColor::blue());
+}
+
+#[test]
+fn only_and_limit() {
+    let effect = Breathe::default().only(Lights::back()).limit(ms(500));
+
+    let frame = effect.frame(ms(2000));
+    assert_eq!(frame[Lights::FlagBottom.index()], RgbColor::off());
+    assert_eq!(frame[Lights::BackBottom.index()], RgbColor::white());
+    assert!(!effect.is_finished(ms(499)));
+    assert!(effect.is_finished(ms(500)));
+}
+
+#[test]
+fn play_until_finished() {
+    let (luxafor, mock) = mock_luxafor();
+    let effect: Box<dyn Effect> = Box::new(Police::default().only(Lights::flag()).limit(ms(50)));
+
+    effect.play(&luxafor, 100, &CancelToken::new()).unwrap();
+
+    assert!(!mock.frames().is_empty());
+    assert_eq!(luxafor.current_state()[3], LedState::Color(RgbColor::off()));
+}
@@ -0,0 +1,89 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! How the firmware plays its own effects, as far as can be told from watching it: how many
+//! times a `repeat` runs them, and what each LED shows partway through a wave or pattern.  The
+//! simulator draws the firmware's effects with these, and `effect` plays them from the host.
+
+use crate::color::Hsv;
+use crate::device::{PatternType, RgbColor, WaveType};
+use std::time::Duration;
+
+/// How long one run of a built-in pattern takes.
+pub(crate) const PATTERN_PERIOD: Duration = Duration::from_secs(3);
+
+/// The number of times an effect given `repeat` runs.  A repeat of 0 still runs it once.
+pub(crate) fn repeats(repeat: u8) -> u32 {
+    u32::from(repeat.max(1))
+}
+
+/// How much of the wave's color the LED at `index` shows, from 0 to 1, `phase` (0 to 1) of the way
+/// through a pass.
+pub(crate) fn wave_level(wave_type: WaveType, phase: f32, index: usize) -> f32 {
+    // Where the LED is on its side: 0 at the bottom to 2 at the top.
+    let height = (index % 3) as f32;
+    let side = (index / 3) as f32;
+
+    let level = match wave_type {
+        // A spot of color travels up both sides, one LED at a time.
+        WaveType::Short | WaveType::OverlappingShort => 1.0 - (phase * 4.0 - 0.5 - height).abs(),
+        // Each side in turn fades up and back down as a whole.
+        WaveType::Long | WaveType::OverlappingLong => {
+            1.0 - ((phase * 2.0 - side) * 2.0 - 1.0).abs()
+        }
+    };
+    level.max(0.0)
+}
+
+/// An approximation of what the built-in pattern shows on the LED at `index`, `phase` (0 to 1)
+/// of the way through a run.
+pub(crate) fn pattern_color(pattern_type: PatternType, phase: f32, index: usize) -> RgbColor {
+    let hue = |hue: f32| {
+        RgbColor::from_hsv(Hsv {
+            hue: hue.rem_euclid(360.0),
+            saturation: 1.0,
+            value: 1.0,
+        })
+    };
+
+    match pattern_type {
+        PatternType::Luxafor => hue(phase * 360.0),
+        PatternType::RainbowWave => hue(phase * 720.0 + (index % 3) as f32 * 40.0),
+        PatternType::Police => {
+            let flash = (phase * 12.0) as usize;
+            match (flash % 2, index / 3) {
+                (0, 0) => RgbColor::red(),
+                (1, 1) => RgbColor::blue(),
+                _ => RgbColor::off(),
+            }
+        }
+        PatternType::Random1
+        | PatternType::Random2
+        | PatternType::Random3
+        | PatternType::Random4
+        | PatternType::Random5 => {
+            // Each random pattern changes color at its own rate, from a fixed sequence so that
+            // runs look the same every time.
+            let rate = pattern_type as u32 * 2;
+            let step = (phase * rate as f32) as u32;
+            let mut x = (step * 6 + index as u32 + 1).wrapping_mul(0x9E37_79B9) ^ rate;
+            x ^= x >> 15;
+            x = x.wrapping_mul(0x2C1B_3C6D);
+            x ^= x >> 12;
+            hue((x % 360) as f32)
+        }
+    }
+}
@@ -0,0 +1,250 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! A per-LED framebuffer for software animations.
+//!
+//! Build a `Frame` holding the color of every LED and hand it to `Luxafor::show`, which compares
+//! it with what the device is already showing and sends only the frames needed to get there.  A
+//! frame where nothing has changed costs nothing, and one where all six LEDs share a color costs a
+//! single write, which keeps animations running at 30+ fps.
+
+use crate::device::{LedState, Lights, RgbColor, SimpleColor, Target};
+use crate::model::{Capability, DeviceModel};
+use enumflags2::BitFlags;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::ops::{Index, IndexMut};
+
+#[cfg(test)]
+mod tests;
+
+/// The color of each of the six LEDs, indexed like `Lights::index`: 0-2 are the flag LEDs from
+/// bottom to top, and 3-5 the back LEDs from bottom to top.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Frame(pub [RgbColor; 6]);
+
+impl Frame {
+    /// A frame with every LED showing `color`.
+    pub fn solid(color: RgbColor) -> Self {
+        Frame([color; 6])
+    }
+
+    /// Returns this frame with `lights` changed to `color`.
+    pub fn with(mut self, lights: BitFlags<Lights>, color: RgbColor) -> Self {
+        self.set(lights, color);
+        self
+    }
+
+    /// Changes `lights` to `color`.
+    pub fn set(&mut self, lights: BitFlags<Lights>, color: RgbColor) {
+        lights
+            .iter()
+            .for_each(|light| self.0[light.index()] = color);
+    }
+}
+
+/// An all-off frame.
+impl Default for Frame {
+    fn default() -> Self {
+        Frame::solid(RgbColor::off())
+    }
+}
+
+impl Index<Lights> for Frame {
+    type Output = RgbColor;
+
+    fn index(&self, light: Lights) -> &RgbColor {
+        &self.0[light.index()]
+    }
+}
+
+impl IndexMut<Lights> for Frame {
+    fn index_mut(&mut self, light: Lights) -> &mut RgbColor {
+        &mut self.0[light.index()]
+    }
+}
+
+/// One frame `Luxafor::show` sends to the device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Write {
+    Simple(SimpleColor),
+    Rgb(Target, RgbColor),
+}
+
+impl Write {
+    pub(crate) fn lights(self) -> BitFlags<Lights> {
+        match self {
+            Write::Simple(_) => Lights::all(),
+            Write::Rgb(target, _) => target.lights(),
+        }
+    }
+
+    pub(crate) fn color(self) -> RgbColor {
+        match self {
+            Write::Simple(color) => color.into(),
+            Write::Rgb(_, color) => color,
+        }
+    }
+}
+
+const SIMPLE_COLORS: [SimpleColor; 8] = [
+    SimpleColor::Red,
+    SimpleColor::Green,
+    SimpleColor::Blue,
+    SimpleColor::Cyan,
+    SimpleColor::Magenta,
+    SimpleColor::Yellow,
+    SimpleColor::White,
+    SimpleColor::Off,
+];
+
+const FLAG_LEDS: [(usize, Target); 3] = [
+    (0, Target::TabBottomLed),
+    (1, Target::TabMiddleLed),
+    (2, Target::TabTopLed),
+];
+
+const BACK_LEDS: [(usize, Target); 3] = [
+    (3, Target::BackBottomLed),
+    (4, Target::BackMiddleLed),
+    (5, Target::BackTopLed),
+];
+
+/// Works out the fewest writes that take LEDs in `current` to `frame`, using only what `model`
+/// supports, or `None` if it can't show the frame at all.
+///
+/// A write to the whole device or one side may be followed by writes that correct individual LEDs
+/// within it, so every combination of "maybe paint everything one color, then maybe paint each
+/// side one color, then fix up single LEDs" is tried.  There are at most 7 × 4 × 4 of them, which
+/// is far cheaper than the USB writes it saves.
+pub(crate) fn plan(
+    current: &[LedState; 6],
+    frame: &Frame,
+    model: &DeviceModel,
+) -> Option<Vec<Write>> {
+    let mut best: Option<Vec<Write>> = None;
+
+    for color in candidates(&frame.0) {
+        let mut state = *current;
+        let mut writes = Vec::new();
+
+        if let Some(color) = color {
+            let write = match whole_device_write(color, &frame.0, model) {
+                Some(write) => write,
+                None => continue,
+            };
+            writes.push(write);
+            state = [LedState::Color(color); 6];
+        }
+
+        let sides = side_plan(&state, frame, Target::Tab, &FLAG_LEDS, model).zip(side_plan(
+            &state,
+            frame,
+            Target::Back,
+            &BACK_LEDS,
+            model,
+        ));
+        if let Some((flag, back)) = sides {
+            writes.extend(flag);
+            writes.extend(back);
+            if best.as_ref().is_none_or(|best| writes.len() < best.len()) {
+                best = Some(writes);
+            }
+        }
+    }
+
+    best
+}
+
+/// `None` (leave things as they are) followed by each distinct color in `colors`.
+fn candidates(colors: &[RgbColor]) -> Vec<Option<RgbColor>> {
+    let mut candidates = vec![None];
+    for &color in colors {
+        if !candidates.contains(&Some(color)) {
+            candidates.push(Some(color));
+        }
+    }
+    candidates
+}
+
+/// How to paint every LED `color` in one write: `SIMPLE_COLOR` if that's all the frame needs and
+/// `color` is one of the simple colors, otherwise an RGB write to `Target::All`.
+fn whole_device_write(
+    color: RgbColor,
+    frame: &[RgbColor; 6],
+    model: &DeviceModel,
+) -> Option<Write> {
+    let simple = SIMPLE_COLORS
+        .iter()
+        .copied()
+        .find(|&simple| RgbColor::from(simple) == color);
+    match simple {
+        Some(simple)
+            if frame.iter().all(|&c| c == color) && model.supports(Capability::SimpleColor) =>
+        {
+            Some(Write::Simple(simple))
+        }
+        _ if model.supports(Capability::RgbColor) && model.supports_target(Target::All) => {
+            Some(Write::Rgb(Target::All, color))
+        }
+        _ => None,
+    }
+}
+
+/// The fewest writes that take one side from `state` to `frame`, or `None` if `model` can't
+/// address the LEDs that need changing.
+fn side_plan(
+    state: &[LedState; 6],
+    frame: &Frame,
+    side: Target,
+    leds: &[(usize, Target); 3],
+    model: &DeviceModel,
+) -> Option<Vec<Write>> {
+    let wanted: Vec<RgbColor> = leds.iter().map(|&(i, _)| frame.0[i]).collect();
+    let mut best: Option<Vec<Write>> = None;
+
+    for color in candidates(&wanted) {
+        let mut writes = Vec::new();
+        if let Some(color) = color {
+            if !model.supports(Capability::RgbColor) || !model.supports_target(side) {
+                continue;
+            }
+            writes.push(Write::Rgb(side, color));
+        }
+
+        let mut addressable = true;
+        for &(i, target) in leds {
+            let showing = match color {
+                Some(color) => LedState::Color(color),
+                None => state[i],
+            };
+            if showing != LedState::Color(frame.0[i]) {
+                if !model.supports(Capability::RgbColor) || !model.supports_target(target) {
+                    addressable = false;
+                    break;
+                }
+                writes.push(Write::Rgb(target, frame.0[i]));
+            }
+        }
+
+        if addressable && best.as_ref().is_none_or(|best| writes.len() < best.len()) {
+            best = Some(writes);
+        }
+    }
+
+    best
+}
@@ -0,0 +1,127 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::Frame;
+use crate::device::{Lights, Luxafor, RgbColor};
+use crate::transport::{mock_luxafor, MockTransport};
+use crate::LuxaforError;
+
+#[test]
+fn indexes_like_lights() {
+    let frame = Frame::default().with(Lights::FlagTop | Lights::BackBottom, RgbColor::red());
+
+    assert_eq!(frame[Lights::FlagTop], RgbColor::red());
+    assert_eq!(frame.0[Lights::BackBottom.index()], RgbColor::red());
+    assert_eq!(frame[Lights::FlagBottom], RgbColor::off());
+}
+
+#[test]
+fn simple_color_when_all_match() {
+    let (luxafor, mock) = mock_luxafor();
+    luxafor.show(&Frame::solid(RgbColor::green())).unwrap();
+
+    assert_eq!(mock.frames(), vec![vec![0, 0, b'G']]);
+}
+
+#[test]
+fn target_all_for_other_solid_colors() {
+    let (luxafor, mock) = mock_luxafor();
+    luxafor.show(&Frame::solid(RgbColor(1, 2, 3))).unwrap();
+
+    assert_eq!(mock.frames(), vec![vec![0, 1, 0xFF, 1, 2, 3]]);
+}
+
+#[test]
+fn unchanged_frame_sends_nothing() {
+    let (luxafor, mock) = mock_luxafor();
+    let frame = Frame::default().with(Lights::flag(), RgbColor(9, 9, 9));
+    luxafor.show(&frame).unwrap();
+    mock.take_frames();
+    luxafor.show(&frame).unwrap();
+
+    assert!(mock.frames().is_empty());
+}
+
+#[test]
+fn whole_sides() {
+    let (luxafor, mock) = mock_luxafor();
+    let frame = Frame::solid(RgbColor(1, 2, 3)).with(Lights::back(), RgbColor(4, 5, 6));
+    luxafor.show(&frame).unwrap();
+
+    assert_eq!(
+        mock.frames(),
+        vec![vec![0, 1, b'A', 1, 2, 3], vec![0, 1, b'B', 4, 5, 6]]
+    );
+}
+
+#[test]
+fn only_changed_leds() {
+    let (luxafor, mock) = mock_luxafor();
+    let mut frame = Frame::solid(RgbColor(1, 2, 3));
+    luxafor.show(&frame).unwrap();
+    mock.take_frames();
+
+    frame[Lights::BackMiddle] = RgbColor(7, 8, 9);
+    luxafor.show(&frame).unwrap();
+
+    assert_eq!(mock.frames(), vec![vec![0, 1, 5, 7, 8, 9]]);
+}
+
+#[test]
+fn paints_a_side_then_corrects_one_led() {
+    let (luxafor, mock) = mock_luxafor();
+    luxafor.show(&Frame::default()).unwrap();
+    mock.take_frames();
+
+    let frame = Frame::default()
+        .with(Lights::FlagBottom | Lights::FlagMiddle, RgbColor(1, 1, 1))
+        .with(Lights::FlagTop.into(), RgbColor(2, 2, 2));
+    luxafor.show(&frame).unwrap();
+
+    assert_eq!(
+        mock.frames(),
+        vec![vec![0, 1, b'A', 1, 1, 1], vec![0, 1, 3, 2, 2, 2]]
+    );
+}
+
+#[test]
+fn diffs_against_other_commands() {
+    let (luxafor, mock) = mock_luxafor();
+    luxafor
+        .set_rgb_color(RgbColor::red(), Lights::all())
+        .unwrap();
+    mock.take_frames();
+
+    luxafor
+        .show(&Frame::solid(RgbColor::red()).with(Lights::FlagTop.into(), RgbColor::blue()))
+        .unwrap();
+
+    assert_eq!(mock.frames(), vec![vec![0, 1, 3, 0, 0, 255]]);
+}
+
+#[test]
+fn single_target_models() {
+    let mock = MockTransport::with_product_string("LUXAFOR ORB");
+    let luxafor = Luxafor::with_transport(Box::new(mock.clone()));
+
+    luxafor.show(&Frame::solid(RgbColor(1, 2, 3))).unwrap();
+    assert_eq!(mock.frames(), vec![vec![0, 1, 0xFF, 1, 2, 3]]);
+
+    match luxafor.show(&Frame::default().with(Lights::back(), RgbColor::red())) {
+        Err(LuxaforError::Unsupported { .. }) => {}
+        other => panic!("expected Unsupported, got {:?}", other),
+    }
+}
@@ -14,26 +14,77 @@
    along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use std::error::Error;
-use std::fmt;
-use std::fmt::{Debug, Display, Formatter};
+use thiserror::Error;
 
+pub mod animation;
+#[cfg(feature = "async")]
+pub mod async_device;
+mod backend;
+pub mod calibration;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod color;
+pub mod command;
 pub mod device;
+pub mod effect;
+mod firmware;
+pub mod frame;
+#[cfg(target_os = "linux")]
+#[doc(hidden)]
+pub mod linux;
+pub mod model;
+pub mod operation;
+pub mod planner;
 mod protocol;
+pub mod recording;
+#[cfg(feature = "serde")]
+mod serialization;
+pub mod simulator;
+pub mod transport;
 mod usb;
+pub mod watcher;
 
-pub struct LuxaforError {
-    message: &'static str,
-}
+/// The backend this build finds and opens devices through: `"hidapi"`, or `"hidraw"` with the
+/// `hidraw` feature.
+pub const BACKEND: &str = backend::NAME;
 
-impl Error for LuxaforError {}
-impl Display for LuxaforError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.write_str(&self.message)
-    }
+#[derive(Error, Debug)]
+pub enum LuxaforError {
+    #[error("No Luxafor device was found")]
+    DeviceNotFound,
+    #[error("Permission denied opening {0}")]
+    PermissionDenied(String),
+    #[error("Unexpected product string: {0}")]
+    UnexpectedProductString(String),
+    #[error("Couldn't open device: {0}")]
+    Open(String),
+    #[error("Error writing command: {0}")]
+    Write(String),
+    #[error("Short write: only {written} of {expected} bytes were written")]
+    ShortWrite { written: usize, expected: usize },
+    #[error("Error reading from device: {0}")]
+    Read(String),
+    #[error("Couldn't start {0}")]
+    Thread(String),
+    #[error("The device has been disconnected")]
+    Disconnected,
+    #[error("Only devices opened over USB can be reconnected")]
+    NotReconnectable,
+    #[cfg(feature = "async")]
+    #[error("The device's I/O thread has stopped")]
+    IoThreadStopped,
+    #[error("Error accessing recording: {0}")]
+    Recording(String),
+    #[error("{command} is not supported on the {model}")]
+    Unsupported {
+        model: &'static str,
+        command: String,
+    },
 }
-impl Debug for LuxaforError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.write_str(&self.message)
+
+// The CLI reports errors as plain strings, so make it easy to `?` a `LuxaforError` into one.
+impl From<LuxaforError> for String {
+    fn from(e: LuxaforError) -> Self {
+        e.to_string()
     }
 }
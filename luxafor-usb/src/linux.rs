@@ -0,0 +1,35 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Reading what Linux says about HID devices in sysfs.  The hidraw backend finds devices this way,
+//! and `luxide doctor` uses it to look for permission problems whichever backend is in use; it
+//! isn't otherwise part of the library's API.
+
+#[cfg(test)]
+mod tests;
+
+/// Parses the `HID_ID` line of a HID device's sysfs `uevent` file (`HID_ID=0003:000004D8:0000F372`)
+/// into its USB vendor and product IDs.
+pub fn parse_hid_id(uevent: &str) -> Option<(u16, u16)> {
+    let id = uevent
+        .lines()
+        .find_map(|line| line.strip_prefix("HID_ID="))?;
+    let mut fields = id.split(':').skip(1);
+    let vendor_id = u32::from_str_radix(fields.next()?, 16).ok()?;
+    let product_id = u32::from_str_radix(fields.next()?, 16).ok()?;
+
+    Some((vendor_id as u16, product_id as u16))
+}
@@ -0,0 +1,28 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::linux::parse_hid_id;
+
+#[test]
+fn hid_ids_are_read_from_uevent() {
+    let uevent = "DRIVER=hid-generic\n\
+                  HID_ID=0003:000004D8:0000F372\n\
+                  HID_NAME=Microchip Technology Inc. LUXAFOR FLAG\n";
+    assert_eq!(parse_hid_id(uevent), Some((0x04D8, 0xF372)));
+    assert_eq!(parse_hid_id("HID_ID=0003:000004D8\n"), None);
+    assert_eq!(parse_hid_id("HID_ID=0003:nothex:0000F372\n"), None);
+    assert_eq!(parse_hid_id("DRIVER=hid-generic\n"), None);
+}
@@ -0,0 +1,215 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::device::Target;
+use crate::usb::{LUXAFOR_PID, LUXAFOR_VID};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The commands a device model may or may not understand.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Capability {
+    SimpleColor,
+    RgbColor,
+    FadeToColor,
+    Strobe,
+    Wave,
+    Pattern,
+}
+
+/// How a model's LEDs are physically arranged.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Topology {
+    /// Three LEDs on the flag (tab) side and three on the back, each individually addressable.
+    TwoSided,
+    /// LEDs behind a single diffuser, which can only be set all at once.
+    Single,
+}
+
+/// Describes one model of Luxafor hardware and what it can do.  Models are only ever referred to
+/// by `&'static` references to the built-in table, so with the `serde` feature they can be
+/// serialized but not deserialized; a `DeviceDescriptor` reads its model back by name instead.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct DeviceModel {
+    pub name: &'static str,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    /// A lowercase substring of the USB product string that identifies this model.  Every model
+    /// we know of shares the same vendor and product IDs, so this is the only way to tell them
+    /// apart.
+    pub product_string_match: &'static str,
+    /// How many LEDs the firmware can set independently.  A `Single` model counts as one, however
+    /// many LEDs sit behind its diffuser.
+    pub led_count: u8,
+    pub topology: Topology,
+    /// The `Target` values the firmware accepts for targeted commands (set RGB color, fade and
+    /// strobe).
+    pub targets: &'static [Target],
+    pub capabilities: &'static [Capability],
+}
+
+const ALL_TARGETS: &[Target] = &[
+    Target::All,
+    Target::Back,
+    Target::Tab,
+    Target::TabBottomLed,
+    Target::TabMiddleLed,
+    Target::TabTopLed,
+    Target::BackBottomLed,
+    Target::BackMiddleLed,
+    Target::BackTopLed,
+];
+
+const ALL_CAPABILITIES: &[Capability] = &[
+    Capability::SimpleColor,
+    Capability::RgbColor,
+    Capability::FadeToColor,
+    Capability::Strobe,
+    Capability::Wave,
+    Capability::Pattern,
+];
+
+pub const FLAG: DeviceModel = DeviceModel {
+    name: "Luxafor Flag",
+    vendor_id: LUXAFOR_VID,
+    product_id: LUXAFOR_PID,
+    product_string_match: "flag",
+    led_count: 6,
+    topology: Topology::TwoSided,
+    targets: ALL_TARGETS,
+    capabilities: ALL_CAPABILITIES,
+};
+
+pub const COLORBLIND_FLAG: DeviceModel = DeviceModel {
+    name: "Luxafor Colorblind Flag",
+    vendor_id: LUXAFOR_VID,
+    product_id: LUXAFOR_PID,
+    product_string_match: "colorblind",
+    led_count: 6,
+    topology: Topology::TwoSided,
+    targets: ALL_TARGETS,
+    capabilities: ALL_CAPABILITIES,
+};
+
+pub const BLUETOOTH: DeviceModel = DeviceModel {
+    name: "Luxafor Bluetooth",
+    vendor_id: LUXAFOR_VID,
+    product_id: LUXAFOR_PID,
+    product_string_match: "bluetooth",
+    led_count: 6,
+    topology: Topology::TwoSided,
+    targets: ALL_TARGETS,
+    capabilities: ALL_CAPABILITIES,
+};
+
+pub const ORB: DeviceModel = DeviceModel {
+    name: "Luxafor Orb",
+    vendor_id: LUXAFOR_VID,
+    product_id: LUXAFOR_PID,
+    product_string_match: "orb",
+    led_count: 1,
+    topology: Topology::Single,
+    targets: &[Target::All],
+    capabilities: &[
+        Capability::SimpleColor,
+        Capability::RgbColor,
+        Capability::FadeToColor,
+        Capability::Strobe,
+        Capability::Pattern,
+    ],
+};
+
+pub const MUTE_BUTTON: DeviceModel = DeviceModel {
+    name: "Luxafor Mute Button",
+    vendor_id: LUXAFOR_VID,
+    product_id: LUXAFOR_PID,
+    product_string_match: "mute",
+    led_count: 1,
+    topology: Topology::Single,
+    targets: &[Target::All],
+    capabilities: &[
+        Capability::SimpleColor,
+        Capability::RgbColor,
+        Capability::FadeToColor,
+        Capability::Strobe,
+    ],
+};
+
+/// Every model we know about.  The plain Flag comes after the Colorblind Flag so that the latter's
+/// more specific product string is matched first.
+pub const MODELS: &[&DeviceModel] = &[&COLORBLIND_FLAG, &FLAG, &ORB, &BLUETOOTH, &MUTE_BUTTON];
+
+impl DeviceModel {
+    /// Identifies a model from its USB IDs and product string.  Devices with the right IDs but an
+    /// unfamiliar (or missing) product string are assumed to be Flags, since that's by far the
+    /// most common model.
+    pub fn detect(
+        vendor_id: u16,
+        product_id: u16,
+        product_string: Option<&str>,
+    ) -> Option<&'static DeviceModel> {
+        let candidates = MODELS
+            .iter()
+            .filter(|m| m.vendor_id == vendor_id && m.product_id == product_id)
+            .copied()
+            .collect::<Vec<&'static DeviceModel>>();
+
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let product_string = product_string.unwrap_or("").to_lowercase();
+        candidates
+            .iter()
+            .find(|m| product_string.contains(m.product_string_match))
+            .or_else(|| candidates.iter().find(|&&m| m == &FLAG))
+            .or_else(|| candidates.first())
+            .copied()
+    }
+
+    /// Returns whether the given USB IDs belong to any known model.
+    pub fn is_known(vendor_id: u16, product_id: u16) -> bool {
+        MODELS
+            .iter()
+            .any(|m| m.vendor_id == vendor_id && m.product_id == product_id)
+    }
+
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
+    pub fn supports_target(&self, target: Target) -> bool {
+        self.targets.contains(&target)
+    }
+}
+
+impl fmt::Display for DeviceModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
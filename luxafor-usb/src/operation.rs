@@ -0,0 +1,119 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! A whole device call as a value, for storing presets and passing operations between processes.
+//!
+//! Unlike a `Command`, which is a single frame, an `Operation` says which lights it applies to
+//! rather than which wire target, just like the `Luxafor` methods it stands in for.  With the
+//! `serde` feature, operations can be serialized; in JSON a strobe looks like
+//! `{"operation": "strobe", "color": "#ff0000", "lights": ["flag-top"], "speed": 20, "repeat": 5}`.
+
+use crate::device::{Lights, Luxafor, PatternType, RgbColor, SimpleColor, WaveType};
+use crate::LuxaforError;
+use enumflags2::BitFlags;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests;
+
+/// A call to one of the `Luxafor` methods that changes the lights, with its arguments.  Pass it
+/// to `Luxafor::perform` to carry it out.
+///
+/// When deserializing, `lights` may be left out to mean all of them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(tag = "operation", rename_all = "kebab-case")
+)]
+pub enum Operation {
+    /// See `Luxafor::set_simple_color`.
+    SimpleColor { color: SimpleColor },
+    /// See `Luxafor::set_rgb_color`.
+    RgbColor {
+        color: RgbColor,
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::serialization::lights", default = "Lights::all")
+        )]
+        lights: BitFlags<Lights>,
+    },
+    /// See `Luxafor::fade_to_color`.
+    FadeToColor {
+        color: RgbColor,
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::serialization::lights", default = "Lights::all")
+        )]
+        lights: BitFlags<Lights>,
+        fade_time: u8,
+    },
+    /// See `Luxafor::strobe`.
+    Strobe {
+        color: RgbColor,
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::serialization::lights", default = "Lights::all")
+        )]
+        lights: BitFlags<Lights>,
+        speed: u8,
+        repeat: u8,
+    },
+    /// See `Luxafor::wave`.
+    Wave {
+        color: RgbColor,
+        wave_type: WaveType,
+        speed: u8,
+        repeat: u8,
+    },
+    /// See `Luxafor::pattern`.
+    Pattern {
+        pattern_type: PatternType,
+        repeat: u8,
+    },
+}
+
+impl Luxafor {
+    /// Carries out `operation` by calling the method it stands for.
+    pub fn perform(&self, operation: Operation) -> Result<(), LuxaforError> {
+        match operation {
+            Operation::SimpleColor { color } => self.set_simple_color(color),
+            Operation::RgbColor { color, lights } => self.set_rgb_color(color, lights),
+            Operation::FadeToColor {
+                color,
+                lights,
+                fade_time,
+            } => self.fade_to_color(color, lights, fade_time),
+            Operation::Strobe {
+                color,
+                lights,
+                speed,
+                repeat,
+            } => self.strobe(color, lights, speed, repeat),
+            Operation::Wave {
+                color,
+                wave_type,
+                speed,
+                repeat,
+            } => self.wave(color, wave_type, speed, repeat),
+            Operation::Pattern {
+                pattern_type,
+                repeat,
+            } => self.pattern(pattern_type, repeat),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::device::{Lights, Luxafor, PatternType, RgbColor, SimpleColor, WaveType};
+use crate::operation::Operation;
+use crate::transport::MockTransport;
+
+#[test]
+fn perform_sends_the_same_frames_as_the_method() {
+    let performed = MockTransport::new();
+    let called = MockTransport::new();
+    let by_operation = Luxafor::with_transport(Box::new(performed.clone()));
+    let by_method = Luxafor::with_transport(Box::new(called.clone()));
+
+    let lights = Lights::FlagTop | Lights::BackTop;
+    by_operation
+        .perform(Operation::SimpleColor {
+            color: SimpleColor::Cyan,
+        })
+        .unwrap();
+    by_method.set_simple_color(SimpleColor::Cyan).unwrap();
+    by_operation
+        .perform(Operation::RgbColor {
+            color: RgbColor(1, 2, 3),
+            lights,
+        })
+        .unwrap();
+    by_method.set_rgb_color(RgbColor(1, 2, 3), lights).unwrap();
+    by_operation
+        .perform(Operation::FadeToColor {
+            color: RgbColor::red(),
+            lights,
+            fade_time: 40,
+        })
+        .unwrap();
+    by_method
+        .fade_to_color(RgbColor::red(), lights, 40)
+        .unwrap();
+    by_operation
+        .perform(Operation::Strobe {
+            color: RgbColor::blue(),
+            lights: Lights::flag(),
+            speed: 10,
+            repeat: 3,
+        })
+        .unwrap();
+    by_method
+        .strobe(RgbColor::blue(), Lights::flag(), 10, 3)
+        .unwrap();
+    by_operation
+        .perform(Operation::Wave {
+            color: RgbColor::green(),
+            wave_type: WaveType::Long,
+            speed: 5,
+            repeat: 2,
+        })
+        .unwrap();
+    by_method
+        .wave(RgbColor::green(), WaveType::Long, 5, 2)
+        .unwrap();
+    by_operation
+        .perform(Operation::Pattern {
+            pattern_type: PatternType::Police,
+            repeat: 1,
+        })
+        .unwrap();
+    by_method.pattern(PatternType::Police, 1).unwrap();
+
+    assert_eq!(performed.frames(), called.frames());
+    assert_eq!(by_operation.current_state(), by_method.current_state());
+}
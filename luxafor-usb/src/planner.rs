@@ -0,0 +1,362 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Choosing between the firmware's effects and software ones.
+//!
+//! When an effect maps exactly onto a firmware command, such as a one-color strobe on a whole
+//! side, the firmware plays it more smoothly than the host can, and the host is free as soon as
+//! the command is sent.  `Planner` takes an `EffectSpec` describing what's wanted and works out
+//! whether a single command can do it, falling back to playing an `Effect` from the host when it
+//! can't.  It logs the reason for its choice at debug level.
+
+use crate::animation::{self, CancelToken};
+use crate::calibration::{Calibration, Curve};
+use crate::command::Command;
+use crate::device::{Lights, Luxafor, PatternType, RgbColor, WaveType};
+use crate::effect::{self, Effect, Police};
+use crate::firmware::{repeats, PATTERN_PERIOD};
+use crate::frame::Frame;
+use crate::model::{Capability, DeviceModel};
+use crate::usb::lights_to_targets;
+use crate::LuxaforError;
+use enumflags2::BitFlags;
+use log::debug;
+use std::fmt;
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests;
+
+/// How far the firmware's nearest speed may be from the period asked for, as a fraction of it,
+/// before the effect is played in software instead.
+const PERIOD_TOLERANCE: f32 = 0.1;
+
+/// How quickly a strobe or wave goes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rate {
+    /// A speed on the firmware's own 0-255 scale, which is sent as it is when the firmware plays
+    /// the effect.
+    Speed(u8),
+    /// How long each flash or pass takes.  The firmware only plays the effect if one of its speeds
+    /// comes close enough.
+    Period(Duration),
+}
+
+/// An effect, described by what it should look like rather than how it's played.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EffectSpec {
+    /// Flashes `color` on and off on `lights`, `repeat` times, at `rate`.
+    Strobe {
+        color: RgbColor,
+        lights: BitFlags<Lights>,
+        rate: Rate,
+        repeat: u8,
+    },
+    /// Runs a wave of `color` over all the lights, `repeat` times, at `rate`.
+    Wave {
+        color: RgbColor,
+        wave_type: WaveType,
+        rate: Rate,
+        repeat: u8,
+    },
+    /// Plays a built-in pattern `repeat` times.
+    Pattern {
+        pattern_type: PatternType,
+        repeat: u8,
+    },
+    /// The police pattern, with `first` flashing on the flag and `second` on the back.
+    Police {
+        first: RgbColor,
+        second: RgbColor,
+        repeat: u8,
+    },
+}
+
+/// How an effect will be played.
+pub enum Plan {
+    /// By the firmware, with a single command.
+    Native(Command),
+    /// From the host, on `lights`; the other lights are left as they are.
+    Software {
+        effect: Box<dyn Effect>,
+        lights: BitFlags<Lights>,
+    },
+}
+
+impl fmt::Debug for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Plan::Native(command) => f.debug_tuple("Native").field(command).finish(),
+            Plan::Software { lights, .. } => {
+                f.debug_struct("Software").field("lights", lights).finish()
+            }
+        }
+    }
+}
+
+impl Plan {
+    pub fn is_native(&self) -> bool {
+        matches!(self, Plan::Native(_))
+    }
+
+    /// Plays the effect on `luxafor`.  A native command is handed to the firmware and this
+    /// returns straight away; a software effect is shown at `fps` frames a second until it
+    /// finishes, when the lights go back to what they were showing, or until `cancel` is
+    /// cancelled.
+    pub fn run(
+        &self,
+        luxafor: &Luxafor,
+        fps: u32,
+        cancel: &CancelToken,
+    ) -> Result<(), LuxaforError> {
+        match self {
+            Plan::Native(command) => luxafor.execute(*command),
+            Plan::Software { effect, lights } => {
+                let base = Frame(
+                    luxafor
+                        .current_state()
+                        .map(|state| state.color().unwrap_or_else(RgbColor::off)),
+                );
+                animation::run(luxafor, fps, cancel, |time| {
+                    if effect.is_finished(time) {
+                        return (base, true);
+                    }
+                    let colors = effect.frame(time);
+                    let mut frame = base;
+                    lights
+                        .iter()
+                        .for_each(|light| frame.0[light.index()] = colors[light.index()]);
+                    (frame, false)
+                })
+            }
+        }
+    }
+}
+
+/// Works out how to play effects on one model of device.
+#[derive(Clone, Debug)]
+pub struct Planner<'a> {
+    model: &'static DeviceModel,
+    calibration: &'a Calibration,
+    force_software: bool,
+}
+
+impl<'a> Planner<'a> {
+    /// A planner for `model`, which converts between periods and the firmware's speeds with
+    /// `calibration`.
+    pub fn new(model: &'static DeviceModel, calibration: &'a Calibration) -> Self {
+        Planner {
+            model,
+            calibration,
+            force_software: false,
+        }
+    }
+
+    /// Plays everything in software, even effects the firmware could play.
+    pub fn force_software(mut self, force_software: bool) -> Self {
+        self.force_software = force_software;
+        self
+    }
+
+    pub fn plan(&self, spec: &EffectSpec) -> Plan {
+        match self.native(spec) {
+            Ok(command) => {
+                debug!("playing {:?} natively, as {}", spec, command);
+                Plan::Native(command)
+            }
+            Err(reason) => {
+                debug!("playing {:?} in software: {}", spec, reason);
+                self.software(spec)
+            }
+        }
+    }
+
+    /// The firmware command that does just what `spec` describes, or why there isn't one.
+    fn native(&self, spec: &EffectSpec) -> Result<Command, String> {
+        if self.force_software {
+            return Err(String::from("software was asked for"));
+        }
+
+        match *spec {
+            EffectSpec::Strobe {
+                color,
+                lights,
+                rate,
+                repeat,
+            } => {
+                self.require(Capability::Strobe, "strobe")?;
+                let target = match lights_to_targets(lights)[..] {
+                    [target] => target,
+                    [] => return Err(String::from("no lights were chosen")),
+                    ref targets => {
+                        return Err(format!(
+                        "{:?} takes {} strobe commands, which the firmware doesn't keep in step",
+                        lights,
+                        targets.len()
+                    ))
+                    }
+                };
+                if !self.model.supports_target(target) {
+                    return Err(format!("the {} can't address {:?}", self.model, target));
+                }
+                let speed = self.speed(&self.calibration.strobe, rate)?;
+                Ok(Command::Strobe {
+                    color,
+                    target,
+                    speed,
+                    repeat,
+                })
+            }
+            EffectSpec::Wave {
+                color,
+                wave_type,
+                rate,
+                repeat,
+            } => {
+                self.require(Capability::Wave, "play waves")?;
+                let speed = self.speed(&self.calibration.wave, rate)?;
+                Ok(Command::Wave {
+                    color,
+                    wave_type,
+                    speed,
+                    repeat,
+                })
+            }
+            EffectSpec::Pattern {
+                pattern_type,
+                repeat,
+            } => {
+                self.require(Capability::Pattern, "play patterns")?;
+                Ok(Command::Pattern {
+                    pattern_type,
+                    repeat,
+                })
+            }
+            EffectSpec::Police {
+                first,
+                second,
+                repeat,
+            } => {
+                if (first, second) != (RgbColor::red(), RgbColor::blue()) {
+                    return Err(String::from(
+                        "the firmware's police pattern is only red and blue",
+                    ));
+                }
+                self.require(Capability::Pattern, "play patterns")?;
+                Ok(Command::Pattern {
+                    pattern_type: PatternType::Police,
+                    repeat,
+                })
+            }
+        }
+    }
+
+    fn require(&self, capability: Capability, what: &str) -> Result<(), String> {
+        if self.model.supports(capability) {
+            Ok(())
+        } else {
+            Err(format!("the {} can't {}", self.model, what))
+        }
+    }
+
+    /// The firmware speed for `rate`: a speed as it is, or the one whose period is closest to a
+    /// period, if it's close enough.
+    fn speed(&self, curve: &Curve, rate: Rate) -> Result<u8, String> {
+        let period = match rate {
+            Rate::Speed(speed) => return Ok(speed),
+            Rate::Period(period) => period,
+        };
+        let speed = curve.value_for(period);
+        let actual = curve.duration(speed);
+        let error = (actual.as_secs_f32() - period.as_secs_f32()).abs();
+        if error <= period.as_secs_f32() * PERIOD_TOLERANCE {
+            Ok(speed)
+        } else {
+            Err(format!(
+                "the firmware's nearest period to {:?} is {:?}",
+                period, actual
+            ))
+        }
+    }
+
+    /// The software version of `spec`.
+    fn software(&self, spec: &EffectSpec) -> Plan {
+        let (effect, lights): (Box<dyn Effect>, _) = match *spec {
+            EffectSpec::Strobe {
+                color,
+                lights,
+                rate,
+                repeat,
+            } => (
+                Box::new(effect::Strobe {
+                    color,
+                    period: period(&self.calibration.strobe, rate),
+                    repeat,
+                }),
+                lights,
+            ),
+            EffectSpec::Wave {
+                color,
+                wave_type,
+                rate,
+                repeat,
+            } => (
+                Box::new(effect::Wave {
+                    color,
+                    wave_type,
+                    period: period(&self.calibration.wave, rate),
+                    repeat,
+                }),
+                Lights::all(),
+            ),
+            EffectSpec::Pattern {
+                pattern_type,
+                repeat,
+            } => (
+                Box::new(effect::Pattern {
+                    pattern_type,
+                    repeat,
+                }),
+                Lights::all(),
+            ),
+            // About as quick as the firmware's own police pattern.
+            EffectSpec::Police {
+                first,
+                second,
+                repeat,
+            } => (
+                Box::new(
+                    Police {
+                        first,
+                        second,
+                        period: PATTERN_PERIOD / 3,
+                    }
+                    .limit(PATTERN_PERIOD * repeats(repeat)),
+                ),
+                Lights::all(),
+            ),
+        };
+        Plan::Software { effect, lights }
+    }
+}
+
+/// The period for `rate`: a speed's as measured by `curve`, or a period as it is.
+fn period(curve: &Curve, rate: Rate) -> Duration {
+    match rate {
+        Rate::Speed(speed) => curve.duration(speed),
+        Rate::Period(period) => period,
+    }
+}
@@ -0,0 +1,159 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{EffectSpec, Plan, Planner, Rate};
+use crate::animation::CancelToken;
+use crate::calibration::Calibration;
+use crate::command::Command;
+use crate::device::{LedState, Lights, PatternType, RgbColor, Target, WaveType};
+use crate::model;
+use crate::transport::mock_luxafor;
+use std::time::Duration;
+
+fn strobe(lights: enumflags2::BitFlags<Lights>, period: Duration) -> EffectSpec {
+    EffectSpec::Strobe {
+        color: RgbColor::red(),
+        lights,
+        rate: Rate::Period(period),
+        repeat: 3,
+    }
+}
+
+#[test]
+fn strobe_on_one_target_is_native() {
+    let calibration = Calibration::default();
+    let planner = Planner::new(&model::FLAG, &calibration);
+    let period = calibration.strobe.duration(20);
+
+    match planner.plan(&strobe(Lights::flag(), period)) {
+        Plan::Native(Command::Strobe {
+            target: Target::Tab,
+            speed,
+            repeat: 3,
+            ..
+        }) => assert_eq!(calibration.strobe.duration(speed), period),
+        plan => panic!("expected a native strobe, got {:?}", plan),
+    }
+}
+
+#[test]
+fn speeds_are_sent_as_they_are() {
+    let calibration = Calibration::default();
+    let planner = Planner::new(&model::FLAG, &calibration);
+
+    for speed in 0..=255 {
+        let spec = EffectSpec::Wave {
+            color: RgbColor::green(),
+            wave_type: WaveType::Short,
+            rate: Rate::Speed(speed),
+            repeat: 2,
+        };
+        match planner.plan(&spec) {
+            Plan::Native(Command::Wave { speed: sent, .. }) => assert_eq!(sent, speed),
+            plan => panic!("expected a native wave, got {:?}", plan),
+        }
+    }
+}
+
+#[test]
+fn strobe_falls_back_to_software() {
+    let calibration = Calibration::default();
+    let planner = Planner::new(&model::FLAG, &calibration);
+    let period = calibration.strobe.duration(20);
+
+    // Two targets.
+    assert!(!planner
+        .plan(&strobe(Lights::flag() | Lights::BackTop, period))
+        .is_native());
+    // Slower than the firmware can go.
+    assert!(!planner
+        .plan(&strobe(Lights::flag(), Duration::from_secs(3600)))
+        .is_native());
+    // Asked for.
+    assert!(!planner
+        .force_software(true)
+        .plan(&strobe(Lights::flag(), period))
+        .is_native());
+}
+
+#[test]
+fn unsupported_effects_fall_back_to_software() {
+    let calibration = Calibration::default();
+    let wave = EffectSpec::Wave {
+        color: RgbColor::green(),
+        wave_type: WaveType::Short,
+        rate: Rate::Period(calibration.wave.duration(40)),
+        repeat: 2,
+    };
+
+    assert!(Planner::new(&model::FLAG, &calibration)
+        .plan(&wave)
+        .is_native());
+    assert!(!Planner::new(&model::ORB, &calibration)
+        .plan(&wave)
+        .is_native());
+}
+
+#[test]
+fn police() {
+    let calibration = Calibration::default();
+    let planner = Planner::new(&model::FLAG, &calibration);
+
+    match planner.plan(&EffectSpec::Police {
+        first: RgbColor::red(),
+        second: RgbColor::blue(),
+        repeat: 2,
+    }) {
+        Plan::Native(Command::Pattern {
+            pattern_type: PatternType::Police,
+            repeat: 2,
+        }) => {}
+        plan => panic!("expected the native police pattern, got {:?}", plan),
+    }
+    assert!(!planner
+        .plan(&EffectSpec::Police {
+            first: RgbColor::green(),
+            second: RgbColor::blue(),
+            repeat: 2,
+        })
+        .is_native());
+}
+
+#[test]
+fn software_plan_restores_the_other_lights() {
+    let (luxafor, mock) = mock_luxafor();
+    luxafor
+        .set_rgb_color(RgbColor::green(), Lights::all())
+        .unwrap();
+    mock.take_frames();
+
+    let calibration = Calibration::default();
+    let plan = Planner::new(&model::FLAG, &calibration)
+        .force_software(true)
+        .plan(&strobe(Lights::FlagTop.into(), Duration::from_millis(20)));
+    plan.run(&luxafor, 200, &CancelToken::new()).unwrap();
+
+    // Only the chosen light flashes, and it's green again afterwards.
+    assert!(mock
+        .frames()
+        .iter()
+        .all(|frame| frame[..3] == [0, 1, Target::TabTopLed as u8]));
+    assert!(mock.frames().contains(&vec![0, 1, 3, 255, 0, 0]));
+    assert_eq!(
+        luxafor.current_state(),
+        [LedState::Color(RgbColor::green()); 6]
+    );
+}
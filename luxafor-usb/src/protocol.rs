@@ -14,16 +14,16 @@
    along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::device::{PatternType, RgbColor, SimpleColor, Target, WaveType};
+use crate::device::{DeviceResponse, PatternType, RgbColor, SimpleColor, Target, WaveType};
 
-mod commands {
+pub(crate) mod commands {
     pub const SIMPLE_COLOR: u8 = 0;
     pub const RGB_COLOR: u8 = 1;
     pub const FADE_TO_COLOR: u8 = 2;
     pub const STROBE: u8 = 3;
     pub const SET_WAVE: u8 = 4;
     pub const SET_PATTERN: u8 = 6;
-    pub const _GET_STATUS: u8 = 0x80;
+    pub const GET_STATUS: u8 = 0x80;
 }
 
 pub(crate) fn set_simple_color(color: SimpleColor) -> [u8; 3] {
@@ -85,6 +85,39 @@ pub(crate) fn pattern(pattern_type: PatternType, repeat: u8) -> [u8; 4] {
     [0, commands::SET_PATTERN, pattern_type as u8, repeat]
 }
 
-pub(crate) fn _get_status() -> [u8; 8] {
-    [commands::_GET_STATUS, 0, 0, 0, 0, 0, 0, 0]
+/// The size of the device's input and output reports, not counting the report ID.
+pub(crate) const REPORT_SIZE: usize = 8;
+
+pub(crate) fn get_status() -> [u8; 9] {
+    [0, commands::GET_STATUS, 0, 0, 0, 0, 0, 0, 0]
+}
+
+/// Returns a human-readable name for one of the command bytes above.
+pub(crate) fn command_name(command: u8) -> Option<&'static str> {
+    match command {
+        commands::SIMPLE_COLOR => Some("set simple color"),
+        commands::RGB_COLOR => Some("set RGB color"),
+        commands::FADE_TO_COLOR => Some("fade to color"),
+        commands::STROBE => Some("strobe"),
+        commands::SET_WAVE => Some("wave"),
+        commands::SET_PATTERN => Some("pattern"),
+        commands::GET_STATUS => Some("get status"),
+        _ => None,
+    }
+}
+
+/// Decodes an input report read from the device.  The report echoes the command being executed
+/// in its first byte, and its second byte is nonzero while that command is still running.
+pub(crate) fn parse_response(report: &[u8]) -> DeviceResponse {
+    match report {
+        [] => DeviceResponse::Unknown,
+        [command, state, ..] if command_name(*command).is_some() => {
+            if *state == 0 {
+                DeviceResponse::Done { command: *command }
+            } else {
+                DeviceResponse::Busy { command: *command }
+            }
+        }
+        _ => DeviceResponse::Unrecognized(report.to_vec()),
+    }
 }
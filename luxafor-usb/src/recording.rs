@@ -0,0 +1,231 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Recording the frames sent to a device, and replaying them later with the same timing.
+//!
+//! A recording is a compact binary file: the magic bytes `LXREC` and a format version byte,
+//! followed by one record per frame.  Each record is the time the frame was sent, in microseconds
+//! since the recording started, as a little-endian `u64`; then the length of the frame as a `u8`;
+//! then the frame itself.
+
+use crate::command::Command;
+use crate::device::Luxafor;
+use crate::transport::Transport;
+use crate::LuxaforError;
+use log::{debug, trace, warn};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryFrom,
+    io::{ErrorKind, Read, Write},
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+#[cfg(test)]
+mod tests;
+
+const MAGIC: &[u8; 5] = b"LXREC";
+const VERSION: u8 = 1;
+
+/// A frame read back from a recording.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RecordedFrame {
+    /// When the frame was sent, relative to the start of the recording.
+    pub offset: Duration,
+    pub frame: Vec<u8>,
+}
+
+/// Passes frames through to another transport, writing each one that was sent successfully to
+/// the recording.  See `Luxafor::record`.
+pub(crate) struct RecordingTransport<W: Write + Send> {
+    inner: Box<dyn Transport>,
+    writer: Mutex<W>,
+    start: Instant,
+    start_offset: Duration,
+}
+
+impl<W: Write + Send> RecordingTransport<W> {
+    pub(crate) fn new(inner: Box<dyn Transport>, writer: W, start_offset: Duration) -> Self {
+        Self {
+            inner,
+            writer: Mutex::new(writer),
+            start: Instant::now(),
+            start_offset,
+        }
+    }
+
+    fn record(&self, frame: &[u8]) -> Result<(), LuxaforError> {
+        let offset = self.start_offset + self.start.elapsed();
+        let micros = u64::try_from(offset.as_micros()).unwrap_or(u64::MAX);
+        let len = u8::try_from(frame.len()).map_err(|_| {
+            LuxaforError::Recording(format!("{}-byte frame is too long", frame.len()))
+        })?;
+        trace!("recording {:?} at {:?}", frame, offset);
+
+        let mut writer = self.writer.lock().unwrap();
+        writer
+            .write_all(&micros.to_le_bytes())
+            .and_then(|_| writer.write_all(&[len]))
+            .and_then(|_| writer.write_all(frame))
+            // Flush every frame, so that a recording cut short by Ctrl-C is still usable.
+            .and_then(|_| writer.flush())
+            .map_err(|e| LuxaforError::Recording(e.to_string()))
+    }
+}
+
+impl<W: Write + Send> Transport for RecordingTransport<W> {
+    fn write(&self, data: &[u8]) -> Result<usize, LuxaforError> {
+        let size = self.inner.write(data)?;
+        self.record(&data[..size])?;
+        Ok(size)
+    }
+
+    fn read_timeout(&self, buf: &mut [u8], timeout: i32) -> Result<usize, LuxaforError> {
+        self.inner.read_timeout(buf, timeout)
+    }
+
+    fn manufacturer_string(&self) -> Result<Option<String>, LuxaforError> {
+        self.inner.manufacturer_string()
+    }
+
+    fn product_string(&self) -> Result<Option<String>, LuxaforError> {
+        self.inner.product_string()
+    }
+
+    fn serial_number_string(&self) -> Result<Option<String>, LuxaforError> {
+        self.inner.serial_number_string()
+    }
+}
+
+impl Luxafor {
+    /// Starts recording every frame sent to the device to `writer`, which is typically a newly
+    /// created file.
+    pub fn record<W: Write + Send + 'static>(self, mut writer: W) -> Result<Self, LuxaforError> {
+        writer
+            .write_all(MAGIC)
+            .and_then(|_| writer.write_all(&[VERSION]))
+            .map_err(|e| LuxaforError::Recording(e.to_string()))?;
+
+        Ok(self.continue_recording(writer, Duration::from_secs(0)))
+    }
+
+    /// Appends to an existing recording, which `writer` should be positioned at the end of.  The
+    /// first frame sent is recorded as happening `offset` after the start of the recording, so
+    /// pass the offset of its last frame plus however long it's been since.
+    pub fn continue_recording<W: Write + Send + 'static>(
+        self,
+        writer: W,
+        offset: Duration,
+    ) -> Self {
+        debug!("recording frames starting at {:?}", offset);
+
+        Self {
+            transport: Box::new(RecordingTransport::new(self.transport, writer, offset)),
+            ..self
+        }
+    }
+}
+
+/// Reads every frame from a recording.
+pub fn read_recording<R: Read>(mut reader: R) -> Result<Vec<RecordedFrame>, LuxaforError> {
+    let err = |e: std::io::Error| LuxaforError::Recording(e.to_string());
+
+    let mut header = [0u8; 6];
+    reader.read_exact(&mut header).map_err(err)?;
+    if &header[..5] != MAGIC {
+        return Err(LuxaforError::Recording(String::from(
+            "Not a luxafor-usb recording",
+        )));
+    }
+    if header[5] != VERSION {
+        return Err(LuxaforError::Recording(format!(
+            "Unsupported recording version {}",
+            header[5]
+        )));
+    }
+
+    let mut frames = vec![];
+    loop {
+        let mut micros = [0u8; 8];
+        match reader.read_exact(&mut micros) {
+            Ok(_) => (),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(err(e)),
+        }
+
+        let mut len = [0u8; 1];
+        let mut frame = vec![];
+        if let Err(e) = reader.read_exact(&mut len).and_then(|_| {
+            frame.resize(len[0] as usize, 0);
+            reader.read_exact(&mut frame)
+        }) {
+            // A recording cut off in the middle of a frame is still worth replaying up to there.
+            warn!("recording ends partway through a frame: {}", e);
+            break;
+        }
+
+        frames.push(RecordedFrame {
+            offset: Duration::from_micros(u64::from_le_bytes(micros)),
+            frame,
+        });
+    }
+    debug!("read {} frame(s) from recording", frames.len());
+
+    Ok(frames)
+}
+
+/// Sends recorded frames to `luxafor` with their original timing, sped up (or slowed down) by
+/// `speed`; 2.0 plays back twice as fast.  Fails if `speed` isn't a positive number, or is so
+/// small that a frame's time can't be represented.
+pub fn replay(luxafor: &Luxafor, frames: &[RecordedFrame], speed: f64) -> Result<(), LuxaforError> {
+    if !(speed > 0.0 && speed.is_finite()) {
+        return Err(LuxaforError::Recording(format!(
+            "{} isn't a valid playback speed",
+            speed
+        )));
+    }
+    let start = Instant::now();
+
+    for recorded in frames {
+        // Sleep until each frame's time relative to the start, rather than for the gap since the
+        // previous frame, so time spent writing doesn't accumulate into drift.
+        let due =
+            Duration::try_from_secs_f64(recorded.offset.as_secs_f64() / speed).map_err(|_| {
+                LuxaforError::Recording(format!(
+                    "A frame recorded at {:?} is too far off to replay at {}x",
+                    recorded.offset, speed
+                ))
+            })?;
+        if let Some(wait) = due.checked_sub(start.elapsed()) {
+            thread::sleep(wait);
+        }
+
+        match Command::decode(&recorded.frame) {
+            Ok(command) => luxafor.execute(command)?,
+            Err(e) => {
+                return Err(LuxaforError::Recording(format!(
+                    "Recorded frame {:?} couldn't be decoded: {}",
+                    recorded.frame, e
+                )))
+            }
+        }
+    }
+
+    Ok(())
+}
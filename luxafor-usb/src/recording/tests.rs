@@ -0,0 +1,112 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::device::{Lights, Luxafor, PatternType, RgbColor, SimpleColor};
+use crate::recording::{read_recording, replay, RecordedFrame};
+use crate::transport::MockTransport;
+use std::{
+    io,
+    io::Write,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn records_and_replays() {
+    let buffer = SharedBuffer::default();
+    let recorded = MockTransport::new();
+    let luxafor = Luxafor::with_transport(Box::new(recorded.clone()))
+        .record(buffer.clone())
+        .unwrap();
+
+    luxafor.set_simple_color(SimpleColor::Red).unwrap();
+    thread::sleep(Duration::from_millis(20));
+    luxafor
+        .set_rgb_color(RgbColor(1, 2, 3), Lights::back())
+        .unwrap();
+    luxafor.pattern(PatternType::Police, 1).unwrap();
+
+    let bytes = buffer.0.lock().unwrap().clone();
+    let frames = read_recording(&bytes[..]).unwrap();
+    assert_eq!(
+        frames.iter().map(|f| f.frame.clone()).collect::<Vec<_>>(),
+        recorded.frames()
+    );
+    assert!(frames[1].offset - frames[0].offset >= Duration::from_millis(20));
+
+    let replayed = MockTransport::new();
+    let start = Instant::now();
+    replay(
+        &Luxafor::with_transport(Box::new(replayed.clone())),
+        &frames,
+        2.0,
+    )
+    .unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(10));
+    assert_eq!(replayed.frames(), recorded.frames());
+}
+
+#[test]
+fn replay_rejects_bad_speeds() {
+    let mock = MockTransport::new();
+    let luxafor = Luxafor::with_transport(Box::new(mock.clone()));
+    let frames = vec![RecordedFrame {
+        offset: Duration::from_secs(1),
+        frame: vec![0, 0, b'R'],
+    }];
+
+    for speed in [0.0, -1.0, f64::NAN, f64::INFINITY, 1e-20] {
+        assert!(
+            replay(&luxafor, &frames, speed).is_err(),
+            "{} was accepted",
+            speed
+        );
+    }
+    assert!(mock.frames().is_empty());
+}
+
+#[test]
+fn rejects_other_files() {
+    assert!(read_recording(&b"GIF89a"[..]).is_err());
+}
+
+#[test]
+fn reads_truncated_recording() {
+    let mut bytes = b"LXREC\x01".to_vec();
+    bytes.extend_from_slice(&1000u64.to_le_bytes());
+    bytes.extend_from_slice(&[3, 0, 0, b'G']);
+    bytes.extend_from_slice(&2000u64.to_le_bytes());
+    bytes.extend_from_slice(&[3, 0]);
+
+    let frames = read_recording(&bytes[..]).unwrap();
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].offset, Duration::from_millis(1));
+    assert_eq!(frames[0].frame, vec![0, 0, b'G']);
+}
@@ -0,0 +1,146 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! `Serialize` and `Deserialize` for the types that have a natural text form, which is available
+//! with the `serde` feature.  Colors are written as `#rrggbb` hex strings and the name-like types
+//! (simple colors, wave and pattern types, lights) by name, so serialized presets stay readable;
+//! anything `FromStr` accepts is read back.  Sets of lights are written as a list of light names.
+//! The remaining types derive their implementations where they're defined.
+
+use crate::device::{
+    BitFlags, LightSelection, Lights, PatternType, RgbColor, SimpleColor, WaveType,
+};
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeSeq,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{fmt, str::FromStr};
+
+#[cfg(test)]
+mod tests;
+
+/// Serializes a type as its `Display` form and deserializes it with `FromStr`.
+macro_rules! serialize_as_string {
+    ($($type:ty),*) => {$(
+        impl Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                <$type>::from_str(&s).map_err(de::Error::custom)
+            }
+        }
+    )*};
+}
+
+serialize_as_string!(RgbColor, SimpleColor, WaveType, PatternType, Lights);
+
+/// Written as a list of the names of the lights in the selection.  Either a list or a single
+/// string is accepted, and each may use anything `LightSelection::from_str` understands, so
+/// `["flag", "back-top"]`, `["1-3"]` and `"!back-middle"` all work.
+impl Serialize for LightSelection {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.iter().count()))?;
+        for light in self.0.iter() {
+            seq.serialize_element(&light)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for LightSelection {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(LightSelectionVisitor)
+    }
+}
+
+struct LightSelectionVisitor;
+
+impl<'de> Visitor<'de> for LightSelectionVisitor {
+    type Value = LightSelection;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list of lights, or a string such as \"flag,back-top\"")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<LightSelection, E> {
+        LightSelection::from_str(s).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<LightSelection, A::Error> {
+        let mut items = vec![];
+        while let Some(item) = seq.next_element::<String>()? {
+            items.push(item);
+        }
+        if items.is_empty() {
+            return Ok(LightSelection(BitFlags::empty()));
+        }
+        LightSelection::from_str(&items.join(",")).map_err(de::Error::custom)
+    }
+}
+
+/// For use with `#[serde(deserialize_with = "...")]` on `&'static DeviceModel` fields.  Models
+/// are serialized in full, and read back by finding the one with the same name in `MODELS`; just
+/// the name will do too.
+pub(crate) mod model {
+    use crate::model::{DeviceModel, MODELS};
+    use serde::{de, Deserialize, Deserializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ModelName {
+        Name(String),
+        Model { name: String },
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<&'static DeviceModel, D::Error> {
+        let name = match ModelName::deserialize(deserializer)? {
+            ModelName::Name(name) | ModelName::Model { name } => name,
+        };
+        MODELS
+            .iter()
+            .copied()
+            .find(|model| model.name.eq_ignore_ascii_case(&name))
+            .ok_or_else(|| de::Error::custom(format!("unknown model \"{}\"", name)))
+    }
+}
+
+/// For use with `#[serde(with = "...")]` on `BitFlags<Lights>` fields, which are written the same
+/// way as a `LightSelection`.
+pub(crate) mod lights {
+    use crate::device::{BitFlags, LightSelection, Lights};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(
+        lights: &BitFlags<Lights>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        LightSelection(*lights).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BitFlags<Lights>, D::Error> {
+        LightSelection::deserialize(deserializer).map(|selection| selection.0)
+    }
+}
@@ -0,0 +1,252 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::command::Command;
+use crate::device::{
+    DeviceDescriptor, LedState, LightSelection, Lights, PatternType, RgbColor, SimpleColor, Target,
+    WaveType,
+};
+use crate::frame::Frame;
+use crate::model;
+use crate::operation::Operation;
+use crate::recording::RecordedFrame;
+use serde_json::{from_str, json, to_value};
+use std::time::Duration;
+
+#[test]
+fn colors_are_hex_strings() {
+    assert_eq!(to_value(RgbColor(255, 128, 0)).unwrap(), json!("#ff8000"));
+    assert_eq!(
+        from_str::<RgbColor>("\"#ff8000\"").unwrap(),
+        RgbColor(255, 128, 0)
+    );
+    // Anything `RgbColor::from_str` accepts can be read back.
+    assert_eq!(
+        from_str::<RgbColor>("\"rebeccapurple\"").unwrap(),
+        RgbColor(102, 51, 153)
+    );
+    assert_eq!(
+        from_str::<RgbColor>("\"rgb(0 0 255)\"").unwrap(),
+        RgbColor::blue()
+    );
+
+    let error = from_str::<RgbColor>("\"#ff80f\"").unwrap_err().to_string();
+    assert!(error.contains("Invalid color"), "{}", error);
+}
+
+#[test]
+fn names_round_trip() {
+    assert_eq!(to_value(SimpleColor::Magenta).unwrap(), json!("magenta"));
+    assert_eq!(
+        to_value(WaveType::OverlappingShort).unwrap(),
+        json!("overlapping-short")
+    );
+    assert_eq!(
+        to_value(PatternType::RainbowWave).unwrap(),
+        json!("rainbow-wave")
+    );
+    assert_eq!(to_value(Lights::BackTop).unwrap(), json!("back-top"));
+    assert_eq!(to_value(Target::TabTopLed).unwrap(), json!("tab-top-led"));
+
+    assert_eq!(
+        from_str::<SimpleColor>("\"Off\"").unwrap(),
+        SimpleColor::Off
+    );
+    assert_eq!(from_str::<WaveType>("\"2\"").unwrap(), WaveType::Long);
+    assert_eq!(
+        from_str::<PatternType>("\"police\"").unwrap(),
+        PatternType::Police
+    );
+    assert_eq!(from_str::<Lights>("\"back-top\"").unwrap(), Lights::BackTop);
+    assert_eq!(from_str::<Target>("\"back\"").unwrap(), Target::Back);
+    assert!(from_str::<SimpleColor>("\"orange\"").is_err());
+}
+
+#[test]
+fn light_selections_are_name_lists() {
+    let selection = LightSelection(Lights::FlagTop | Lights::BackTop);
+    assert_eq!(
+        to_value(selection).unwrap(),
+        json!(["flag-top", "back-top"])
+    );
+    assert_eq!(
+        to_value(LightSelection(Lights::all()))
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .len(),
+        6
+    );
+
+    let parse = |s: &str| from_str::<LightSelection>(s).unwrap().0;
+    assert_eq!(parse("[\"flag-top\", \"back-top\"]"), selection.0);
+    assert_eq!(parse("[\"flag\", \"6\"]"), Lights::flag() | Lights::BackTop);
+    assert_eq!(parse("[\"1-2\"]"), Lights::FlagBottom | Lights::FlagMiddle);
+    assert_eq!(
+        parse("\"!back-middle\""),
+        Lights::all() & !Lights::BackMiddle
+    );
+    assert!(parse("[]").is_empty());
+    assert!(from_str::<LightSelection>("[\"back_top\"]").is_err());
+
+    for bits in 0..64u8 {
+        let selection = LightSelection(crate::device::BitFlags::from_bits(bits).unwrap());
+        let json = serde_json::to_string(&selection).unwrap();
+        assert_eq!(
+            from_str::<LightSelection>(&json).unwrap(),
+            selection,
+            "{}",
+            json
+        );
+    }
+}
+
+#[test]
+fn operations_capture_the_whole_call() {
+    let strobe = Operation::Strobe {
+        color: RgbColor::red(),
+        lights: Lights::FlagTop.into(),
+        speed: 20,
+        repeat: 5,
+    };
+    let value = json!({
+        "operation": "strobe",
+        "color": "#ff0000",
+        "lights": ["flag-top"],
+        "speed": 20,
+        "repeat": 5
+    });
+    assert_eq!(to_value(strobe).unwrap(), value);
+    assert_eq!(serde_json::from_value::<Operation>(value).unwrap(), strobe);
+
+    // Leaving out the lights means all of them.
+    assert_eq!(
+        from_str::<Operation>(r#"{"operation": "rgb-color", "color": "white"}"#).unwrap(),
+        Operation::RgbColor {
+            color: RgbColor::white(),
+            lights: Lights::all()
+        }
+    );
+
+    for operation in &[
+        Operation::SimpleColor {
+            color: SimpleColor::Green,
+        },
+        Operation::FadeToColor {
+            color: RgbColor(1, 2, 3),
+            lights: Lights::back(),
+            fade_time: 100,
+        },
+        Operation::Wave {
+            color: RgbColor::blue(),
+            wave_type: WaveType::Short,
+            speed: 3,
+            repeat: 4,
+        },
+        Operation::Pattern {
+            pattern_type: PatternType::Random3,
+            repeat: 2,
+        },
+    ] {
+        let json = serde_json::to_string(operation).unwrap();
+        assert_eq!(
+            &from_str::<Operation>(&json).unwrap(),
+            operation,
+            "{}",
+            json
+        );
+    }
+}
+
+#[test]
+fn commands_and_states_round_trip() {
+    let commands = [
+        Command::SimpleColor(SimpleColor::Red),
+        Command::RgbColor {
+            color: RgbColor::cyan(),
+            target: Target::Tab,
+        },
+        Command::Wave {
+            color: RgbColor::white(),
+            wave_type: WaveType::Long,
+            speed: 1,
+            repeat: 2,
+        },
+        Command::GetStatus,
+    ];
+    for command in &commands {
+        let json = serde_json::to_string(command).unwrap();
+        assert_eq!(&from_str::<Command>(&json).unwrap(), command, "{}", json);
+    }
+    assert_eq!(
+        to_value(commands[1]).unwrap(),
+        json!({"rgb-color": {"color": "#00ffff", "target": "tab"}})
+    );
+
+    let state = [
+        LedState::Unknown,
+        LedState::Color(RgbColor::red()),
+        LedState::Indeterminate,
+        LedState::Unknown,
+        LedState::Unknown,
+        LedState::Unknown,
+    ];
+    let json = serde_json::to_string(&state).unwrap();
+    assert_eq!(from_str::<[LedState; 6]>(&json).unwrap(), state);
+}
+
+#[test]
+fn descriptors_and_recorded_frames_round_trip() {
+    let descriptor = DeviceDescriptor {
+        path: String::from("/dev/hidraw3"),
+        serial_number: Some(String::from("1234")),
+        product_string: Some(String::from("LUXAFOR ORB")),
+        release_number: 0x0100,
+        model: &model::ORB,
+    };
+    let json = serde_json::to_string(&descriptor).unwrap();
+    let read = from_str::<DeviceDescriptor>(&json).unwrap();
+    assert_eq!(read.path, descriptor.path);
+    assert_eq!(read.serial_number, descriptor.serial_number);
+    assert_eq!(read.model, &model::ORB);
+
+    // The model's name alone is enough, and an unknown one is an error.
+    let mut value = to_value(&descriptor).unwrap();
+    value["model"] = json!("luxafor flag");
+    assert_eq!(
+        serde_json::from_value::<DeviceDescriptor>(value.clone())
+            .unwrap()
+            .model,
+        &model::FLAG
+    );
+    value["model"] = json!("Luxafor Lamp");
+    assert!(serde_json::from_value::<DeviceDescriptor>(value).is_err());
+
+    let frame = RecordedFrame {
+        offset: Duration::from_micros(1500),
+        frame: vec![0, 0, b'R'],
+    };
+    let json = serde_json::to_string(&frame).unwrap();
+    assert_eq!(from_str::<RecordedFrame>(&json).unwrap(), frame);
+}
+
+#[test]
+fn frames_are_lists_of_colors() {
+    let frame = Frame::solid(RgbColor::red()).with(Lights::back(), RgbColor::blue());
+    let value = json!(["#ff0000", "#ff0000", "#ff0000", "#0000ff", "#0000ff", "#0000ff"]);
+    assert_eq!(to_value(frame).unwrap(), value);
+    assert_eq!(serde_json::from_value::<Frame>(value).unwrap(), frame);
+}
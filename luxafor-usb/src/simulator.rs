@@ -0,0 +1,449 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! A `Transport` that draws a virtual flag in the terminal, for working on effects without a
+//! device plugged in.
+//!
+//! The simulator understands every command the library sends.  Colors are shown as they're set,
+//! and the hardware effects (fades, strobes, waves and patterns) are played back in software,
+//! timed with `Calibration::default()`.  The built-in patterns are the firmware's own, so they
+//! can only be approximated.
+
+use crate::calibration::Calibration;
+use crate::command::Command;
+use crate::device::{BitFlags, Lights, PatternType, RgbColor, WaveType};
+use crate::firmware::{pattern_color, repeats, wave_level, PATTERN_PERIOD};
+use crate::protocol;
+use crate::transport::Transport;
+use crate::LuxaforError;
+use std::{
+    collections::VecDeque,
+    fmt::Write as _,
+    io::{self, Write},
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// How often the virtual flag is redrawn while it's changing.
+const FRAME_INTERVAL: Duration = Duration::from_millis(33);
+
+/// A `Transport` that draws the six LEDs as colored blocks, using 24-bit ANSI color escapes.
+///
+/// The flag is redrawn in place on a single line, flag side first and then the back, each from
+/// bottom to top.  Like `MockTransport`, clones share the same virtual flag.  Once the last one
+/// is dropped, any effect still running is played out before the final colors are left on screen.
+///
+/// ```no_run
+/// use luxafor_usb::device::{Lights, Luxafor, RgbColor};
+/// use luxafor_usb::simulator::SimulatorTransport;
+///
+/// let luxafor = Luxafor::with_transport(Box::new(SimulatorTransport::new()));
+/// luxafor.strobe(RgbColor::red(), Lights::flag(), 20, 5).unwrap();
+/// ```
+#[derive(Clone)]
+pub struct SimulatorTransport {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    state: Arc<Mutex<State>>,
+    // Dropped to tell the renderer to finish up.
+    stop: Mutex<Option<Sender<()>>>,
+    renderer: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl SimulatorTransport {
+    /// Creates a simulator that draws on standard error.
+    pub fn new() -> Self {
+        Self::with_output(Box::new(io::stderr()), Calibration::default())
+    }
+
+    /// Creates a simulator that draws on `output`, timing effects with `calibration`.
+    pub fn with_output(output: Box<dyn Write + Send>, calibration: Calibration) -> Self {
+        let state = Arc::new(Mutex::new(State::new(calibration)));
+        let (stop, stopped) = mpsc::channel();
+
+        let renderer_state = state.clone();
+        let renderer = thread::Builder::new()
+            .name(String::from("luxafor-simulator"))
+            .spawn(move || render_loop(&renderer_state, stopped, output))
+            .ok();
+
+        SimulatorTransport {
+            shared: Arc::new(Shared {
+                state,
+                stop: Mutex::new(Some(stop)),
+                renderer: Mutex::new(renderer),
+            }),
+        }
+    }
+
+    /// Returns the colors the virtual LEDs are showing right now, indexed as in `Lights::index`.
+    pub fn colors(&self) -> [RgbColor; 6] {
+        self.shared.state.lock().unwrap().colors_at(Instant::now())
+    }
+}
+
+impl Default for SimulatorTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        self.stop.lock().unwrap().take();
+        if let Some(renderer) = self.renderer.lock().unwrap().take() {
+            let _ = renderer.join();
+        }
+    }
+}
+
+impl Transport for SimulatorTransport {
+    fn write(&self, data: &[u8]) -> Result<usize, LuxaforError> {
+        let command = Command::decode(data).map_err(|e| LuxaforError::Write(e.to_string()))?;
+        self.shared
+            .state
+            .lock()
+            .unwrap()
+            .apply(command, Instant::now());
+        Ok(data.len())
+    }
+
+    fn read_timeout(&self, buf: &mut [u8], _timeout: i32) -> Result<usize, LuxaforError> {
+        match self.shared.state.lock().unwrap().responses.pop_front() {
+            Some(report) => {
+                let size = report.len().min(buf.len());
+                buf[..size].copy_from_slice(&report[..size]);
+                Ok(size)
+            }
+            None => Ok(0),
+        }
+    }
+
+    fn manufacturer_string(&self) -> Result<Option<String>, LuxaforError> {
+        Ok(Some(String::from("luxafor-usb")))
+    }
+
+    fn product_string(&self) -> Result<Option<String>, LuxaforError> {
+        Ok(Some(String::from("LUXAFOR FLAG (simulated)")))
+    }
+
+    fn serial_number_string(&self) -> Result<Option<String>, LuxaforError> {
+        Ok(Some(String::from("SIMULATOR")))
+    }
+}
+
+/// Redraws the flag whenever it changes, until `stopped` is disconnected and every effect has
+/// finished.
+fn render_loop(
+    state: &Mutex<State>,
+    stopped: mpsc::Receiver<()>,
+    mut output: Box<dyn Write + Send>,
+) {
+    let mut finishing = false;
+    let mut last = None;
+
+    loop {
+        if finishing {
+            thread::sleep(FRAME_INTERVAL);
+        } else if let Err(RecvTimeoutError::Disconnected) = stopped.recv_timeout(FRAME_INTERVAL) {
+            finishing = true;
+        }
+
+        let now = Instant::now();
+        let (colors, idle) = {
+            let mut state = state.lock().unwrap();
+            state.retire(now);
+            (state.colors_at(now), state.effects.is_empty())
+        };
+        if last != Some(colors) {
+            let _ = write!(output, "\r{}\x1b[K", render(&colors));
+            let _ = output.flush();
+            last = Some(colors);
+        }
+
+        if finishing && idle {
+            break;
+        }
+    }
+
+    if last.is_some() {
+        let _ = writeln!(output);
+    }
+}
+
+/// Draws the LEDs as a line of colored blocks.
+fn render(colors: &[RgbColor; 6]) -> String {
+    let mut line = String::new();
+    for (side, leds) in ["flag", "back"].iter().zip(colors.chunks(3)) {
+        let _ = write!(line, "{} ", side);
+        for &color in leds {
+            if color == RgbColor::off() {
+                // Black blocks vanish on a dark terminal, so show the LED's outline instead.
+                line.push_str("\x1b[38;2;96;96;96m░░\x1b[0m ");
+            } else {
+                let RgbColor(r, g, b) = color;
+                let _ = write!(line, "\x1b[38;2;{};{};{}m██\x1b[0m ", r, g, b);
+            }
+        }
+        line.push(' ');
+    }
+    line.truncate(line.trim_end().len());
+    line
+}
+
+/// The virtual flag: the colors the LEDs settle on, and the effects playing over them.
+struct State {
+    calibration: Calibration,
+    base: [RgbColor; 6],
+    effects: Vec<Effect>,
+    last_command: Option<u8>,
+    responses: VecDeque<Vec<u8>>,
+}
+
+struct Effect {
+    lights: BitFlags<Lights>,
+    start: Instant,
+    kind: EffectKind,
+}
+
+enum EffectKind {
+    Fade {
+        from: [RgbColor; 6],
+        to: RgbColor,
+        duration: Duration,
+    },
+    Strobe {
+        color: RgbColor,
+        period: Duration,
+        repeat: u32,
+    },
+    Wave {
+        color: RgbColor,
+        wave_type: WaveType,
+        from: [RgbColor; 6],
+        period: Duration,
+        repeat: u32,
+    },
+    Pattern {
+        pattern_type: PatternType,
+        repeat: u32,
+    },
+}
+
+impl State {
+    fn new(calibration: Calibration) -> Self {
+        State {
+            calibration,
+            base: [RgbColor::off(); 6],
+            effects: vec![],
+            last_command: None,
+            responses: VecDeque::new(),
+        }
+    }
+
+    /// Carries out `command` as the firmware would, as of `now`.
+    fn apply(&mut self, command: Command, now: Instant) {
+        let shown = self.colors_at(now);
+        let effect = |lights, kind| Effect {
+            lights,
+            start: now,
+            kind,
+        };
+
+        match command {
+            Command::SimpleColor(color) => self.set(Lights::all(), color.into()),
+            Command::RgbColor { color, target } => self.set(target.lights(), color),
+            Command::FadeToColor {
+                color,
+                target,
+                fade_time,
+            } => {
+                self.set(target.lights(), color);
+                let duration = self.calibration.fade.duration(fade_time);
+                self.effects.push(effect(
+                    target.lights(),
+                    EffectKind::Fade {
+                        from: shown,
+                        to: color,
+                        duration,
+                    },
+                ));
+            }
+            Command::Strobe {
+                color,
+                target,
+                speed,
+                repeat,
+            } => {
+                self.clear(target.lights());
+                let period = self.calibration.strobe.duration(speed);
+                self.effects.push(effect(
+                    target.lights(),
+                    EffectKind::Strobe {
+                        color,
+                        period,
+                        repeat: repeats(repeat),
+                    },
+                ));
+            }
+            Command::Wave {
+                color,
+                wave_type,
+                speed,
+                repeat,
+            } => {
+                self.clear(Lights::all());
+                let period = self.calibration.wave.duration(speed);
+                self.effects.push(effect(
+                    Lights::all(),
+                    EffectKind::Wave {
+                        color,
+                        wave_type,
+                        from: shown,
+                        period,
+                        repeat: repeats(repeat),
+                    },
+                ));
+            }
+            Command::Pattern {
+                pattern_type,
+                repeat,
+            } => {
+                self.clear(Lights::all());
+                self.effects.push(effect(
+                    Lights::all(),
+                    EffectKind::Pattern {
+                        pattern_type,
+                        repeat: repeats(repeat),
+                    },
+                ));
+            }
+            Command::GetStatus => {
+                // Unlike most firmware, the simulator does say what it's doing.
+                if let Some(command) = self.last_command {
+                    self.retire(now);
+                    let busy = !self.effects.is_empty();
+                    let mut report = vec![0; protocol::REPORT_SIZE];
+                    report[0] = command;
+                    report[1] = busy as u8;
+                    self.responses.push_back(report);
+                }
+                return;
+            }
+        }
+
+        self.last_command = Some(command.encode()[1]);
+    }
+
+    /// Sets `lights` to `color`, stopping any effects running on them.
+    fn set(&mut self, lights: BitFlags<Lights>, color: RgbColor) {
+        self.clear(lights);
+        for light in lights.iter() {
+            self.base[light.index()] = color;
+        }
+    }
+
+    /// Stops any effects running on `lights`.
+    fn clear(&mut self, lights: BitFlags<Lights>) {
+        for effect in self.effects.iter_mut() {
+            effect.lights &= !lights;
+        }
+        self.effects.retain(|effect| !effect.lights.is_empty());
+    }
+
+    /// Forgets effects that have finished by `now`.
+    fn retire(&mut self, now: Instant) {
+        self.effects
+            .retain(|effect| now.saturating_duration_since(effect.start) < effect.duration());
+    }
+
+    /// Works out what each LED shows at `now`.
+    fn colors_at(&self, now: Instant) -> [RgbColor; 6] {
+        let mut colors = self.base;
+        for effect in &self.effects {
+            let elapsed = now.saturating_duration_since(effect.start);
+            if elapsed >= effect.duration() {
+                continue;
+            }
+            for light in effect.lights.iter() {
+                let index = light.index();
+                colors[index] = effect.color(index, elapsed);
+            }
+        }
+        colors
+    }
+}
+
+impl Effect {
+    fn duration(&self) -> Duration {
+        match self.kind {
+            EffectKind::Fade { duration, .. } => duration,
+            EffectKind::Strobe { period, repeat, .. } => period * repeat,
+            EffectKind::Wave { period, repeat, .. } => period * repeat,
+            EffectKind::Pattern { repeat, .. } => PATTERN_PERIOD * repeat,
+        }
+    }
+
+    /// The color the LED at `index` shows `elapsed` into the effect.
+    fn color(&self, index: usize, elapsed: Duration) -> RgbColor {
+        // How far through the current repetition the effect is, from 0 up to 1.
+        let phase = |period: Duration| {
+            if period.as_nanos() == 0 {
+                1.0
+            } else {
+                (elapsed.as_nanos() % period.as_nanos()) as f32 / period.as_nanos() as f32
+            }
+        };
+        match self.kind {
+            EffectKind::Fade { from, to, duration } => {
+                let t = elapsed.as_secs_f32() / duration.as_secs_f32();
+                from[index].lerp(to, t)
+            }
+            EffectKind::Strobe { color, period, .. } => {
+                if phase(period) < 0.5 {
+                    color
+                } else {
+                    RgbColor::off()
+                }
+            }
+            EffectKind::Wave {
+                color,
+                wave_type,
+                from,
+                period,
+                ..
+            } => {
+                let background = match wave_type {
+                    WaveType::Short | WaveType::Long => RgbColor::off(),
+                    WaveType::OverlappingShort | WaveType::OverlappingLong => from[index],
+                };
+                background.lerp(color, wave_level(wave_type, phase(period), index))
+            }
+            EffectKind::Pattern { pattern_type, .. } => {
+                pattern_color(pattern_type, phase(PATTERN_PERIOD), index)
+            }
+        }
+    }
+}
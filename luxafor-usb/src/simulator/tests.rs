@@ -0,0 +1,229 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::animation::ms;
+use crate::calibration::Calibration;
+use crate::command::Command;
+use crate::device::{
+    DeviceResponse, Lights, Luxafor, PatternType, RgbColor, SimpleColor, Target, WaveType,
+};
+use crate::model;
+use crate::simulator::{render, SimulatorTransport, State};
+use std::{
+    io::{self, Write},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// A state whose timings are easy to work with: every fade takes `fade_time` x 10ms, a flash
+/// `speed` x 10ms, and a wave pass `speed` x 100ms.
+fn state() -> State {
+    let calibration = "fade 0 0ms\nfade 100 1000ms\n\
+                       strobe 0 0ms\nstrobe 100 1000ms\n\
+                       wave 0 0ms\nwave 100 10000ms\n";
+    State::new(calibration.parse::<Calibration>().unwrap())
+}
+
+#[derive(Clone, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn colors_are_set_on_their_targets() {
+    let mut state = state();
+    let now = Instant::now();
+
+    state.apply(Command::SimpleColor(SimpleColor::Green), now);
+    state.apply(
+        Command::RgbColor {
+            color: RgbColor(1, 2, 3),
+            target: Target::BackTopLed,
+        },
+        now,
+    );
+
+    let colors = state.colors_at(now);
+    assert_eq!(colors[..5], [RgbColor::green(); 5]);
+    assert_eq!(colors[Lights::BackTop.index()], RgbColor(1, 2, 3));
+}
+
+#[test]
+fn fades_move_towards_the_new_color() {
+    let mut state = state();
+    let start = Instant::now();
+
+    state.apply(Command::SimpleColor(SimpleColor::White), start);
+    state.apply(
+        Command::FadeToColor {
+            color: RgbColor::off(),
+            target: Target::Tab,
+            fade_time: 100,
+        },
+        start,
+    );
+
+    assert_eq!(state.colors_at(start)[0], RgbColor::white());
+    assert_eq!(state.colors_at(start + ms(500))[0], RgbColor(128, 128, 128));
+    assert_eq!(state.colors_at(start + ms(500))[3], RgbColor::white());
+    assert_eq!(state.colors_at(start + ms(1000))[0], RgbColor::off());
+
+    state.retire(start + ms(1000));
+    assert!(state.effects.is_empty());
+}
+
+#[test]
+fn strobes_flash_and_then_stop() {
+    let mut state = state();
+    let start = Instant::now();
+
+    state.apply(Command::SimpleColor(SimpleColor::Blue), start);
+    state.apply(
+        Command::Strobe {
+            color: RgbColor::red(),
+            target: Target::TabTopLed,
+            speed: 10,
+            repeat: 3,
+        },
+        start,
+    );
+
+    let top = Lights::FlagTop.index();
+    assert_eq!(state.colors_at(start + ms(20))[top], RgbColor::red());
+    assert_eq!(state.colors_at(start + ms(70))[top], RgbColor::off());
+    assert_eq!(state.colors_at(start + ms(220))[top], RgbColor::red());
+    assert_eq!(state.colors_at(start + ms(70))[0], RgbColor::blue());
+    // Once it's done, the LED goes back to the color it had.
+    assert_eq!(state.colors_at(start + ms(300))[top], RgbColor::blue());
+
+    // Setting a color stops the strobe.
+    state.apply(
+        Command::RgbColor {
+            color: RgbColor::white(),
+            target: Target::All,
+        },
+        start + ms(50),
+    );
+    assert_eq!(state.colors_at(start + ms(70))[top], RgbColor::white());
+    assert!(state.effects.is_empty());
+}
+
+#[test]
+fn waves_travel_up_the_flag() {
+    let mut state = state();
+    let start = Instant::now();
+
+    state.apply(
+        Command::Wave {
+            color: RgbColor::red(),
+            wave_type: WaveType::Short,
+            speed: 10,
+            repeat: 1,
+        },
+        start,
+    );
+
+    let brightest = |at: u64| {
+        let colors = state.colors_at(start + ms(at));
+        (0..3).max_by_key(|&i| colors[i].0).unwrap()
+    };
+    assert_eq!(brightest(125), 0);
+    assert_eq!(brightest(375), 1);
+    assert_eq!(brightest(625), 2);
+    assert_eq!(state.colors_at(start + ms(125))[3], RgbColor::red());
+    assert_eq!(state.colors_at(start + ms(1000)), [RgbColor::off(); 6]);
+}
+
+#[test]
+fn patterns_run_for_their_repeats() {
+    let mut state = state();
+    let start = Instant::now();
+
+    state.apply(
+        Command::Pattern {
+            pattern_type: PatternType::Police,
+            repeat: 2,
+        },
+        start,
+    );
+
+    let colors = state.colors_at(start + ms(10));
+    assert_eq!(colors[0], RgbColor::red());
+    assert_eq!(colors[3], RgbColor::off());
+    state.retire(start + Duration::from_secs(5));
+    assert_eq!(state.effects.len(), 1);
+    state.retire(start + Duration::from_secs(6));
+    assert!(state.effects.is_empty());
+}
+
+#[test]
+fn status_reports_whether_an_effect_is_running() {
+    let simulator = SimulatorTransport::with_output(Box::new(io::sink()), Calibration::default());
+    let luxafor = Luxafor::with_transport(Box::new(simulator.clone()));
+    assert_eq!(luxafor.model(), &model::FLAG);
+
+    assert_eq!(luxafor.status().unwrap(), DeviceResponse::Unknown);
+    luxafor
+        .strobe(RgbColor::red(), Lights::all(), 255, 255)
+        .unwrap();
+    assert_eq!(
+        luxafor.status().unwrap(),
+        DeviceResponse::Busy { command: 3 }
+    );
+    luxafor.set_simple_color(SimpleColor::Cyan).unwrap();
+    assert_eq!(
+        luxafor.status().unwrap(),
+        DeviceResponse::Done { command: 0 }
+    );
+    assert_eq!(simulator.colors(), [RgbColor::cyan(); 6]);
+}
+
+#[test]
+fn the_flag_is_drawn_in_truecolor() {
+    let mut colors = [RgbColor::off(); 6];
+    colors[0] = RgbColor(255, 128, 0);
+    let line = render(&colors);
+
+    assert!(
+        line.starts_with("flag \x1b[38;2;255;128;0m██\x1b[0m "),
+        "{:?}",
+        line
+    );
+    assert_eq!(line.matches("░░").count(), 5);
+    assert!(line.contains(" back "));
+
+    let output = Output::default();
+    let simulator =
+        SimulatorTransport::with_output(Box::new(output.clone()), Calibration::default());
+    let luxafor = Luxafor::with_transport(Box::new(simulator));
+    luxafor
+        .set_rgb_color(RgbColor::blue(), Lights::back())
+        .unwrap();
+    drop(luxafor);
+
+    let written = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+    assert!(written.contains("\x1b[38;2;0;0;255m██"), "{:?}", written);
+    assert!(written.ends_with('\n'));
+}
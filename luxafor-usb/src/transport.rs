@@ -0,0 +1,154 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::LuxaforError;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+/// The channel over which a `Luxafor` talks to its hardware.
+///
+/// The real implementations are the backends' (see `backend`), but anything that can accept
+/// frames and hand back reports will do, which lets the code that drives a flag run without one
+/// plugged in.
+pub trait Transport: Send {
+    /// Writes a single frame (including the leading report ID) to the device, returning the
+    /// number of bytes written.
+    fn write(&self, data: &[u8]) -> Result<usize, LuxaforError>;
+
+    /// Reads a single input report into `buf`, waiting at most `timeout` milliseconds (-1 waits
+    /// forever).  Returns the number of bytes read, which is 0 if the timeout elapsed.
+    fn read_timeout(&self, buf: &mut [u8], timeout: i32) -> Result<usize, LuxaforError>;
+
+    fn manufacturer_string(&self) -> Result<Option<String>, LuxaforError>;
+
+    fn product_string(&self) -> Result<Option<String>, LuxaforError>;
+
+    fn serial_number_string(&self) -> Result<Option<String>, LuxaforError>;
+}
+
+#[derive(Debug)]
+struct MockState {
+    frames: Vec<Vec<u8>>,
+    responses: VecDeque<Vec<u8>>,
+    product_string: String,
+}
+
+impl Default for MockState {
+    fn default() -> Self {
+        Self {
+            frames: vec![],
+            responses: VecDeque::new(),
+            product_string: String::from("LUXAFOR FLAG"),
+        }
+    }
+}
+
+/// An in-memory `Transport` that records every frame written to it, for use in tests.
+///
+/// `MockTransport` is cheaply cloneable, and every clone shares the same recording, so a test can
+/// hand one clone to `Luxafor::with_transport` and keep another to inspect what was sent:
+///
+/// ```
+/// use luxafor_usb::device::{Luxafor, SimpleColor};
+/// use luxafor_usb::transport::MockTransport;
+///
+/// let mock = MockTransport::new();
+/// let luxafor = Luxafor::with_transport(Box::new(mock.clone()));
+/// luxafor.set_simple_color(SimpleColor::Red).unwrap();
+///
+/// assert_eq!(mock.frames(), vec![vec![0, 0, b'R']]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MockTransport {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a `MockTransport` that reports the given USB product string, which determines the
+    /// device model `Luxafor::with_transport` detects.
+    pub fn with_product_string(product_string: &str) -> Self {
+        let mock = Self::default();
+        mock.state.lock().unwrap().product_string = product_string.to_string();
+        mock
+    }
+
+    /// Returns every frame written so far, oldest first.
+    pub fn frames(&self) -> Vec<Vec<u8>> {
+        self.state.lock().unwrap().frames.clone()
+    }
+
+    /// Returns the frames written so far and forgets them.
+    pub fn take_frames(&self) -> Vec<Vec<u8>> {
+        std::mem::take(&mut self.state.lock().unwrap().frames)
+    }
+
+    /// Queues a report to be handed back by a later `read_timeout`.  Reports are returned in the
+    /// order they were queued; once the queue is empty, reads time out and return 0 bytes, which
+    /// is what the hardware does.
+    pub fn push_response(&self, report: &[u8]) {
+        self.state
+            .lock()
+            .unwrap()
+            .responses
+            .push_back(report.to_vec());
+    }
+}
+
+impl Transport for MockTransport {
+    fn write(&self, data: &[u8]) -> Result<usize, LuxaforError> {
+        self.state.lock().unwrap().frames.push(data.to_vec());
+        Ok(data.len())
+    }
+
+    fn read_timeout(&self, buf: &mut [u8], _timeout: i32) -> Result<usize, LuxaforError> {
+        match self.state.lock().unwrap().responses.pop_front() {
+            Some(report) => {
+                let size = report.len().min(buf.len());
+                buf[..size].copy_from_slice(&report[..size]);
+                Ok(size)
+            }
+            None => Ok(0),
+        }
+    }
+
+    fn manufacturer_string(&self) -> Result<Option<String>, LuxaforError> {
+        Ok(Some(String::from("Microchip Technology Inc.")))
+    }
+
+    fn product_string(&self) -> Result<Option<String>, LuxaforError> {
+        Ok(Some(self.state.lock().unwrap().product_string.clone()))
+    }
+
+    fn serial_number_string(&self) -> Result<Option<String>, LuxaforError> {
+        Ok(Some(String::from("MOCK")))
+    }
+}
+
+/// A `Luxafor` talking to a new `MockTransport`, and a clone of the mock to see what it was sent.
+#[cfg(test)]
+pub(crate) fn mock_luxafor() -> (crate::device::Luxafor, MockTransport) {
+    let mock = MockTransport::new();
+    (
+        crate::device::Luxafor::with_transport(Box::new(mock.clone())),
+        mock,
+    )
+}
@@ -14,97 +14,103 @@
    along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::device::{Lights, Luxafor, PatternType, RgbColor, SimpleColor, Target, WaveType};
+use crate::command::Command;
+use crate::device::{
+    DeviceResponse, LedState, Lights, Luxafor, PatternType, RgbColor, SimpleColor, Target, WaveType,
+};
+use crate::frame::{self, Frame, Write};
+use crate::model::Capability;
 use crate::protocol;
+use crate::LuxaforError;
 use enumflags2::BitFlags;
-use log::{debug, info, trace, warn};
+use log::{debug, info, trace};
+
+#[cfg(test)]
+mod tests;
 
 pub(crate) const LUXAFOR_VID: u16 = 0x04D8;
 pub(crate) const LUXAFOR_PID: u16 = 0xF372;
 
-fn lights_to_targets(lights: BitFlags<Lights>) -> Vec<Target> {
-    if lights.is_empty() {
-        vec![]
-    } else if lights.is_all() {
-        vec![Target::All]
-    } else if lights.bits() == Lights::back().bits() {
-        vec![Target::Back]
-    } else if lights.bits() == Lights::flag().bits() {
-        vec![Target::Tab]
-    } else {
-        lights
-            .iter()
-            .map(|light| match light {
+/// How long `status` waits for the device to reply, in milliseconds.
+const STATUS_TIMEOUT_MS: i32 = 500;
+
+/// Covers `lights` with as few targets as possible: `Target::All` for every LED, `Tab` or `Back`
+/// for each side that's wholly selected, and single LEDs for the rest.  Targets never reach
+/// outside the selection, so this is also the smallest exact cover.
+pub(crate) fn lights_to_targets(lights: BitFlags<Lights>) -> Vec<Target> {
+    if lights.is_all() {
+        return vec![Target::All];
+    }
+
+    let mut targets = Vec::new();
+    for &(side, side_target) in &[
+        (Lights::flag(), Target::Tab),
+        (Lights::back(), Target::Back),
+    ] {
+        if lights.contains(side) {
+            targets.push(side_target);
+        } else {
+            targets.extend((lights & side).iter().map(|light| match light {
                 Lights::FlagBottom => Target::TabBottomLed,
                 Lights::FlagMiddle => Target::TabMiddleLed,
                 Lights::FlagTop => Target::TabTopLed,
                 Lights::BackBottom => Target::BackBottomLed,
                 Lights::BackMiddle => Target::BackMiddleLed,
                 Lights::BackTop => Target::BackTopLed,
-            })
-            .collect()
+            }));
+        }
     }
+    targets
 }
 
-// We don't bother actually doing anything with the responses in any of these functions because
+// We don't bother actually doing anything with the responses in most of these functions because
 // the Luxafor flag (or at least, the one I have; maybe they fixed this in a later hardware
 // revision) doesn't actually return the response the protocol documentation (that I was able to
 // find, such as it is) says it will: we read zero bytes from it after sending a command, but we're
 // _supposed to_ get a response telling us the command that's executing, or if there's one already
-// executing that we need to wait to finish before sending another.
+// executing that we need to wait to finish before sending another.  Since the hardware we know of
+// never replies, commands only pick up a response if one is already waiting rather than stalling
+// every write on a timeout; `status` is the one place that actually waits for an answer.
 impl Luxafor {
     /// Sets one of the Luxafor "simple" colors (red, green, blue, cyan, magenta, yellow, white,
     /// or off).  This command always affects all lights.
-    pub fn set_simple_color(&self, color: SimpleColor) {
+    pub fn set_simple_color(&self, color: SimpleColor) -> Result<(), LuxaforError> {
         info!("sending 'set simple color' {:?}", color);
+        self.require(Capability::SimpleColor, "Setting a simple color")?;
 
-        let cmd = protocol::set_simple_color(color);
-        debug!("writing to {:?}", &self);
-
-        match &self.hid_device.write(&cmd) {
-            Ok(size) => {
-                trace!("wrote {} bytes: {:?}", size, cmd);
+        debug!("writing command to {:?}", &self);
+        self.send(&protocol::set_simple_color(color))?;
+        self.update_state(Lights::all(), LedState::Color(color.into()));
 
-                let mut buf = Vec::<u8>::new();
-                match &self.hid_device.read_timeout(&mut buf, -1) {
-                    Ok(size) => {
-                        trace!("read {} bytes: {:?}", size, buf);
-                    }
-                    Err(e) => warn!("Error reading response: {}", e),
-                }
-            }
-            Err(e) => warn!("Error writing command: {}", e),
-        }
+        Ok(())
     }
 
     /// Sets an arbitrary RGB color on the specified lights.
-    pub fn set_rgb_color(&self, color: RgbColor, lights: BitFlags<Lights>) {
+    pub fn set_rgb_color(
+        &self,
+        color: RgbColor,
+        lights: BitFlags<Lights>,
+    ) -> Result<(), LuxaforError> {
         info!("sending 'set RGB color' {:?} {:?}", color, lights);
+        self.require(Capability::RgbColor, "Setting an RGB color")?;
 
         let targets = lights_to_targets(lights);
         debug!("mapped {:?} to targets {:?}", lights, targets);
+        self.require_targets(&targets)?;
 
         debug!(
             "writing command{} to {:?}",
             if targets.len() > 1 { "s" } else { "" },
             &self
         );
-        targets
-            .iter()
-            .map(|&target| protocol::set_rgb_color(color, target))
-            .for_each(|cmd| match &self.hid_device.write(&cmd) {
-                Ok(size) => {
-                    trace!("wrote {} bytes: {:?}", size, cmd);
-                    let mut buf = Vec::<u8>::new();
-                    match &self.hid_device.read_timeout(&mut buf, -1) {
-                        Ok(size) => {
-                            trace!("read {} bytes: {:?}", size, buf);
-                        }
-                        Err(e) => warn!("Error reading response: {}", e),
-                    }
-                }
-                Err(e) => warn!("Error reading response: {}", e),
-            });
+        self.send_all(
+            targets
+                .iter()
+                .map(|&target| protocol::set_rgb_color(color, target)),
+        )?;
+        self.update_state(lights, LedState::Color(color));
+
+        Ok(())
     }
 
     /// Fades the specified lights from their current color to the specified one over the duration
@@ -113,119 +119,285 @@ impl Luxafor {
     /// `fade_time` is a `u8` on an arbitrary scale from 0 (instant) to 255 (very slow). The precise
     /// length of time each value corresponds to is determined by the hardware, and may differ
     /// depending on the starting and ending colors of the fade.
-    pub fn fade_to_color(&self, color: RgbColor, lights: BitFlags<Lights>, fade_time: u8) {
+    pub fn fade_to_color(
+        &self,
+        color: RgbColor,
+        lights: BitFlags<Lights>,
+        fade_time: u8,
+    ) -> Result<(), LuxaforError> {
         info!(
             "sending 'fade to color' {:?}, {:?}, fade_time {}",
             color, lights, fade_time
         );
+        self.require(Capability::FadeToColor, "Fading to a color")?;
 
         let targets = lights_to_targets(lights);
         debug!("mapped {:?} to targets {:?}", lights, targets);
+        self.require_targets(&targets)?;
 
         debug!(
             "writing command{} to {:?}",
             if targets.len() > 1 { "s" } else { "" },
             &self
         );
-        targets
-            .iter()
-            .map(|&target| protocol::fade_to_color(color, target, fade_time))
-            .for_each(|cmd| match &self.hid_device.write(&cmd) {
-                Ok(size) => {
-                    trace!("wrote {} bytes: {:?}", size, cmd);
-                    let mut buf = Vec::<u8>::new();
-                    match &self.hid_device.read_timeout(&mut buf, -1) {
-                        Ok(size) => {
-                            trace!("read {} bytes: {:?}", size, buf);
-                        }
-                        Err(e) => warn!("Error reading response: {}", e),
-                    }
-                }
-                Err(e) => warn!("Error reading response: {}", e),
-            });
+        self.send_all(
+            targets
+                .iter()
+                .map(|&target| protocol::fade_to_color(color, target, fade_time)),
+        )?;
+        self.update_state(lights, LedState::Color(color));
+
+        Ok(())
     }
 
     /// Triggers a strobe effect, flashing the specified lights on and off with the specified color.
     /// `speed` specifies how rapidly the lights will flash, and is a `u8` on an arbitrary scale
     /// from 0 (very fast) to 255 (very slow).  `repeat` is a u8 specifying the number of times the
     /// lights should flash.
-    pub fn strobe(&self, color: RgbColor, lights: BitFlags<Lights>, speed: u8, repeat: u8) {
+    pub fn strobe(
+        &self,
+        color: RgbColor,
+        lights: BitFlags<Lights>,
+        speed: u8,
+        repeat: u8,
+    ) -> Result<(), LuxaforError> {
         info!(
             "sending 'strobe' {:?}, {:?}, speed {}, repeat {}",
             color, lights, speed, repeat
         );
+        self.require(Capability::Strobe, "Strobing")?;
 
         let targets = lights_to_targets(lights);
         debug!("mapped {:?} to targets {:?}", lights, targets);
+        self.require_targets(&targets)?;
 
         debug!(
             "writing command{} to {:?}",
             if targets.len() > 1 { "s" } else { "" },
             &self
         );
-        targets
-            .iter()
-            .map(|&target| protocol::strobe(color, target, speed, repeat))
-            .for_each(|cmd| match &self.hid_device.write(&cmd) {
-                Ok(size) => {
-                    trace!("wrote {} bytes: {:?}", size, cmd);
-                    let mut buf = Vec::<u8>::new();
-                    match &self.hid_device.read_timeout(&mut buf, -1) {
-                        Ok(size) => {
-                            trace!("read {} bytes {:?}", size, buf);
-                        }
-                        Err(e) => warn!("Error reading response: {}", e),
-                    }
-                }
-                Err(e) => warn!("Error reading response: {}", e),
-            })
+        self.send_all(
+            targets
+                .iter()
+                .map(|&target| protocol::strobe(color, target, speed, repeat)),
+        )?;
+        self.update_state(lights, LedState::Indeterminate);
+
+        Ok(())
     }
 
     /// Triggers one of the four predefined wave effects.
-    pub fn wave(&self, color: RgbColor, wave_type: WaveType, speed: u8, repeat: u8) {
+    pub fn wave(
+        &self,
+        color: RgbColor,
+        wave_type: WaveType,
+        speed: u8,
+        repeat: u8,
+    ) -> Result<(), LuxaforError> {
         info!(
             "sending 'wave' {:?}, wave type {:?}, speed {}, repeat {}",
             color, wave_type, speed, repeat
         );
+        self.require(Capability::Wave, "The wave effect")?;
 
         debug!("writing command to {:?}", &self);
-        let cmd = protocol::wave(color, wave_type, speed, repeat);
-        match &self.hid_device.write(&cmd) {
-            Ok(size) => {
-                trace!("wrote {} bytes: {:?}", size, cmd);
-                let mut buf = Vec::<u8>::new();
-                match &self.hid_device.read_timeout(&mut buf, -1) {
-                    Ok(size) => {
-                        trace!("read {} bytes: {:?}", size, buf);
-                    }
-                    Err(e) => warn!("Error reading response: {}", e),
-                }
-            }
-            Err(e) => warn!("Error reading response: {}", e),
-        }
+        self.send(&protocol::wave(color, wave_type, speed, repeat))?;
+        self.update_state(Lights::all(), LedState::Indeterminate);
+
+        Ok(())
     }
 
     /// Triggers one of the handful of built-in patterns.
-    pub fn pattern(&self, pattern_type: PatternType, repeat: u8) {
+    pub fn pattern(&self, pattern_type: PatternType, repeat: u8) -> Result<(), LuxaforError> {
         info!(
             "sending 'pattern' pattern type {:?}, repeat {}",
             pattern_type, repeat
         );
+        self.require(Capability::Pattern, "The pattern effect")?;
+
+        debug!("writing command to {:?}", &self);
+        self.send(&protocol::pattern(pattern_type, repeat))?;
+        self.update_state(Lights::all(), LedState::Indeterminate);
+
+        Ok(())
+    }
+
+    /// Sends a single, already-built command frame, such as one decoded from a recording.  Unlike
+    /// the methods above, this never splits the command into several frames.
+    pub fn execute(&self, command: Command) -> Result<(), LuxaforError> {
+        info!("sending {}", command);
+
+        let (capability, lights, led_state) = match command {
+            Command::SimpleColor(color) => (
+                Some(Capability::SimpleColor),
+                Lights::all(),
+                LedState::Color(color.into()),
+            ),
+            Command::RgbColor { color, target } => (
+                Some(Capability::RgbColor),
+                target.lights(),
+                LedState::Color(color),
+            ),
+            Command::FadeToColor { color, target, .. } => (
+                Some(Capability::FadeToColor),
+                target.lights(),
+                LedState::Color(color),
+            ),
+            Command::Strobe { target, .. } => (
+                Some(Capability::Strobe),
+                target.lights(),
+                LedState::Indeterminate,
+            ),
+            Command::Wave { .. } => (
+                Some(Capability::Wave),
+                Lights::all(),
+                LedState::Indeterminate,
+            ),
+            Command::Pattern { .. } => (
+                Some(Capability::Pattern),
+                Lights::all(),
+                LedState::Indeterminate,
+            ),
+            Command::GetStatus => (None, BitFlags::empty(), LedState::Unknown),
+        };
+
+        if let Some(capability) = capability {
+            self.require(capability, &command.to_string())?;
+        }
+        match command {
+            Command::RgbColor { target, .. }
+            | Command::FadeToColor { target, .. }
+            | Command::Strobe { target, .. } => self.require_targets(&[target])?,
+            _ => (),
+        }
+
+        debug!("writing command to {:?}", &self);
+        self.send(&command.encode())?;
+        self.update_state(lights, led_state);
+
+        Ok(())
+    }
+
+    /// Shows `frame`, sending only what's needed to change the LEDs from what they're showing now:
+    /// nothing if they already match, one `SIMPLE_COLOR` frame if all six are the same simple
+    /// color, whole-device or whole-side frames where LEDs share a color, and single-LED frames for
+    /// the rest.  LEDs whose state is unknown or indeterminate are always written.
+    ///
+    /// The frames are written back to back without waiting on the device, so this is cheap enough
+    /// to call 30 or more times a second for software animations.
+    pub fn show(&self, frame: &Frame) -> Result<(), LuxaforError> {
+        trace!("showing {:?}", frame);
+        let writes = frame::plan(&self.current_state(), frame, self.model).ok_or_else(|| {
+            debug!("{} can't address the LEDs in {:?}", self.model, frame);
+            LuxaforError::Unsupported {
+                model: self.model.name,
+                command: "Showing a frame".to_string(),
+            }
+        })?;
+        if writes.is_empty() {
+            return Ok(());
+        }
+
+        debug!("writing {:?} to {:?}", writes, &self);
+        self.send_all(writes.iter().map(|&write| match write {
+            Write::Simple(color) => protocol::set_simple_color(color).to_vec(),
+            Write::Rgb(target, color) => protocol::set_rgb_color(color, target).to_vec(),
+        }))?;
+        writes
+            .iter()
+            .for_each(|write| self.update_state(write.lights(), LedState::Color(write.color())));
+
+        Ok(())
+    }
+
+    /// Asks the device what it's doing.  Returns `DeviceResponse::Unknown` if the device doesn't
+    /// reply within half a second, which is what most firmware does.
+    pub fn status(&self) -> Result<DeviceResponse, LuxaforError> {
+        info!("sending 'get status'");
 
         debug!("writing command to {:?}", &self);
-        let cmd = protocol::pattern(pattern_type, repeat);
-        match &self.hid_device.write(&cmd) {
-            Ok(size) => {
-                trace!("wrote {} bytes {:?}", size, cmd);
-                let mut buf = Vec::<u8>::new();
-                match &self.hid_device.read_timeout(&mut buf, -1) {
-                    Ok(size) => {
-                        trace!("read {} bytes {:?}", size, buf);
-                    }
-                    Err(e) => warn!("Error reading response: {}", e),
-                }
+        self.write_frame(&protocol::get_status())?;
+
+        let response = self.read_response(STATUS_TIMEOUT_MS)?;
+        debug!("status is {:?}", response);
+
+        Ok(response)
+    }
+
+    fn update_state(&self, lights: BitFlags<Lights>, led_state: LedState) {
+        let mut state = self.state.lock().unwrap();
+        lights
+            .iter()
+            .for_each(|light| state[light.index()] = led_state);
+        trace!("LED state is now {:?}", state);
+    }
+
+    /// Fails with `LuxaforError::Unsupported` if this device's model lacks `capability`, rather
+    /// than sending a command the hardware will silently ignore.
+    fn require(&self, capability: Capability, command: &str) -> Result<(), LuxaforError> {
+        if self.model.supports(capability) {
+            Ok(())
+        } else {
+            debug!("{} doesn't support {:?}", self.model, capability);
+            Err(LuxaforError::Unsupported {
+                model: self.model.name,
+                command: command.to_string(),
+            })
+        }
+    }
+
+    fn require_targets(&self, targets: &[Target]) -> Result<(), LuxaforError> {
+        match targets.iter().find(|&&t| !self.model.supports_target(t)) {
+            None => Ok(()),
+            Some(target) => {
+                debug!("{} doesn't support target {:?}", self.model, target);
+                Err(LuxaforError::Unsupported {
+                    model: self.model.name,
+                    command: format!("Addressing {:?}", target),
+                })
             }
-            Err(e) => warn!("Error reading response: {}", e),
         }
     }
+
+    /// Writes a single frame to the device and collects the response, if one is waiting.
+    fn send(&self, cmd: &[u8]) -> Result<(), LuxaforError> {
+        self.write_frame(cmd)?;
+        self.read_response(0)?;
+
+        Ok(())
+    }
+
+    /// Writes several frames back to back and then collects the response once, so that a command
+    /// split across targets starts on all of them as close together as the device allows.
+    fn send_all<C: AsRef<[u8]>>(
+        &self,
+        cmds: impl IntoIterator<Item = C>,
+    ) -> Result<(), LuxaforError> {
+        cmds.into_iter()
+            .try_for_each(|cmd| self.write_frame(cmd.as_ref()))?;
+        self.read_response(0)?;
+
+        Ok(())
+    }
+
+    fn write_frame(&self, cmd: &[u8]) -> Result<(), LuxaforError> {
+        let size = self.transport.write(cmd)?;
+        trace!("wrote {} bytes: {:?}", size, cmd);
+        if size < cmd.len() {
+            return Err(LuxaforError::ShortWrite {
+                written: size,
+                expected: cmd.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn read_response(&self, timeout: i32) -> Result<DeviceResponse, LuxaforError> {
+        let mut buf = [0u8; protocol::REPORT_SIZE];
+        let size = self.transport.read_timeout(&mut buf, timeout)?;
+        trace!("read {} bytes: {:?}", size, &buf[..size]);
+
+        Ok(protocol::parse_response(&buf[..size]))
+    }
 }
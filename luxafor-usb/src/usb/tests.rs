@@ -0,0 +1,307 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::lights_to_targets;
+use crate::device::{
+    DeviceResponse, LedState, Lights, Luxafor, PatternType, RgbColor, SimpleColor, Target, WaveType,
+};
+use crate::transport::{mock_luxafor, MockTransport, Transport};
+use crate::{model, LuxaforError};
+use enumflags2::BitFlags;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn set_simple_color() {
+    let (luxafor, mock) = mock_luxafor();
+    luxafor.set_simple_color(SimpleColor::Green).unwrap();
+
+    assert_eq!(mock.frames(), vec![vec![0, 0, b'G']]);
+}
+
+#[test]
+fn set_rgb_color_all() {
+    let (luxafor, mock) = mock_luxafor();
+    luxafor
+        .set_rgb_color(RgbColor(0x12, 0x34, 0x56), Lights::all())
+        .unwrap();
+
+    assert_eq!(mock.frames(), vec![vec![0, 1, 0xFF, 0x12, 0x34, 0x56]]);
+}
+
+#[test]
+fn set_rgb_color_sides() {
+    let (luxafor, mock) = mock_luxafor();
+    luxafor
+        .set_rgb_color(RgbColor::red(), Lights::flag())
+        .unwrap();
+    luxafor
+        .set_rgb_color(RgbColor::blue(), Lights::back())
+        .unwrap();
+
+    assert_eq!(
+        mock.frames(),
+        vec![vec![0, 1, b'A', 255, 0, 0], vec![0, 1, b'B', 0, 0, 255]]
+    );
+}
+
+#[test]
+fn set_rgb_color_individual_leds() {
+    let (luxafor, mock) = mock_luxafor();
+    luxafor
+        .set_rgb_color(RgbColor::white(), Lights::FlagTop | Lights::BackBottom)
+        .unwrap();
+
+    assert_eq!(
+        mock.frames(),
+        vec![vec![0, 1, 3, 255, 255, 255], vec![0, 1, 4, 255, 255, 255]]
+    );
+}
+
+#[test]
+fn covers_mixed_selections_minimally() {
+    assert_eq!(
+        lights_to_targets(Lights::flag() | Lights::BackTop),
+        vec![Target::Tab, Target::BackTopLed]
+    );
+    assert_eq!(
+        lights_to_targets(Lights::back() | Lights::FlagMiddle),
+        vec![Target::TabMiddleLed, Target::Back]
+    );
+    assert_eq!(lights_to_targets(Lights::all()), vec![Target::All]);
+    assert_eq!(lights_to_targets(BitFlags::empty()), vec![]);
+}
+
+#[test]
+fn fade_to_color_mixed_selection() {
+    let (luxafor, mock) = mock_luxafor();
+    luxafor
+        .fade_to_color(RgbColor::red(), Lights::flag() | Lights::BackTop, 40)
+        .unwrap();
+
+    assert_eq!(
+        mock.frames(),
+        vec![
+            vec![0, 2, b'A', 255, 0, 0, 40],
+            vec![0, 2, 6, 255, 0, 0, 40]
+        ]
+    );
+}
+
+#[test]
+fn fade_to_color() {
+    let (luxafor, mock) = mock_luxafor();
+    luxafor
+        .fade_to_color(RgbColor::cyan(), Lights::all(), 40)
+        .unwrap();
+
+    assert_eq!(mock.frames(), vec![vec![0, 2, 0xFF, 0, 255, 255, 40]]);
+}
+
+#[test]
+fn strobe() {
+    let (luxafor, mock) = mock_luxafor();
+    luxafor
+        .strobe(RgbColor::magenta(), Lights::back(), 31, 5)
+        .unwrap();
+
+    assert_eq!(mock.frames(), vec![vec![0, 3, b'B', 255, 0, 255, 31, 0, 5]]);
+}
+
+#[test]
+fn strobe_writes_frames_back_to_back() {
+    #[derive(Clone, Default)]
+    struct LoggingTransport(Arc<Mutex<Vec<&'static str>>>);
+    impl Transport for LoggingTransport {
+        fn write(&self, data: &[u8]) -> Result<usize, LuxaforError> {
+            self.0.lock().unwrap().push("write");
+            Ok(data.len())
+        }
+        fn read_timeout(&self, _buf: &mut [u8], _timeout: i32) -> Result<usize, LuxaforError> {
+            self.0.lock().unwrap().push("read");
+            Ok(0)
+        }
+        fn manufacturer_string(&self) -> Result<Option<String>, LuxaforError> {
+            Ok(None)
+        }
+        fn product_string(&self) -> Result<Option<String>, LuxaforError> {
+            Ok(None)
+        }
+        fn serial_number_string(&self) -> Result<Option<String>, LuxaforError> {
+            Ok(None)
+        }
+    }
+
+    let transport = LoggingTransport::default();
+    let luxafor = Luxafor::with_transport(Box::new(transport.clone()));
+    luxafor
+        .strobe(
+            RgbColor::red(),
+            Lights::FlagBottom | Lights::BackMiddle | Lights::BackTop,
+            10,
+            3,
+        )
+        .unwrap();
+
+    assert_eq!(
+        *transport.0.lock().unwrap(),
+        vec!["write", "write", "write", "read"]
+    );
+}
+
+#[test]
+fn wave() {
+    let (luxafor, mock) = mock_luxafor();
+    luxafor
+        .wave(RgbColor::yellow(), WaveType::OverlappingShort, 31, 3)
+        .unwrap();
+
+    assert_eq!(mock.frames(), vec![vec![0, 4, 3, 255, 255, 0, 0, 3, 31]]);
+}
+
+#[test]
+fn short_write() {
+    struct ShortTransport;
+    impl Transport for ShortTransport {
+        fn write(&self, data: &[u8]) -> Result<usize, LuxaforError> {
+            Ok(data.len() - 1)
+        }
+        fn read_timeout(&self, _buf: &mut [u8], _timeout: i32) -> Result<usize, LuxaforError> {
+            Ok(0)
+        }
+        fn manufacturer_string(&self) -> Result<Option<String>, LuxaforError> {
+            Ok(None)
+        }
+        fn product_string(&self) -> Result<Option<String>, LuxaforError> {
+            Ok(None)
+        }
+        fn serial_number_string(&self) -> Result<Option<String>, LuxaforError> {
+            Ok(None)
+        }
+    }
+
+    let luxafor = Luxafor::with_transport(Box::new(ShortTransport));
+    match luxafor.set_simple_color(SimpleColor::Off) {
+        Err(LuxaforError::ShortWrite { written, expected }) => {
+            assert_eq!((written, expected), (2, 3))
+        }
+        r => panic!("expected a short write, got {:?}", r),
+    }
+}
+
+#[test]
+fn pattern() {
+    let (luxafor, mock) = mock_luxafor();
+    luxafor.pattern(PatternType::Police, 2).unwrap();
+
+    assert_eq!(mock.frames(), vec![vec![0, 6, 5, 2]]);
+}
+
+#[test]
+fn status_without_reply() {
+    let (luxafor, mock) = mock_luxafor();
+
+    assert_eq!(luxafor.status().unwrap(), DeviceResponse::Unknown);
+    assert_eq!(mock.frames(), vec![vec![0, 0x80, 0, 0, 0, 0, 0, 0, 0]]);
+}
+
+#[test]
+fn status_busy() {
+    let (luxafor, mock) = mock_luxafor();
+    mock.push_response(&[3, 1, 0, 0, 0, 0, 0, 0]);
+
+    assert_eq!(
+        luxafor.status().unwrap(),
+        DeviceResponse::Busy { command: 3 }
+    );
+}
+
+#[test]
+fn status_unrecognized() {
+    let (luxafor, mock) = mock_luxafor();
+    mock.push_response(&[0x42, 0, 0, 0, 0, 0, 0, 0]);
+
+    assert_eq!(
+        luxafor.status().unwrap(),
+        DeviceResponse::Unrecognized(vec![0x42, 0, 0, 0, 0, 0, 0, 0])
+    );
+}
+
+#[test]
+fn detects_model() {
+    let luxafor =
+        Luxafor::with_transport(Box::new(MockTransport::with_product_string("LUXAFOR ORB")));
+
+    assert_eq!(luxafor.model(), &model::ORB);
+}
+
+#[test]
+fn unsupported_command() {
+    let mock = MockTransport::with_product_string("LUXAFOR MUTE");
+    let luxafor = Luxafor::with_transport(Box::new(mock.clone()));
+
+    match luxafor.pattern(PatternType::Police, 2) {
+        Err(LuxaforError::Unsupported { model, .. }) => assert_eq!(model, "Luxafor Mute Button"),
+        r => panic!("expected an unsupported command, got {:?}", r),
+    }
+    assert!(mock.frames().is_empty());
+}
+
+#[test]
+fn unsupported_target() {
+    let mock = MockTransport::with_product_string("LUXAFOR ORB");
+    let luxafor = Luxafor::with_transport(Box::new(mock.clone()));
+
+    assert!(luxafor
+        .set_rgb_color(RgbColor::red(), Lights::FlagTop.into())
+        .is_err());
+    luxafor
+        .set_rgb_color(RgbColor::red(), Lights::all())
+        .unwrap();
+    assert_eq!(mock.frames(), vec![vec![0, 1, 0xFF, 255, 0, 0]]);
+}
+
+#[test]
+fn current_state() {
+    let (luxafor, _) = mock_luxafor();
+    assert_eq!(luxafor.current_state(), [LedState::Unknown; 6]);
+
+    luxafor.set_simple_color(SimpleColor::Blue).unwrap();
+    assert_eq!(
+        luxafor.current_state(),
+        [LedState::Color(RgbColor::blue()); 6]
+    );
+
+    luxafor
+        .fade_to_color(RgbColor(1, 2, 3), Lights::FlagTop | Lights::BackTop, 10)
+        .unwrap();
+    luxafor
+        .strobe(RgbColor::red(), Lights::BackBottom.into(), 10, 3)
+        .unwrap();
+    assert_eq!(
+        luxafor.current_state(),
+        [
+            LedState::Color(RgbColor::blue()),
+            LedState::Color(RgbColor::blue()),
+            LedState::Color(RgbColor(1, 2, 3)),
+            LedState::Indeterminate,
+            LedState::Color(RgbColor::blue()),
+            LedState::Color(RgbColor(1, 2, 3)),
+        ]
+    );
+
+    luxafor.pattern(PatternType::Luxafor, 1).unwrap();
+    assert_eq!(luxafor.current_state(), [LedState::Indeterminate; 6]);
+}
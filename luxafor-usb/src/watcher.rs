@@ -0,0 +1,310 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Noticing when Luxafor devices are plugged in and unplugged, and reconnecting to them.
+//!
+//! hidapi can't tell us when devices come and go, so `DeviceWatcher` polls the list of connected
+//! devices from a background thread and reports what's changed.  `Luxafor::watch` uses it to
+//! reopen a device that's been unplugged once it's plugged back in.
+
+use crate::device::{DeviceDescriptor, DeviceIdentity, LedState, Lights, Luxafor, RgbColor};
+use crate::model::DeviceModel;
+use crate::protocol;
+use crate::transport::Transport;
+use crate::usb::lights_to_targets;
+use crate::LuxaforError;
+use enumflags2::BitFlags;
+use log::{debug, info, trace, warn};
+use std::{
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    thread::JoinHandle,
+    time::Duration,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// How often to check for devices, if you don't have a better idea.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A Luxafor device being plugged in or unplugged.
+#[derive(Clone, Debug)]
+pub enum DeviceEvent {
+    Attached(DeviceDescriptor),
+    Detached(DeviceDescriptor),
+}
+
+/// Watches for Luxafor devices being plugged in and unplugged, calling back from a background
+/// thread each time one is.  Watching stops when the `DeviceWatcher` is dropped.
+pub struct DeviceWatcher {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl DeviceWatcher {
+    /// Starts watching, checking which devices are connected every `interval`.  Devices that are
+    /// already connected when watching starts aren't reported.
+    pub fn spawn<F>(interval: Duration, mut callback: F) -> Result<Self, LuxaforError>
+    where
+        F: FnMut(DeviceEvent) + Send + 'static,
+    {
+        let mut known = Luxafor::enumerate()?;
+        // Nothing is ever sent on this; dropping the sender is what tells the thread to stop.
+        let (stop, stopped) = mpsc::channel::<()>();
+
+        let thread = thread::Builder::new()
+            .name(String::from("luxafor-watcher"))
+            .spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    match Luxafor::enumerate() {
+                        Ok(devices) => {
+                            for event in changes(&known, &devices) {
+                                debug!("{:?}", event);
+                                callback(event);
+                            }
+                            known = devices;
+                        }
+                        Err(e) => warn!("Couldn't check for devices: {}", e),
+                    }
+                }
+                trace!("device watcher stopped");
+            })
+            .map_err(|e| LuxaforError::Thread(format!("device watcher: {}", e)))?;
+
+        Ok(Self {
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for DeviceWatcher {
+    fn drop(&mut self) {
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            // The callback may have dropped us, in which case we're on the thread already.
+            if thread.thread().id() != thread::current().id() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+/// Works out which devices have been plugged in or unplugged between two calls to `enumerate`.
+pub(crate) fn changes(before: &[DeviceDescriptor], after: &[DeviceDescriptor]) -> Vec<DeviceEvent> {
+    let detached = before
+        .iter()
+        .filter(|d| !after.iter().any(|a| a.path == d.path))
+        .map(|d| DeviceEvent::Detached(d.clone()));
+    let attached = after
+        .iter()
+        .filter(|a| !before.iter().any(|d| d.path == a.path))
+        .map(|a| DeviceEvent::Attached(a.clone()));
+
+    detached.chain(attached).collect()
+}
+
+/// Stands in for the transport of a device that's being watched, so that the device underneath
+/// can be swapped out as it's unplugged and plugged back in.
+pub(crate) struct HotplugTransport {
+    device: Arc<Mutex<Option<Box<dyn Transport>>>>,
+}
+
+impl HotplugTransport {
+    fn with_device<T, F>(&self, f: F) -> Result<T, LuxaforError>
+    where
+        F: FnOnce(&dyn Transport) -> Result<T, LuxaforError>,
+    {
+        match self.device.lock().unwrap().as_deref() {
+            Some(device) => f(device),
+            None => Err(LuxaforError::Disconnected),
+        }
+    }
+}
+
+impl Transport for HotplugTransport {
+    fn write(&self, data: &[u8]) -> Result<usize, LuxaforError> {
+        self.with_device(|d| d.write(data))
+    }
+
+    fn read_timeout(&self, buf: &mut [u8], timeout: i32) -> Result<usize, LuxaforError> {
+        self.with_device(|d| d.read_timeout(buf, timeout))
+    }
+
+    fn manufacturer_string(&self) -> Result<Option<String>, LuxaforError> {
+        self.with_device(|d| d.manufacturer_string())
+    }
+
+    fn product_string(&self) -> Result<Option<String>, LuxaforError> {
+        self.with_device(|d| d.product_string())
+    }
+
+    fn serial_number_string(&self) -> Result<Option<String>, LuxaforError> {
+        self.with_device(|d| d.serial_number_string())
+    }
+}
+
+/// Lives on the watcher thread, taking the device away from a `HotplugTransport` when it's
+/// unplugged and handing a freshly opened one back when it returns.
+pub(crate) struct Reconnector {
+    device: Arc<Mutex<Option<Box<dyn Transport>>>>,
+    state: Arc<Mutex<[LedState; 6]>>,
+    identity: DeviceIdentity,
+    model: &'static DeviceModel,
+}
+
+impl Reconnector {
+    /// Deals with `event`, using `open` to open the device if it's ours and has come back.
+    /// Returns whether the event was about our device.
+    pub(crate) fn handle<F>(&mut self, event: &DeviceEvent, open: F) -> bool
+    where
+        F: FnOnce(&DeviceDescriptor) -> Result<Box<dyn Transport>, LuxaforError>,
+    {
+        match event {
+            DeviceEvent::Detached(d) if d.path == self.identity.path => {
+                info!("{} at {} was unplugged", d.model, d.path);
+                self.device.lock().unwrap().take();
+                true
+            }
+            DeviceEvent::Attached(d) if self.is_ours(d) => {
+                match open(d).and_then(|device| self.restore(device.as_ref()).map(|_| device)) {
+                    Ok(device) => {
+                        info!("{} is back at {}", d.model, d.path);
+                        self.identity.path = d.path.clone();
+                        *self.device.lock().unwrap() = Some(device);
+                        true
+                    }
+                    Err(e) => {
+                        warn!("Couldn't reopen {} at {}: {}", d.model, d.path, e);
+                        false
+                    }
+                }
+            }
+            _ => false,
+        }
+    }
+
+    fn is_ours(&self, device: &DeviceDescriptor) -> bool {
+        if self.device.lock().unwrap().is_some() {
+            return false;
+        }
+
+        match &self.identity.serial_number {
+            Some(serial_number) => device.serial_number.as_ref() == Some(serial_number),
+            // Without a serial number there's no telling two devices of the same model apart, so
+            // the first one to turn up will have to do.
+            None => device.model.name == self.model.name,
+        }
+    }
+
+    /// Puts the LEDs back the way they were before the device was unplugged.  LEDs that were
+    /// running an effect, or that were never set, are left alone.
+    fn restore(&self, device: &dyn Transport) -> Result<(), LuxaforError> {
+        let state = *self.state.lock().unwrap();
+        let mut buf = [0u8; protocol::REPORT_SIZE];
+
+        for frame in restore_frames(&state, self.model) {
+            trace!("restoring with {:?}", frame);
+            device.write(&frame)?;
+            device.read_timeout(&mut buf, 0)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds the frames that set each LED with a known color back to that color, setting LEDs that
+/// share a color together.
+pub(crate) fn restore_frames(state: &[LedState; 6], model: &DeviceModel) -> Vec<[u8; 6]> {
+    let mut colors: Vec<(RgbColor, BitFlags<Lights>)> = vec![];
+    for light in Lights::all().iter() {
+        if let Some(color) = state[light.index()].color() {
+            match colors.iter_mut().find(|(c, _)| *c == color) {
+                Some((_, lights)) => *lights |= light,
+                None => colors.push((color, BitFlags::from(light))),
+            }
+        }
+    }
+
+    colors
+        .into_iter()
+        .flat_map(|(color, lights)| {
+            lights_to_targets(lights)
+                .into_iter()
+                .filter(|&target| model.supports_target(target))
+                .map(move |target| protocol::set_rgb_color(color, target))
+        })
+        .collect()
+}
+
+impl Luxafor {
+    /// Watches for the device being unplugged, and reopens it when it's plugged back in, putting
+    /// its LEDs back the way they were.  `callback` is called from a background thread each time
+    /// the device goes or comes back; `DEFAULT_POLL_INTERVAL` is a reasonable `interval`.
+    ///
+    /// While the device is unplugged, commands fail with `LuxaforError::Disconnected`.  Only
+    /// devices opened with `new`, `open_serial` or `open_path` can be watched, and only once;
+    /// watching should start before recording does.
+    pub fn watch<F>(self, interval: Duration, mut callback: F) -> Result<Self, LuxaforError>
+    where
+        F: FnMut(DeviceEvent) + Send + 'static,
+    {
+        if self.watcher.is_some() {
+            return Err(LuxaforError::NotReconnectable);
+        }
+        let identity = self
+            .identity
+            .clone()
+            .ok_or(LuxaforError::NotReconnectable)?;
+        let (luxafor, mut reconnector) = self.hotplug(identity);
+
+        let watcher = DeviceWatcher::spawn(interval, move |event| {
+            if reconnector.handle(&event, |d| Luxafor::open_path(&d.path).map(|l| l.transport)) {
+                callback(event);
+            }
+        })?;
+
+        Ok(Self {
+            watcher: Some(watcher),
+            ..luxafor
+        })
+    }
+
+    /// Moves the transport behind a `HotplugTransport`, returning the `Reconnector` that swaps
+    /// devices in and out of it.
+    pub(crate) fn hotplug(self, identity: DeviceIdentity) -> (Self, Reconnector) {
+        let device = Arc::new(Mutex::new(Some(self.transport)));
+        let reconnector = Reconnector {
+            device: device.clone(),
+            state: self.state.clone(),
+            identity: identity.clone(),
+            model: self.model,
+        };
+
+        (
+            Self {
+                transport: Box::new(HotplugTransport { device }),
+                identity: Some(identity),
+                ..self
+            },
+            reconnector,
+        )
+    }
+}
@@ -0,0 +1,160 @@
+/* This file is part of luxafor-usb, a Rust library for communicating with Luxafor Flags.
+  Copyright © 2020 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Lesser General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU Lesser General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::device::{DeviceDescriptor, DeviceIdentity, Lights, Luxafor, RgbColor, SimpleColor};
+use crate::model;
+use crate::transport::{MockTransport, Transport};
+use crate::watcher::{changes, restore_frames, DeviceEvent, DEFAULT_POLL_INTERVAL};
+use crate::LuxaforError;
+
+fn descriptor(path: &str, serial_number: Option<&str>) -> DeviceDescriptor {
+    DeviceDescriptor {
+        path: String::from(path),
+        serial_number: serial_number.map(String::from),
+        product_string: Some(String::from("LUXAFOR FLAG")),
+        release_number: 0x0100,
+        model: &model::FLAG,
+    }
+}
+
+fn identity(path: &str, serial_number: Option<&str>) -> DeviceIdentity {
+    DeviceIdentity {
+        path: String::from(path),
+        serial_number: serial_number.map(String::from),
+    }
+}
+
+#[test]
+fn changes_reports_devices_by_path() {
+    let before = vec![
+        descriptor("/dev/hidraw1", None),
+        descriptor("/dev/hidraw2", None),
+    ];
+    let after = vec![
+        descriptor("/dev/hidraw2", None),
+        descriptor("/dev/hidraw3", None),
+    ];
+
+    let events = changes(&before, &after);
+
+    assert_eq!(events.len(), 2);
+    assert!(matches!(&events[0], DeviceEvent::Detached(d) if d.path == "/dev/hidraw1"));
+    assert!(matches!(&events[1], DeviceEvent::Attached(d) if d.path == "/dev/hidraw3"));
+    assert!(changes(&after, &after).is_empty());
+}
+
+#[test]
+fn restore_frames_groups_leds_by_color() {
+    let mock = MockTransport::new();
+    let luxafor = Luxafor::with_transport(Box::new(mock));
+    luxafor
+        .set_rgb_color(RgbColor::red(), Lights::flag())
+        .unwrap();
+    luxafor
+        .set_rgb_color(RgbColor::blue(), Lights::back())
+        .unwrap();
+    luxafor
+        .strobe(RgbColor::green(), Lights::BackTop.into(), 10, 1)
+        .unwrap();
+
+    assert_eq!(
+        restore_frames(&luxafor.current_state(), &model::FLAG),
+        vec![
+            [0, 1, b'A', 255, 0, 0],
+            [0, 1, 4, 0, 0, 255],
+            [0, 1, 5, 0, 0, 255]
+        ]
+    );
+}
+
+#[test]
+fn reconnects_and_restores_state() {
+    let first = MockTransport::new();
+    let second = MockTransport::new();
+    let luxafor = Luxafor::with_transport(Box::new(first.clone()));
+    let (luxafor, mut reconnector) = luxafor.hotplug(identity("/dev/hidraw1", Some("MOCK")));
+
+    luxafor
+        .set_rgb_color(RgbColor::cyan(), Lights::all())
+        .unwrap();
+    assert!(reconnector.handle(
+        &DeviceEvent::Detached(descriptor("/dev/hidraw1", Some("MOCK"))),
+        |_| panic!("nothing should be opened when a device goes away"),
+    ));
+    assert!(matches!(
+        luxafor.set_simple_color(SimpleColor::Red),
+        Err(LuxaforError::Disconnected)
+    ));
+
+    let reopened = second.clone();
+    assert!(reconnector.handle(
+        &DeviceEvent::Attached(descriptor("/dev/hidraw4", Some("MOCK"))),
+        move |d| {
+            assert_eq!(d.path, "/dev/hidraw4");
+            Ok(Box::new(reopened) as Box<dyn Transport>)
+        },
+    ));
+    assert_eq!(second.take_frames(), vec![vec![0, 1, 0xFF, 0, 255, 255]]);
+
+    luxafor.set_simple_color(SimpleColor::Red).unwrap();
+    assert_eq!(second.frames(), vec![vec![0, 0, b'R']]);
+    assert_eq!(first.frames(), vec![vec![0, 1, 0xFF, 0, 255, 255]]);
+
+    // The device is known by its new path from now on.
+    assert!(reconnector.handle(
+        &DeviceEvent::Detached(descriptor("/dev/hidraw4", Some("MOCK"))),
+        |_| unreachable!(),
+    ));
+}
+
+#[test]
+fn ignores_other_devices() {
+    let luxafor = Luxafor::with_transport(Box::new(MockTransport::new()));
+    let (luxafor, mut reconnector) = luxafor.hotplug(identity("/dev/hidraw1", Some("MOCK")));
+
+    assert!(!reconnector.handle(
+        &DeviceEvent::Detached(descriptor("/dev/hidraw2", Some("OTHER"))),
+        |_| unreachable!(),
+    ));
+    luxafor.set_simple_color(SimpleColor::Red).unwrap();
+
+    reconnector.handle(
+        &DeviceEvent::Detached(descriptor("/dev/hidraw1", Some("MOCK"))),
+        |_| unreachable!(),
+    );
+    assert!(!reconnector.handle(
+        &DeviceEvent::Attached(descriptor("/dev/hidraw2", Some("OTHER"))),
+        |_| unreachable!(),
+    ));
+}
+
+#[test]
+fn watch_needs_a_usb_device() {
+    let luxafor = Luxafor::with_transport(Box::new(MockTransport::new()));
+
+    assert!(matches!(
+        luxafor.watch(DEFAULT_POLL_INTERVAL, |_| ()),
+        Err(LuxaforError::NotReconnectable)
+    ));
+}
+
+#[test]
+fn hotplug_keeps_the_serial_number() {
+    let luxafor = Luxafor::with_transport(Box::new(MockTransport::new()));
+    let (luxafor, _reconnector) = luxafor.hotplug(identity("/dev/hidraw1", Some("MOCK")));
+
+    assert_eq!(luxafor.serial_number(), Some("MOCK"));
+}
@@ -0,0 +1,269 @@
+/* This file is part of Luxide, a command-line tool for operating the Luxafor Flag.
+  Copyright © 2020, 2021 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::commands::util;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use log::{debug, trace, warn};
+use luxafor_usb::{
+    calibration::{Calibration, Curve},
+    device::{Lights, Luxafor, RgbColor, SimpleColor, WaveType},
+    model::Capability,
+    LuxaforError,
+};
+use std::{
+    fs, io,
+    io::Write,
+    path::PathBuf,
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
+};
+
+pub struct Calibrate {}
+
+const FADE_SAMPLES: [u8; 3] = [32, 128, 255];
+const STROBE_SAMPLES: [u8; 3] = [8, 31, 128];
+const WAVE_SAMPLES: [u8; 3] = [8, 31, 128];
+
+// Strobes and waves are repeated until they take about this long, so that how quickly Enter is
+// pressed at the end makes little difference to the time measured for each flash or pass.
+const MEASUREMENT_TIME: Duration = Duration::from_secs(5);
+
+impl Calibrate {
+    pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+        SubCommand::with_name("calibrate")
+            .about("Measures how long fades, strobes and waves take on this device")
+            .long_about("Measures how long fades, strobes and waves take on this device, so that durations such as --fade 1.5s and --speed 200ms can be given to other commands.  Each effect is run a few times at different speeds, and you press Enter as soon as it finishes.  The results are saved as a profile for the device (by serial number, or by model if it has none) in the configuration directory; devices that haven't been calibrated use built-in figures for a Luxafor Flag.")
+            .arg(
+                Arg::with_name("SHOW")
+                    .long("show")
+                    .conflicts_with("RESET")
+                    .help("Prints the device's calibration profile instead of measuring it")
+            )
+            .arg(
+                Arg::with_name("RESET")
+                    .long("reset")
+                    .help("Deletes the device's calibration profile, going back to the built-in figures")
+            )
+    }
+
+    pub fn exec(opts: &ArgMatches) -> Result<(), String> {
+        trace!("executing \"calibrate\" command");
+        let luxafor = util::open_luxafor(opts)?;
+        let path = profile_path(&luxafor).ok_or_else(|| {
+            String::from("Couldn't find a configuration directory to keep the profile in")
+        })?;
+
+        if opts.is_present("SHOW") {
+            if path.exists() {
+                println!("# {}", path.display());
+            } else {
+                println!(
+                    "# {} (not calibrated; these are the built-in figures)",
+                    path.display()
+                );
+            }
+            print!("{}", load(&luxafor));
+            return Ok(());
+        }
+        if opts.is_present("RESET") {
+            return match fs::remove_file(&path) {
+                Ok(()) => {
+                    println!("Removed {}", path.display());
+                    Ok(())
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(format!("Couldn't remove {}: {}", path.display(), e)),
+            };
+        }
+
+        if !isatty::stdin_isatty() {
+            return Err(String::from(
+                "Calibration needs you to watch the device and press Enter, so it has to be run from a terminal",
+            ));
+        }
+        println!("Each effect will run a few times at different speeds.  Press Enter to start it, then press Enter again as soon as it finishes.\n");
+
+        let previous = load(&luxafor);
+        let model = luxafor.model();
+        let fade = if model.supports(Capability::FadeToColor) {
+            println!("Fades: press Enter when the lights stop getting brighter.");
+            measure(
+                "Fade",
+                &FADE_SAMPLES,
+                |_| 1,
+                |value, _| {
+                    reset(&luxafor)?;
+                    luxafor.fade_to_color(RgbColor::white(), Lights::all(), value)
+                },
+            )?
+        } else {
+            previous.fade.clone()
+        };
+        let strobe = if model.supports(Capability::Strobe) {
+            println!("Strobes: press Enter when the lights stop flashing.");
+            let repeats = |value| repeats_for(previous.strobe.duration(value));
+            measure("Strobe", &STROBE_SAMPLES, repeats, |value, repeat| {
+                reset(&luxafor)?;
+                luxafor.strobe(RgbColor::white(), Lights::all(), value, repeat)
+            })?
+        } else {
+            previous.strobe.clone()
+        };
+        let wave = if model.supports(Capability::Wave) {
+            println!("Waves: press Enter when the lights stop moving.");
+            let repeats = |value| repeats_for(previous.wave.duration(value));
+            measure("Wave", &WAVE_SAMPLES, repeats, |value, repeat| {
+                reset(&luxafor)?;
+                luxafor.wave(RgbColor::white(), WaveType::Short, value, repeat)
+            })?
+        } else {
+            previous.wave.clone()
+        };
+        luxafor.set_simple_color(SimpleColor::Off)?;
+
+        let calibration = Calibration { fade, strobe, wave };
+        let err = |e: io::Error| format!("Couldn't save {}: {}", path.display(), e);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(err)?;
+        }
+        fs::write(
+            &path,
+            format!(
+                "# Calibration for {}\n{}",
+                device_name(&luxafor),
+                calibration
+            ),
+        )
+        .map_err(err)?;
+        println!("\nSaved to {}", path.display());
+
+        Ok(())
+    }
+}
+
+/// Runs `effect` once for each of `samples`, repeated `repeats(value)` times, and times how long
+/// it takes until Enter is pressed.
+fn measure<R, E>(name: &str, samples: &[u8], repeats: R, effect: E) -> Result<Curve, String>
+where
+    R: Fn(u8) -> u8,
+    E: Fn(u8, u8) -> Result<(), LuxaforError>,
+{
+    let mut points = vec![];
+    for (i, &value) in samples.iter().enumerate() {
+        let repeat = repeats(value);
+        prompt(&format!(
+            "  {} {} of {} (speed {}): press Enter to start",
+            name,
+            i + 1,
+            samples.len(),
+            value
+        ))?;
+        effect(value, repeat)?;
+        let start = Instant::now();
+        prompt("  ...and again when it finishes")?;
+
+        let each = start.elapsed() / u32::from(repeat);
+        debug!("{} {} x{} took {:?} each", name, value, repeat, each);
+        println!("  {}ms", each.as_millis());
+        points.push((value, each));
+    }
+
+    Curve::new(points).map_err(|e| e.to_string())
+}
+
+/// How many times to repeat an effect expected to take `each` so that it runs for about
+/// `MEASUREMENT_TIME`.
+fn repeats_for(each: Duration) -> u8 {
+    let repeats = MEASUREMENT_TIME.as_secs_f64() / each.as_secs_f64().max(0.001);
+    repeats.round().clamp(1.0, 20.0) as u8
+}
+
+/// Turns the lights off, so that every measurement starts from the same place.
+fn reset(luxafor: &Luxafor) -> Result<(), LuxaforError> {
+    luxafor.set_rgb_color(RgbColor::off(), Lights::all())?;
+    thread::sleep(Duration::from_millis(250));
+    Ok(())
+}
+
+fn prompt(message: &str) -> Result<(), String> {
+    print!("{} ", message);
+    io::stdout().flush().map_err(|e| e.to_string())?;
+
+    let mut line = String::new();
+    match io::stdin().read_line(&mut line) {
+        Ok(0) => Err(String::from("Calibration cancelled")),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// The device's serial number, or its model's name if it has none.
+fn device_name(luxafor: &Luxafor) -> String {
+    match luxafor.serial_number() {
+        Some(serial_number) => serial_number.to_string(),
+        None => luxafor.model().name.to_string(),
+    }
+}
+
+/// Where the calibration profile for `luxafor` is kept: `luxide/calibration/<name>.profile` in
+/// the user's configuration directory, named as `device_name` returns.
+fn profile_path(luxafor: &Luxafor) -> Option<PathBuf> {
+    let name: String = device_name(luxafor)
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+
+    dirs::config_dir().map(|dir| {
+        dir.join("luxide")
+            .join("calibration")
+            .join(format!("{}.profile", name))
+    })
+}
+
+/// Loads the calibration profile for `luxafor`, or the built-in one if it hasn't been calibrated
+/// (or its profile can't be read).
+pub(crate) fn load(luxafor: &Luxafor) -> Calibration {
+    let path = match profile_path(luxafor) {
+        Some(path) => path,
+        None => return Calibration::default(),
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(profile) => match Calibration::from_str(&profile) {
+            Ok(calibration) => {
+                debug!("loaded calibration from {}", path.display());
+                calibration
+            }
+            Err(e) => {
+                warn!("Ignoring calibration profile {}: {}", path.display(), e);
+                Calibration::default()
+            }
+        },
+        Err(e) => {
+            if e.kind() != io::ErrorKind::NotFound {
+                warn!(
+                    "Couldn't read calibration profile {}: {}",
+                    path.display(),
+                    e
+                );
+            }
+            debug!("using the built-in calibration");
+            Calibration::default()
+        }
+    }
+}
@@ -18,7 +18,7 @@ use crate::commands::{util, util::ColorSpec};
 use clap::{App, Arg, ArgGroup, ArgMatches, SubCommand};
 use either::Either::Right;
 use log::{error, trace};
-use luxafor_usb::device::{BitFlags, Luxafor, SimpleColor};
+use luxafor_usb::device::{BitFlags, SimpleColor};
 use std::str::FromStr;
 
 pub struct Color {}
@@ -38,8 +38,8 @@ impl Color {
                     .long("rgb")
                     .alias("rgb-color")
                     .empty_values(false)
-                    .help("The RGB color to set (R,G,B decimal, or #RRGGBB or #RGB hex).  Either this or <COLOR> is required.")
-                    .long_help("The RGB color to set, specified in either R,G,B format with R, G, and B being in the range 0-255, or an HTML-style #RRGGBB, or CSS shorthand #RGB, hex color.  #RGB will be expanded to #RRGGBB as in CSS; that is, #b0b => #bb00bb.")
+                    .help("The RGB color to set (any CSS color, such as orange, #ff8000 or rgb(255 128 0), or R,G,B decimal).  Either this or <COLOR> is required.")
+                    .long_help(util::COLOR_HELP)
             )
             .group(
                 ArgGroup::with_name("colors")
@@ -53,8 +53,8 @@ impl Color {
                     .empty_values(true)
                     .default_value_if("COLOR", None, "")
                     .default_value_if("RGB", None, "0")
-                    .help("The duration (0-255) over which to fade to the given color.  Smaller values are faster (0 is instant, and the default).")
-                    .long_help("The duration (0-255) over which to fade to the given color.  Smaller values are faster (0 is instant, and the default).  The precise duration that corresponds to <DURATION> is determined by the hardware, and the same value may produce different real-time durations vary based on the starting and ending colors.")
+                    .help("The duration over which to fade to the given color: either 0-255, where smaller values are faster, or a time such as 1.5s.  0 is instant, and the default.")
+                    .long_help("The duration over which to fade to the given color: either 0-255, where smaller values are faster, or a time such as 500ms or 1.5s.  0 is instant, and the default.  The precise duration that corresponds to each value is determined by the hardware, and the same value may produce different real-time durations based on the starting and ending colors; times are converted using the device's calibration profile (see `luxide calibrate`).")
             )
             .arg(
                 Arg::with_name("LIGHTS")
@@ -64,15 +64,14 @@ impl Color {
                     .multiple(true)
                     .min_values(1)
                     .max_values(6)
-                    .possible_values(&["all","flag","back","flag-bottom","flag-middle","flag-top","back-bottom","back-middle","back-top","1","2","3","4","5","6"])
-                    .help("The light or lights whose color you wish to set.  Specify once for each light (e.g., -l flag-top -l back-top).  Defaults to \"all\".")
-                    .long_help("The light or lights whose color you wish to set.  Specify once for each light (e.g., -l flag-top -l back-top).  Defaults to \"all\".\nThe special values 'all', 'flag', and 'tab' may be used to set all the lights, only the flag lights, or only the back lights, or an arbitrary combination of other lights may be specified.  In addition, \"f\" and \"b\" may be used as shorthand for \"flag\" and \"back\", and the individual LEDs may be referred to numerically (1-6), with 1 being the bottom flag LED and 4 being the bottom back LED and going up from there.")
+                    .help("The light or lights whose color you wish to set, such as flag, back-top, 1-3 or !back-middle.  Defaults to \"all\".")
+                    .long_help(util::LIGHTS_HELP)
             )
     }
 
     pub fn exec(opts: &ArgMatches) -> Result<(), String> {
         trace!("executing \"color\" subcommand");
-        let luxafor = Luxafor::new()?;
+        let luxafor = util::open_luxafor(opts)?;
 
         let duration_value = opts.value_of("DURATION").unwrap();
         let color_value = if opts.is_present("COLOR") {
@@ -94,38 +93,28 @@ impl Color {
             let color = util::colorspec_to_rgb(Right(&color_value))?;
             trace!("color {:?} is {:?}", color_value, color);
 
-            let lights = util::parse_lights(lights_value);
+            let lights = util::parse_lights(lights_value)?;
             trace!("lights is {:?}", lights);
 
             let fade_time_value = opts.value_of("DURATION").unwrap();
-            let fade_time = match u8::from_str(fade_time_value) {
-                Ok(t) => t,
-                Err(_) => 0,
+            let fade_time = match fade_time_value {
+                "" => 0,
+                value => util::parse_timing(value, &luxafor, |c| &c.fade)?,
             };
             trace!("fade_time is \"{}\" = {:?}", fade_time_value, fade_time);
 
-            luxafor.fade_to_color(color, lights.unwrap_or(BitFlags::all()), fade_time);
+            luxafor.fade_to_color(color, lights.unwrap_or(BitFlags::all()), fade_time)?;
         } else {
             let color = match color_value {
                 ColorSpec::NumericColor(_) => unreachable!(),
-                ColorSpec::NamedColor(name) => match name.to_lowercase().as_str() {
-                    "red" => Ok(SimpleColor::Red),
-                    "green" => Ok(SimpleColor::Green),
-                    "blue" => Ok(SimpleColor::Blue),
-                    "cyan" => Ok(SimpleColor::Cyan),
-                    "magenta" => Ok(SimpleColor::Magenta),
-                    "yellow" => Ok(SimpleColor::Yellow),
-                    "white" => Ok(SimpleColor::White),
-                    "off" => Ok(SimpleColor::Off),
-                    s => {
-                        error!("Unrecognized simple color: \"{}\"", s);
-                        Err(format!("Invalid color: \"{}\"", s))
-                    }
-                },
+                ColorSpec::NamedColor(name) => SimpleColor::from_str(name).map_err(|e| {
+                    error!("Unrecognized simple color: \"{}\"", name);
+                    e.to_string()
+                }),
             }?;
 
             trace!("color {:?} is {:?}", color_value, color);
-            luxafor.set_simple_color(color);
+            luxafor.set_simple_color(color)?;
         }
 
         Ok(())
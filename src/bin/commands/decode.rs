@@ -0,0 +1,89 @@
+/* This file is part of Luxide, a command-line tool for operating the Luxafor Flag.
+  Copyright © 2020, 2021 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use log::trace;
+use luxafor_usb::command::Command;
+
+pub struct Decode {}
+
+impl Decode {
+    pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+        SubCommand::with_name("decode")
+            .about("Decodes a raw command frame")
+            .arg(
+                Arg::with_name("BYTES")
+                    .required(true)
+                    .multiple(true)
+                    .allow_hyphen_values(true)
+                    .help("The bytes of the frame, in hex (e.g. 00 01 ff 00 00 ff, or 0001ff0000ff).")
+                    .long_help("The bytes of the frame, including the leading report ID.  Either hex bytes, separated or not (e.g. \"00 01 ff 00 00 ff\", \"0x00 0x01 ...\", or \"0001ff0000ff\"), or a bracketed list of decimal bytes as printed in luxide's trace output (e.g. \"[0, 1, 255, 0, 0, 255]\").")
+            )
+    }
+
+    pub fn exec(opts: &ArgMatches) -> Result<(), String> {
+        trace!("executing \"decode\" command");
+
+        let input = opts
+            .values_of("BYTES")
+            .expect("clap was supposed to enforce the presence of this!  Noooo...")
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let frame = parse_bytes(&input)?;
+        trace!("frame is {:?}", frame);
+
+        let command = Command::decode(&frame).map_err(|e| e.to_string())?;
+        println!("{}", command);
+
+        Ok(())
+    }
+}
+
+fn parse_bytes(input: &str) -> Result<Vec<u8>, String> {
+    let separators = |c: char| c.is_whitespace() || c == ',' || c == ':';
+
+    if input.trim_start().starts_with('[') {
+        input
+            .trim()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .split(separators)
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse::<u8>()
+                    .map_err(|_| format!("\"{}\" isn't a decimal byte (0-255)", s))
+            })
+            .collect()
+    } else {
+        let mut bytes = vec![];
+        for token in input.split(separators).filter(|s| !s.is_empty()) {
+            let digits = token.trim_start_matches("0x").trim_start_matches("0X");
+            if digits.is_empty() || digits.len() % 2 != 0 || !digits.is_ascii() {
+                return Err(format!(
+                    "\"{}\" isn't a whole number of hex bytes (two digits each)",
+                    token
+                ));
+            }
+            for i in (0..digits.len()).step_by(2) {
+                bytes.push(
+                    u8::from_str_radix(&digits[i..i + 2], 16)
+                        .map_err(|_| format!("\"{}\" isn't a hex byte", &digits[i..i + 2]))?,
+                );
+            }
+        }
+        Ok(bytes)
+    }
+}
@@ -0,0 +1,375 @@
+/* This file is part of Luxide, a command-line tool for operating the Luxafor Flag.
+  Copyright © 2020, 2021 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+#[cfg(test)]
+mod tests;
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use log::{debug, trace};
+#[cfg(target_os = "linux")]
+use luxafor_usb::linux::parse_hid_id;
+use luxafor_usb::{
+    device::{DeviceDescriptor, Luxafor},
+    model, BACKEND,
+};
+use std::fs;
+#[cfg(target_os = "linux")]
+use std::{
+    fs::OpenOptions,
+    io,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+};
+
+pub struct Doctor {}
+
+/// Lets whoever is logged in at the seat use the device (`uaccess`), for both the hidraw backend
+/// and the libusb one.  The `uaccess` tag has to be set before systemd's 73-seat-late.rules acts
+/// on it, hence the file name this is meant to be saved under.
+const UDEV_RULES: &str = r#"# Luxafor devices (Flag, Orb, Mute, Bluetooth and Colorblind Flag), which all share these IDs.
+# Save as /etc/udev/rules.d/70-luxafor.rules, then run:
+#   sudo udevadm control --reload-rules && sudo udevadm trigger
+SUBSYSTEM=="hidraw", ATTRS{idVendor}=="04d8", ATTRS{idProduct}=="f372", TAG+="uaccess"
+SUBSYSTEM=="usb", ATTR{idVendor}=="04d8", ATTR{idProduct}=="f372", TAG+="uaccess"
+"#;
+
+impl Doctor {
+    pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+        SubCommand::with_name("doctor")
+            .about("Finds out why a device can't be found or opened")
+            .long_about("Finds out why a device can't be found or opened.  Looks for Luxafor devices (USB ID 04d8:f372), both through the backend luxide was built with (hidapi or hidraw) and, on Linux, directly in /sys; checks who owns their device nodes and whether you can open them; looks for other processes holding them open; and checks for a udev rule granting access.  With --udev-rules, prints a udev rule that gives the logged-in user access to Luxafor devices instead, or writes it to PATH.")
+            .arg(
+                Arg::with_name("UDEV-RULES")
+                    .long("udev-rules")
+                    .value_name("PATH")
+                    .takes_value(true)
+                    .min_values(0)
+                    .max_values(1)
+                    .help("Prints udev rules granting access to Luxafor devices, or writes them to PATH (normally /etc/udev/rules.d/70-luxafor.rules)")
+            )
+    }
+
+    pub fn exec(opts: &ArgMatches) -> Result<(), String> {
+        trace!("executing \"doctor\" command");
+
+        if opts.is_present("UDEV-RULES") {
+            return match opts.value_of("UDEV-RULES") {
+                None => {
+                    print!("{}", UDEV_RULES);
+                    Ok(())
+                }
+                Some(path) => {
+                    fs::write(path, UDEV_RULES)
+                        .map_err(|e| format!("Couldn't write {}: {}", path, e))?;
+                    println!("Wrote {}.  To apply it, run:", path);
+                    println!("  sudo udevadm control --reload-rules && sudo udevadm trigger");
+                    Ok(())
+                }
+            };
+        }
+
+        let mut problems = vec![];
+        check_backend(&mut problems);
+        check_nodes(&mut problems);
+
+        println!();
+        if problems.is_empty() {
+            println!("No problems found.");
+        } else {
+            println!("Problems found:");
+            for problem in &problems {
+                println!("  - {}", problem);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Lists the devices luxafor-usb's backend can see, and with hidapi, which of its own backends
+/// it's using.
+fn check_backend(problems: &mut Vec<String>) {
+    match Luxafor::enumerate() {
+        Ok(devices) => {
+            println!(
+                "{} found {} Luxafor device{}{}",
+                BACKEND,
+                devices.len(),
+                if devices.len() == 1 { "" } else { "s" },
+                match devices.first() {
+                    Some(d) if BACKEND == "hidapi" => {
+                        format!(" (using the {} backend):", backend(&d.path))
+                    }
+                    Some(_) => String::from(":"),
+                    None => String::from("."),
+                }
+            );
+            for device in &devices {
+                println!("  {}", describe(device));
+            }
+            if devices.is_empty() {
+                problems.push(format!(
+                    "{} didn't find any Luxafor devices.  Check that one is plugged in (`lsusb -d 04d8:f372` should list it).",
+                    BACKEND
+                ));
+            }
+        }
+        Err(e) => {
+            println!("{} couldn't list devices: {}", BACKEND, e);
+            problems.push(format!("{} couldn't list devices: {}", BACKEND, e));
+        }
+    }
+}
+
+fn describe(device: &DeviceDescriptor) -> String {
+    format!(
+        "{}: {}, serial number {}",
+        device.path,
+        device.model,
+        device.serial_number.as_deref().unwrap_or("(none)")
+    )
+}
+
+/// Works out which hidapi backend is in use from the form of the paths it hands out.
+fn backend(path: &str) -> &'static str {
+    let is_usb_address = path.len() == 12
+        && path
+            .split(':')
+            .map(|part| part.len())
+            .eq([4, 4, 2].iter().copied())
+        && path.chars().all(|c| c.is_ascii_hexdigit() || c == ':');
+
+    if path.starts_with("/dev/hidraw") {
+        "Linux hidraw"
+    } else if is_usb_address {
+        "libusb"
+    } else if path.starts_with("IOService:") || path.starts_with("DevSrvsID:") {
+        "macOS IOHidManager"
+    } else if path.starts_with(r"\\?\") {
+        "Windows HID"
+    } else {
+        "unknown"
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn check_nodes(_problems: &mut Vec<String>) {
+    println!("\nPermission checks are only available on Linux.");
+}
+
+/// Finds the Luxafor hidraw nodes in /sys, and the USB device node behind each (which is what the
+/// libusb backend opens), and checks that each can be opened.
+#[cfg(target_os = "linux")]
+fn check_nodes(problems: &mut Vec<String>) {
+    let entries = match fs::read_dir("/sys/class/hidraw") {
+        Ok(entries) => entries,
+        Err(e) => {
+            println!("\nCouldn't read /sys/class/hidraw: {}", e);
+            return;
+        }
+    };
+
+    let mut found = 0;
+    for entry in entries.filter_map(Result::ok) {
+        let sys_path = entry.path();
+        let ids = fs::read_to_string(sys_path.join("device/uevent"))
+            .ok()
+            .and_then(|uevent| parse_hid_id(&uevent));
+        if !ids.is_some_and(|(vendor_id, product_id)| {
+            model::DeviceModel::is_known(vendor_id, product_id)
+        }) {
+            continue;
+        }
+        found += 1;
+
+        let hidraw = Path::new("/dev").join(entry.file_name());
+        println!("\n{}:", hidraw.display());
+        check_node(&hidraw, problems);
+
+        if let Some(usb) = usb_node(&sys_path) {
+            println!("{} (the same device, as libusb sees it):", usb.display());
+            check_node(&usb, problems);
+        }
+    }
+
+    if found == 0 {
+        println!("\nNo Luxafor hidraw devices in /sys/class/hidraw.");
+        problems.push(String::from(
+            "The kernel hasn't created a hidraw device for any Luxafor.  If `lsusb -d 04d8:f372` lists it, check that the hidraw module is loaded.",
+        ));
+    }
+    check_udev_rules();
+}
+
+/// Finds the /dev/bus/usb node of the USB device a hidraw device belongs to.
+#[cfg(target_os = "linux")]
+fn usb_node(sys_path: &Path) -> Option<PathBuf> {
+    let device = fs::canonicalize(sys_path.join("device")).ok()?;
+    let usb_device = device
+        .ancestors()
+        .find(|dir| dir.join("busnum").exists() && dir.join("devnum").exists())?;
+    let read = |name: &str| -> Option<u32> {
+        fs::read_to_string(usb_device.join(name))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    };
+
+    Some(PathBuf::from(format!(
+        "/dev/bus/usb/{:03}/{:03}",
+        read("busnum")?,
+        read("devnum")?
+    )))
+}
+
+/// Reports a device node's owner and mode, whether we can open it, and who else has it open.
+#[cfg(target_os = "linux")]
+fn check_node(path: &Path, problems: &mut Vec<String>) {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            println!("  can't be examined: {}", e);
+            problems.push(format!("{} can't be examined: {}", path.display(), e));
+            return;
+        }
+    };
+    println!(
+        "  owner {}, group {}, mode {:04o}",
+        name_of("/etc/passwd", metadata.uid()),
+        name_of("/etc/group", metadata.gid()),
+        metadata.mode() & 0o7777
+    );
+
+    match OpenOptions::new().read(true).write(true).open(path) {
+        Ok(_) => println!("  can be opened for reading and writing"),
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            println!("  can't be opened for reading and writing: permission denied");
+            problems.push(format!(
+                "You don't have permission to use {}.  Run `luxide doctor --udev-rules /etc/udev/rules.d/70-luxafor.rules` as root to install a udev rule that grants it, then unplug the device and plug it back in.",
+                path.display()
+            ));
+        }
+        Err(e) => {
+            println!("  can't be opened: {}", e);
+            problems.push(format!("{} can't be opened: {}", path.display(), e));
+        }
+    }
+
+    let holders = holders(path);
+    for (pid, command) in &holders {
+        println!("  is open in process {} ({})", pid, command);
+    }
+    if !holders.is_empty() {
+        problems.push(format!(
+            "{} is already open in {}.  Commands sent by other programs will interfere with luxide's, and with the libusb backend only one program can use it at a time.",
+            path.display(),
+            holders
+                .iter()
+                .map(|(pid, command)| format!("{} ({})", command, pid))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+}
+
+/// Finds the processes that have `path` open, by looking through their file descriptors in
+/// /proc.  Without root, only our own processes can be seen.
+#[cfg(target_os = "linux")]
+fn holders(path: &Path) -> Vec<(u32, String)> {
+    let own_pid = std::process::id();
+    let mut holders = vec![];
+
+    for entry in fs::read_dir("/proc")
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+    {
+        let pid = match entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<u32>().ok())
+        {
+            Some(pid) if pid != own_pid => pid,
+            _ => continue,
+        };
+        let fds = match fs::read_dir(entry.path().join("fd")) {
+            Ok(fds) => fds,
+            Err(_) => continue,
+        };
+        if fds
+            .filter_map(Result::ok)
+            .any(|fd| fs::read_link(fd.path()).is_ok_and(|target| target == path))
+        {
+            let command = fs::read_to_string(entry.path().join("comm")).unwrap_or_default();
+            holders.push((pid, command.trim().to_string()));
+        }
+    }
+    debug!("{} is held open by {:?}", path.display(), holders);
+
+    holders
+}
+
+/// Looks up a user or group name by ID in /etc/passwd or /etc/group, falling back to the ID.
+#[cfg(target_os = "linux")]
+fn name_of(database: &str, id: u32) -> String {
+    fs::read_to_string(database)
+        .ok()
+        .and_then(|contents| {
+            contents.lines().find_map(|line| {
+                let fields: Vec<&str> = line.split(':').collect();
+                match fields.get(2) {
+                    Some(field) if field.parse() == Ok(id) => Some(fields[0].to_string()),
+                    _ => None,
+                }
+            })
+        })
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Looks for udev rules that mention the Luxafor's product ID.
+#[cfg(target_os = "linux")]
+fn check_udev_rules() {
+    let rules = [
+        "/etc/udev/rules.d",
+        "/run/udev/rules.d",
+        "/usr/lib/udev/rules.d",
+        "/lib/udev/rules.d",
+    ]
+    .iter()
+    .flat_map(|dir| {
+        fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+    })
+    .map(|entry| entry.path())
+    .filter(|path| {
+        fs::read_to_string(path).is_ok_and(|rules| rules.to_lowercase().contains("f372"))
+    })
+    .collect::<Vec<_>>();
+
+    println!();
+    if rules.is_empty() {
+        println!(
+            "No udev rules mention Luxafor devices (04d8:f372); see `luxide doctor --udev-rules`."
+        );
+    } else {
+        for path in rules {
+            println!("udev rules for Luxafor devices: {}", path.display());
+        }
+    }
+}
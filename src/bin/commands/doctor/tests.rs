@@ -0,0 +1,34 @@
+/* This file is part of Luxide, a command-line tool for operating the Luxafor Flag.
+  Copyright © 2020, 2021 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::commands::doctor::backend;
+
+#[test]
+fn backends_are_recognized_by_their_paths() {
+    assert_eq!(backend("/dev/hidraw3"), "Linux hidraw");
+    assert_eq!(backend("0001:0004:00"), "libusb");
+    assert_eq!(
+        backend("IOService:/AppleACPIPlatformExpert/PCI0@0/XHC1@14"),
+        "macOS IOHidManager"
+    );
+    assert_eq!(
+        backend(
+            r"\\?\hid#vid_04d8&pid_f372#6&1b2a3c4d&0&0000#{4d1e55b2-f16f-11cf-88cb-001111000030}"
+        ),
+        "Windows HID"
+    );
+    assert_eq!(backend("0001:0004"), "unknown");
+}
@@ -0,0 +1,222 @@
+/* This file is part of Luxide, a command-line tool for operating the Luxafor Flag.
+  Copyright © 2020, 2021 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::commands::util;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use either::Either::Left;
+use log::{debug, trace};
+use luxafor_usb::{
+    animation::{CancelToken, MAX_FPS},
+    calibration::parse_duration,
+    device::RgbColor,
+    effect::{self, Breathe, Candle, Comet, Effect as _, Gradient, Heartbeat, Police, Rainbow},
+};
+use std::{str::FromStr, time::Duration};
+
+pub struct Effect {}
+
+impl Effect {
+    pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+        SubCommand::with_name("effect")
+            .about("Plays an effect worked out in software, until interrupted")
+            .arg(
+                Arg::with_name("NAME")
+                    .index(1)
+                    .required(true)
+                    .possible_values(&["breathe", "pulse", "rainbow", "gradient", "heartbeat", "candle", "comet", "police"])
+                    .help("The effect to play.")
+                    .long_help("The effect to play:
+breathe (or pulse): fades the lights up to a color and back down.
+rainbow: cycles through the hues, each LED a little ahead of the one below it.
+gradient: sweeps a gradient between two colors up the flag.
+heartbeat: a double beat.
+candle: flickers each LED like a candle flame.
+comet: a point of light with a fading tail, running up the flag and down the back.
+police: double flashes of one color on the flag, then another on the back.
+With -c, gradient and police take two colors: the colors at either end, and the flag's color then the back's.  Rainbow has no color, and each effect has its own default.  Lights left out with -l are turned off.")
+            )
+            .arg(
+                Arg::with_name("COLOR")
+                    .short("c")
+                    .long("color")
+                    .multiple(true)
+                    .min_values(1)
+                    .max_values(2)
+                    .help("The effect's color, or two colors for gradient and police.  Can be a named color, or any CSS color.")
+                    .long_help(util::COLOR_HELP)
+            )
+            .arg(
+                Arg::with_name("PERIOD")
+                    .short("p")
+                    .long("period")
+                    .takes_value(true)
+                    .validator(util::validate_duration)
+                    .help("How long the effect takes to go round once, such as 2s.  For candle, how often the flame changes.")
+            )
+            .arg(
+                Arg::with_name("LIGHTS")
+                    .short("l")
+                    .long("light")
+                    .required(false)
+                    .multiple(true)
+                    .min_values(1)
+                    .max_values(6)
+                    .help("The light or lights to play the effect on, such as flag, back-top, 1-3 or !back-middle.  The others are turned off.  Defaults to \"all\".")
+                    .long_help(util::LIGHTS_HELP)
+            )
+            .arg(
+                Arg::with_name("DURATION")
+                    .long("duration")
+                    .takes_value(true)
+                    .validator(util::validate_duration)
+                    .help("Stops the effect after this long, such as 30s, leaving the lights as they are.  By default it runs until interrupted.")
+            )
+            .arg(
+                Arg::with_name("FPS")
+                    .long("fps")
+                    .default_value("30")
+                    .validator(validate_fps)
+                    .help("How many frames a second to show, from 1 to 1000.")
+            )
+    }
+
+    pub fn exec(opts: &ArgMatches) -> Result<(), String> {
+        trace!("executing \"effect\" command");
+
+        let name = opts.value_of("NAME").unwrap();
+        let colors = match opts.values_of("COLOR") {
+            Some(values) => values
+                .map(|value| util::colorspec_to_rgb(Left(value)))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => vec![],
+        };
+        trace!("colors are {:?}", colors);
+        let period = opts
+            .value_of("PERIOD")
+            .map(parse_duration)
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        trace!("period is {:?}", period);
+
+        let mut effect = build(name, &colors, period)?;
+        if let Some(lights) = util::parse_lights(opts.values_of("LIGHTS"))? {
+            trace!("lights is {:?}", lights);
+            effect = Box::new(effect.only(lights));
+        }
+        if let Some(duration) = opts.value_of("DURATION") {
+            let duration = parse_duration(duration).map_err(|e| e.to_string())?;
+            trace!("duration is {:?}", duration);
+            effect = Box::new(effect.limit(duration));
+        }
+        let fps = u32::from_str(opts.value_of("FPS").unwrap())
+            .expect("clap was supposed to have validated this!  Noooo...");
+
+        let luxafor = util::open_luxafor(opts)?;
+        debug!("playing {} at {} fps", name, fps);
+        effect.play(&luxafor, fps, &CancelToken::new())?;
+
+        Ok(())
+    }
+}
+
+fn validate_fps(str: String) -> Result<(), String> {
+    match u32::from_str(&str) {
+        Ok(fps) if (1..=MAX_FPS).contains(&fps) => Ok(()),
+        _ => Err(format!(
+            "Value should be a whole number from 1 to {}.",
+            MAX_FPS
+        )),
+    }
+}
+
+/// Builds the effect called `name`, with the colors and period given on the command line in
+/// place of its defaults.
+fn build(
+    name: &str,
+    colors: &[RgbColor],
+    period: Option<Duration>,
+) -> Result<Box<dyn effect::Effect>, String> {
+    let max_colors = match name {
+        "rainbow" => 0,
+        "gradient" | "police" => 2,
+        _ => 1,
+    };
+    if colors.len() > max_colors {
+        return Err(match max_colors {
+            0 => format!("The {} effect doesn't take a color", name),
+            1 => format!("The {} effect takes only one color", name),
+            _ => format!("The {} effect takes at most {} colors", name, max_colors),
+        });
+    }
+    let first = colors.first().copied();
+    let second = colors.get(1).copied();
+
+    Ok(match name {
+        "breathe" | "pulse" => {
+            let default = Breathe::default();
+            Box::new(Breathe {
+                color: first.unwrap_or(default.color),
+                period: period.unwrap_or(default.period),
+            })
+        }
+        "rainbow" => {
+            let default = Rainbow::default();
+            Box::new(Rainbow {
+                period: period.unwrap_or(default.period),
+                ..default
+            })
+        }
+        "gradient" => {
+            let default = Gradient::default();
+            Box::new(Gradient {
+                from: first.unwrap_or(default.from),
+                to: second.unwrap_or(default.to),
+                period: period.unwrap_or(default.period),
+            })
+        }
+        "heartbeat" => {
+            let default = Heartbeat::default();
+            Box::new(Heartbeat {
+                color: first.unwrap_or(default.color),
+                period: period.unwrap_or(default.period),
+            })
+        }
+        "candle" => {
+            let default = Candle::default();
+            Box::new(Candle {
+                color: first.unwrap_or(default.color),
+                period: period.unwrap_or(default.period),
+            })
+        }
+        "comet" => {
+            let default = Comet::default();
+            Box::new(Comet {
+                color: first.unwrap_or(default.color),
+                period: period.unwrap_or(default.period),
+                ..default
+            })
+        }
+        "police" => {
+            let default = Police::default();
+            Box::new(Police {
+                first: first.unwrap_or(default.first),
+                second: second.unwrap_or(default.second),
+                period: period.unwrap_or(default.period),
+            })
+        }
+        _ => return Err(format!("Unrecognized effect {}", name)),
+    })
+}
@@ -16,9 +16,16 @@
 
 mod util;
 
+pub mod calibrate;
 pub mod color;
+pub mod decode;
+pub mod doctor;
+pub mod effect;
 pub mod morse;
 pub mod off;
 pub mod pattern;
+pub mod record;
+pub mod replay;
+pub mod status;
 pub mod strobe;
 pub mod wave;
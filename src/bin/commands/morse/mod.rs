@@ -23,7 +23,10 @@ use clap::{App, Arg, ArgMatches, SubCommand};
 use either::Either::Left;
 use itertools::{Itertools, Position};
 use log::{info, trace};
-use luxafor_usb::device::{BitFlags, Luxafor, SimpleColor};
+use luxafor_usb::{
+    device::{BitFlags, SimpleColor},
+    LuxaforError,
+};
 use morse_table::{GapType, Morsel, Morsel::*};
 use std::{io, io::Write, str::FromStr, thread, time::Duration};
 
@@ -77,6 +80,18 @@ fn words_to_morse<'a>(words: &Vec<String>) -> Vec<&'a Morsel> {
         })
 }
 
+/// While the flag is unplugged, carry on with the message (so it stays in time) rather than
+/// giving up; the flag picks up again wherever we've got to when it's plugged back in.
+fn skip_if_disconnected(result: Result<(), LuxaforError>) -> Result<(), LuxaforError> {
+    match result {
+        Err(LuxaforError::Disconnected) => {
+            trace!("device is disconnected; skipping");
+            Ok(())
+        }
+        result => result,
+    }
+}
+
 pub fn string_to_morse<'a>(words: &String) -> Vec<&'a Morsel> {
     words_to_morse(&words.split_whitespace().map(String::from).collect())
 }
@@ -104,7 +119,8 @@ impl Morse {
                 Arg::with_name("COLOR")
                     .short("c")
                     .long("color")
-                    .help("The color in which to flash the light when signaling your message, either as a named color (red, green, blue, cyan, yellow, magenta, white), or a numeric color specified in R,G,B (where R, G, B are from 0-255) decimal, #RRGGBB hex, or #RGB CSS-style shorthand hex.  #RGB will be expanded as in CSS, i.e., #b0b => #bb00bb.")
+                    .help("The color in which to flash the light when signaling your message.  Can be a named color, or any CSS color.")
+                    .long_help(util::COLOR_HELP)
                     .default_value("white")
             )
             .arg(
@@ -128,7 +144,7 @@ impl Morse {
 
     pub fn exec(opts: &ArgMatches) -> Result<(), String> {
         trace!("executing 'morse' command");
-        let luxafor = Luxafor::new()?;
+        let luxafor = util::open_luxafor_reconnecting(opts)?;
 
         let color_value = opts.value_of("COLOR").unwrap();
         let color = util::colorspec_to_rgb(Left(color_value))?;
@@ -146,7 +162,8 @@ impl Morse {
         );
         trace!("message is \"{}\"", message);
 
-        let quiet = opts.is_present("QUIET") || !isatty::stdout_isatty();
+        // The simulator redraws its line in place, so printing alongside it would only make a mess.
+        let quiet = opts.is_present("QUIET") || !isatty::stdout_isatty() || util::simulating(opts);
         trace!("quiet is {}", quiet);
 
         let morsels = string_to_morse(&message);
@@ -167,13 +184,13 @@ impl Morse {
                 );
                 io::stdout().flush().expect("failed to flush stdout");
             }
-            match morsel {
+            skip_if_disconnected(match morsel {
                 Dot | Dash => luxafor.set_rgb_color(color, BitFlags::all()),
                 Gap(_) => luxafor.set_simple_color(SimpleColor::Off),
-            }
+            })?;
             thread::sleep(symbol_duration);
         }
-        luxafor.set_simple_color(SimpleColor::Off);
+        skip_if_disconnected(luxafor.set_simple_color(SimpleColor::Off))?;
         if !quiet {
             println!();
         }
@@ -14,8 +14,9 @@
    along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use clap::{App, SubCommand};
-use luxafor_usb::device::{Luxafor, SimpleColor};
+use crate::commands::util;
+use clap::{App, ArgMatches, SubCommand};
+use luxafor_usb::device::SimpleColor;
 
 pub struct Off {}
 
@@ -24,9 +25,11 @@ impl Off {
         SubCommand::with_name("off").about("Shorthand for `luxide color off`")
     }
 
-    pub fn exec() -> Result<(), String> {
-        let luxafor = Luxafor::new()?;
+    pub fn exec(opts: &ArgMatches) -> Result<(), String> {
+        let luxafor = util::open_luxafor(opts)?;
 
-        Ok(luxafor.set_simple_color(SimpleColor::Off))
+        luxafor.set_simple_color(SimpleColor::Off)?;
+
+        Ok(())
     }
 }
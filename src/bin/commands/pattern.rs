@@ -14,10 +14,10 @@
    along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::commands::util;
+use crate::commands::{calibrate, util};
 use clap::{App, Arg, ArgMatches, SubCommand};
 use log::trace;
-use luxafor_usb::device::{Luxafor, PatternType};
+use luxafor_usb::{device::PatternType, planner::EffectSpec};
 use std::str::FromStr;
 
 pub struct Pattern {}
@@ -39,34 +39,37 @@ impl Pattern {
                 .default_value("3")
                 .validator(util::validate_string_is_u8)
             )
+            .arg(
+                Arg::with_name("FORCE-SOFTWARE")
+                    .long("force-software")
+                    .help("Plays the pattern from this computer, even if the device could play it itself.")
+                    .long_help("Plays the pattern from this computer, a frame at a time, even if the device could play it itself.  Without this, the device's own pattern is used whenever it can do just what was asked, and the pattern is only played from this computer when it can't.  Run with -vv to see which was chosen and why.")
+            )
     }
 
     pub fn exec(opts: &ArgMatches) -> Result<(), String> {
         trace!("executing \"pattern\" command");
-        let luxafor = Luxafor::new()?;
+        let luxafor = util::open_luxafor(opts)?;
 
         let pattern_value = opts
             .value_of("PATTERN")
             .expect("clap was supposed to enforce the presence of this!  Noooo...");
-        let pattern = match pattern_value.to_lowercase().as_str() {
-            "1" | "luxafor" => Ok(PatternType::Luxafor),
-            "2" | "random1" => Ok(PatternType::Random1),
-            "3" | "random2" => Ok(PatternType::Random2),
-            "4" | "random3" => Ok(PatternType::Random3),
-            "6" | "random4" => Ok(PatternType::Random4),
-            "7" | "random5" => Ok(PatternType::Random5),
-            "8" | "rainbow-wave" | "rainbow" => Ok(PatternType::RainbowWave),
-            "5" | "police" => Ok(PatternType::Police),
-            p => Err(format!("An invalid pattern type {} was specified.", p)),
-        }?;
+        let pattern = PatternType::from_str(pattern_value).map_err(|e| e.to_string())?;
         trace!("pattern is {} {:?}", pattern_value, pattern);
 
         let repeat = u8::from_str(opts.value_of("REPEAT").unwrap())
             .expect("clap was supposed to have validated this!  Noooo...");
         trace!("repeat is {}", repeat);
 
-        luxafor.pattern(pattern, repeat);
-
-        Ok(())
+        let calibration = calibrate::load(&luxafor);
+        util::play(
+            &luxafor,
+            &calibration,
+            EffectSpec::Pattern {
+                pattern_type: pattern,
+                repeat,
+            },
+            opts,
+        )
     }
 }
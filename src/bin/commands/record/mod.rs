@@ -0,0 +1,89 @@
+/* This file is part of Luxide, a command-line tool for operating the Luxafor Flag.
+  Copyright © 2020, 2021 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::commands::util;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use log::trace;
+
+#[cfg(test)]
+mod tests;
+
+pub struct Record {}
+
+impl Record {
+    pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+        SubCommand::with_name("record")
+            .about("Records what another command sends to the device, to be replayed later")
+            .usage("luxide record [FLAGS] <FILE> -- <COMMAND>...")
+            .setting(AppSettings::TrailingVarArg)
+            .arg(
+                Arg::with_name("FILE")
+                    .required(true)
+                    .help("The file to record to.  It will be overwritten unless --append is given.")
+            )
+            .arg(
+                Arg::with_name("APPEND")
+                    .short("a")
+                    .long("append")
+                    .help("Add to the end of an existing recording instead of overwriting it.")
+                    .long_help("Add to the end of an existing recording instead of overwriting it.  The time between the end of the existing recording and the start of this command is kept, so a sequence of commands can be recorded one after another.")
+            )
+            .arg(
+                Arg::with_name("COMMAND")
+                    .required(true)
+                    .multiple(true)
+                    .last(true)
+                    .help("The luxide command to run and record, e.g. \"-- color red\".")
+            )
+    }
+
+    pub fn exec(opts: &ArgMatches) -> Result<(), String> {
+        trace!("executing \"record\" command");
+
+        let file = opts
+            .value_of("FILE")
+            .expect("clap was supposed to enforce the presence of this!  Noooo...");
+
+        let mut args = vec!["luxide", "--record-to", file];
+        if opts.is_present("APPEND") {
+            args.push("--record-append");
+        }
+        if let Some(device) = opts.value_of("DEVICE") {
+            args.extend(&["--device", device]);
+        }
+        if util::simulating(opts) {
+            args.push("--simulate");
+        }
+        args.extend(
+            opts.values_of("COMMAND")
+                .expect("clap was supposed to enforce the presence of this!  Noooo..."),
+        );
+        trace!("recording {:?}", args);
+
+        let inner = crate::app()
+            .get_matches_from_safe(args)
+            .map_err(|e| e.message)?;
+        match inner.subcommand_name() {
+            Some("record") | Some("replay") => {
+                Err(String::from("Only device commands can be recorded."))
+            }
+            _ => {
+                crate::dispatch(&inner);
+                Ok(())
+            }
+        }
+    }
+}
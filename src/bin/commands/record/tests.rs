@@ -0,0 +1,41 @@
+/* This file is part of Luxide, a command-line tool for operating the Luxafor Flag.
+  Copyright © 2020, 2021 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::commands::record::Record;
+use luxafor_usb::recording::read_recording;
+use std::{env, fs, fs::File, process};
+
+#[test]
+fn records_against_the_simulator() {
+    let path = env::temp_dir().join(format!("luxide-record-test-{}.lxr", process::id()));
+    let path_value = path.to_str().unwrap();
+    let opts = crate::app().get_matches_from(vec![
+        "luxide",
+        "--simulate",
+        "record",
+        path_value,
+        "--",
+        "color",
+        "red",
+    ]);
+
+    Record::exec(opts.subcommand_matches("record").unwrap()).unwrap();
+
+    let frames = read_recording(File::open(&path).unwrap()).unwrap();
+    let _ = fs::remove_file(&path);
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].frame, vec![0, 0, b'R']);
+}
@@ -0,0 +1,88 @@
+/* This file is part of Luxide, a command-line tool for operating the Luxafor Flag.
+  Copyright © 2020, 2021 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::commands::util;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use log::{info, trace};
+use luxafor_usb::recording;
+use std::{fs::File, io::BufReader, str::FromStr};
+
+pub struct Replay {}
+
+impl Replay {
+    pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+        SubCommand::with_name("replay")
+            .about("Replays a recording made with `luxide record`")
+            .arg(
+                Arg::with_name("FILE")
+                    .required(true)
+                    .help("The recording to replay.")
+            )
+            .arg(
+                Arg::with_name("SPEED")
+                    .short("s")
+                    .long("speed")
+                    .default_value("1x")
+                    .validator(|s| parse_speed(&s).map(|_| ()))
+                    .help("How fast to replay the recording relative to the original timing, e.g. 2x or 0.5x.")
+            )
+            .arg(
+                Arg::with_name("LOOP")
+                    .short("l")
+                    .long("loop")
+                    .help("Replay the recording over and over until interrupted.")
+            )
+    }
+
+    pub fn exec(opts: &ArgMatches) -> Result<(), String> {
+        trace!("executing \"replay\" command");
+
+        let path = opts
+            .value_of("FILE")
+            .expect("clap was supposed to enforce the presence of this!  Noooo...");
+        let file = File::open(path).map_err(|e| format!("Couldn't open {}: {}", path, e))?;
+        let frames = recording::read_recording(BufReader::new(file))?;
+        info!("{} frame(s) in {}", frames.len(), path);
+        if frames.is_empty() {
+            return Err(format!("{} has no frames to replay", path));
+        }
+
+        let speed = parse_speed(opts.value_of("SPEED").unwrap())
+            .expect("clap was supposed to have validated this!  Noooo...");
+        trace!("speed is {}", speed);
+
+        let luxafor = util::open_luxafor(opts)?;
+        loop {
+            recording::replay(&luxafor, &frames, speed)?;
+            if !opts.is_present("LOOP") {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a playback speed such as "2x", "0.5x" or "1.5".
+fn parse_speed(value: &str) -> Result<f64, String> {
+    match f64::from_str(value.trim_end_matches(['x', 'X'])) {
+        Ok(speed) if speed > 0.0 && speed.is_finite() => Ok(speed),
+        _ => Err(format!(
+            "\"{}\" isn't a valid speed (expected something like 2x or 0.5x)",
+            value
+        )),
+    }
+}
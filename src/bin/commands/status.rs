@@ -0,0 +1,39 @@
+/* This file is part of Luxide, a command-line tool for operating the Luxafor Flag.
+  Copyright © 2020, 2021 Mike Yount
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU General Public License as published by
+   the Free Software Foundation, version 3.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU General Public License for more details.
+
+   You should have received a copy of the GNU General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::commands::util;
+use clap::{App, ArgMatches, SubCommand};
+use log::trace;
+
+pub struct Status {}
+
+impl Status {
+    pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+        SubCommand::with_name("status")
+            .about("Asks the device what it's doing")
+            .long_about("Asks the device what it's doing.  Most Luxafor firmware never replies, in which case the status is reported as unknown.")
+    }
+
+    pub fn exec(opts: &ArgMatches) -> Result<(), String> {
+        trace!("executing \"status\" command");
+        let luxafor = util::open_luxafor(opts)?;
+
+        let status = luxafor.status()?;
+        println!("{}", status);
+
+        Ok(())
+    }
+}
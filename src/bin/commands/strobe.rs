@@ -14,11 +14,11 @@
    along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::commands::util;
+use crate::commands::{calibrate, util};
 use clap::{App, Arg, ArgMatches, SubCommand};
 use either::Either::Left;
 use log::trace;
-use luxafor_usb::device::{BitFlags, Luxafor};
+use luxafor_usb::{device::BitFlags, planner::EffectSpec};
 use std::str::FromStr;
 
 pub struct Strobe {}
@@ -30,8 +30,8 @@ impl Strobe {
             .arg(
                 Arg::with_name("COLOR")
                     .required(true)
-                    .help("The color of the lights to be strobed.  Can be a named color or an RGB color.")
-                    .long_help("The color of the lights to be strobed, either a named color (red, green, blue, cyan, yellow, magenta, white), or an RGB color specified in either R,G,B format with R, G, and B being in the range 0-255, or an HTML-style #RRGGBB, or CSS shorthand #RGB, hex color.  #RGB will be expanded to #RRGGBB as in CSS; that is, #b0b => #bb00bb.")
+                    .help("The color of the lights to be strobed.  Can be a named color, or any CSS color.")
+                    .long_help(util::COLOR_HELP)
             )
             .arg(
                 Arg::with_name("REPEATS")
@@ -47,47 +47,60 @@ impl Strobe {
                     .short("l")
                     .long("light")
                     .required(false)
-                    .multiple(false)
+                    .multiple(true)
                     .min_values(1)
                     .max_values(6)
-                    .possible_values(&["all","flag","back","flag-bottom","flag-middle","flag-top","back-bottom","back-middle","back-top","1","2","3","4","5","6"])
-                    .help("The light or lights you wish to strobe.  Defaults to \"all\".  Arbitrary combinations are not supported.")
-                    .long_help("The light or lights you wish to strobe.  Defaults to \"all\".\nThe special values 'all', 'flag', and 'tab' may be used to set all the lights, only the flag lights, or only the back lights, or an arbitrary combination of other lights may be specified.  In addition, \"f\" and \"b\" may be used as shorthand for \"flag\" and \"back\", and the individual LEDs may be referred to numerically (1-6), with 1 being the bottom flag LED and 4 being the bottom back LED and going up from there.  Unlike other commands, arbitrary combinations of lights are not supported.")
+                    .help("The light or lights you wish to strobe, such as flag, back-top, 1-3 or !back-middle.  Defaults to \"all\".")
+                    .long_help(util::LIGHTS_HELP)
             )
             .arg(
                 Arg::with_name("SPEED")
                     .long("speed")
                     .short("s")
-                    .help("The rate at which to strobe the light(s) (0-255).  Smaller values are faster.")
-                    .long_help("The rate at which to strobe the light(s) (0-255).  Smaller values are faster.  The actual real-time duration of the flash is determined by the hardware and may differ depending on what color the lights are already set to.  Keep in mind that rapidly flashing lights can potentially trigger seizures for people with photosensitive epilepsy.")
+                    .help("The rate at which to strobe the light(s): either 0-255, where smaller values are faster, or how long each flash should take, such as 200ms.")
+                    .long_help("The rate at which to strobe the light(s): either 0-255, where smaller values are faster, or how long each flash (on and back off) should take, such as 200ms or 1.5s.  The real-time duration each value corresponds to is determined by the hardware and may differ depending on what color the lights are already set to; durations are converted using the device's calibration profile (see `luxide calibrate`).  Keep in mind that rapidly flashing lights can potentially trigger seizures for people with photosensitive epilepsy.")
                     .required(false)
                     .default_value("31")
-                    .validator(util::validate_string_is_u8)
+                    .validator(util::validate_timing)
+            )
+            .arg(
+                Arg::with_name("FORCE-SOFTWARE")
+                    .long("force-software")
+                    .help("Plays the strobe from this computer, even if the device could play it itself.")
+                    .long_help("Plays the strobe from this computer, a frame at a time, even if the device could play it itself.  Without this, the device's own strobe is used whenever it can do just what was asked (one color on all the lights, one side or one LED, at a speed it has), and the strobe is only played from this computer when it can't.  Run with -vv to see which was chosen and why.")
             )
     }
 
     pub fn exec(opts: &ArgMatches) -> Result<(), String> {
         trace!("executing \"strobe\" command");
-        let luxafor = Luxafor::new()?;
+        let luxafor = util::open_luxafor(opts)?;
 
         let color_value = opts.value_of("COLOR").unwrap();
         let color = util::colorspec_to_rgb(Left(color_value))?;
         trace!("color is {:?}", color);
 
         let lights_value = opts.values_of("LIGHTS");
-        let lights = util::parse_lights(lights_value);
+        let lights = util::parse_lights(lights_value)?;
         trace!("lights is {:?}", lights);
 
         let repeat = u8::from_str(opts.value_of("REPEATS").unwrap())
             .expect("clap was supposed to have validated this!  Noooo...");
         trace!("repeat is {}", repeat);
 
-        let speed = u8::from_str(opts.value_of("SPEED").unwrap())
-            .expect("clap was supposed to have validated this!  Noooo...");
-        trace!("speed is {}", speed);
-
-        luxafor.strobe(color, lights.unwrap_or(BitFlags::all()), speed, repeat);
+        let calibration = calibrate::load(&luxafor);
+        let rate = util::parse_rate(opts.value_of("SPEED").unwrap())?;
+        trace!("rate is {:?}", rate);
 
-        Ok(())
+        util::play(
+            &luxafor,
+            &calibration,
+            EffectSpec::Strobe {
+                color,
+                lights: lights.unwrap_or(BitFlags::all()),
+                rate,
+                repeat,
+            },
+            opts,
+        )
     }
 }
@@ -14,15 +14,40 @@
    along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
+use crate::commands::calibrate;
 use crate::commands::util::ColorSpec::{NamedColor, NumericColor};
-use clap::Values;
+use clap::{ArgMatches, Values};
 use either::{
     Either,
     Either::{Left, Right},
 };
-use log::error;
-use luxafor_usb::device::{BitFlags, Lights, RgbColor};
-use std::{convert::TryFrom, num::NonZeroU64, str::FromStr};
+use log::{debug, warn};
+use luxafor_usb::{
+    animation::{CancelToken, DEFAULT_FPS},
+    calibration::{parse_duration, Calibration, Curve},
+    device::{BitFlags, LightSelection, Lights, Luxafor, RgbColor},
+    planner::{EffectSpec, Planner, Rate},
+    recording,
+    simulator::SimulatorTransport,
+    watcher::{DeviceEvent, DEFAULT_POLL_INTERVAL},
+    LuxaforError,
+};
+use std::{
+    convert::TryFrom,
+    fs,
+    fs::{File, OpenOptions},
+    io,
+    io::{BufReader, BufWriter},
+    num::NonZeroU64,
+    str::FromStr,
+};
+
+/// The `long_help` of every argument that takes a color.
+pub(crate) const COLOR_HELP: &str = "One of the Luxafor's named colors (red, green, blue, cyan, yellow, magenta, white), or any CSS color: a CSS color name such as orange or rebeccapurple, a #RGB, #RRGGBB or #RRGGBBAA hex color, or rgb(), hsl() or hwb(), as in rgb(255 128 0) or hsl(30deg 100% 50%).  R,G,B (with R, G and B from 0-255) and 0xRRGGBB are also accepted.  An alpha value dims the color.  Note that the Luxafor's names take precedence over CSS's where they differ: green is 0,255,0, not CSS's #008000.";
+
+/// The `long_help` of every argument that selects lights.
+pub(crate) const LIGHTS_HELP: &str = "Defaults to \"all\".
+The special values 'all', 'flag', and 'back' select all the lights, only the flag lights, or only the back lights (\"f\", \"tab\" and \"b\" work too), or an arbitrary combination of other lights may be specified.  The individual LEDs are flag-bottom, flag-middle, flag-top, back-bottom, back-middle and back-top, or may be referred to numerically (1-6), with 1 being the bottom flag LED and 4 being the bottom back LED and going up from there; a range such as 1-3 selects several.  Lights may be combined by separating them with commas or giving -l more than once (e.g., -l flag-top,back-top), and a light prefixed with '!' is left out (e.g., -l flag,!flag-middle).  On its own, '!' leaves the light out of all of them: -l '!back-middle' selects every light but that one.";
 
 impl<'a> TryFrom<&'a str> for ColorSpec<'a> {
     type Error = ();
@@ -52,75 +77,169 @@ impl From<ColorSpecParseError> for String {
     }
 }
 
+/// Parses a color given on the command line: any color `RgbColor::from_str` understands, except
+/// that the Luxafor's own eight color names take precedence over CSS's, so that (for instance)
+/// "green" is the same 0,255,0 it is for `luxide color green` rather than CSS's darker #008000.
 pub(crate) fn colorspec_to_rgb(
     color_spec: Either<&str, &ColorSpec>,
 ) -> Result<RgbColor, ColorSpecParseError> {
-    match color_spec {
-        Left(str) => match str.to_lowercase().as_str() {
-            "red" => Ok(RgbColor::red()),
-            "green" => Ok(RgbColor::green()),
-            "blue" => Ok(RgbColor::blue()),
-            "cyan" => Ok(RgbColor::cyan()),
-            "magenta" => Ok(RgbColor::magenta()),
-            "yellow" => Ok(RgbColor::yellow()),
-            "white" => Ok(RgbColor::white()),
-            "off" => Ok(RgbColor::off()),
-            s => match RgbColor::try_from(s) {
-                Ok(color) => Ok(color),
-                Err(e) => Err(ColorSpecParseError(e.to_string())),
-            },
-        },
+    let value = match color_spec {
+        Left(str) => str,
         Right(color_spec) => match *color_spec {
-            ColorSpec::NamedColor(name) => match name.to_lowercase().as_str() {
-                s => {
-                    error!("Unrecognized color name \"{}\"", s);
-                    Err(ColorSpecParseError(format!("Invalid color: \"{}\"", s)))
-                }
-            },
-            ColorSpec::NumericColor(num) => match RgbColor::try_from(num) {
-                Ok(color) => Ok(color),
-                Err(e) => Err(ColorSpecParseError(e.to_string())),
-            },
+            NamedColor(str) | NumericColor(str) => str,
         },
+    };
+
+    match value.to_lowercase().as_str() {
+        "red" => Ok(RgbColor::red()),
+        "green" => Ok(RgbColor::green()),
+        "blue" => Ok(RgbColor::blue()),
+        "cyan" => Ok(RgbColor::cyan()),
+        "magenta" => Ok(RgbColor::magenta()),
+        "yellow" => Ok(RgbColor::yellow()),
+        "white" => Ok(RgbColor::white()),
+        "off" => Ok(RgbColor::off()),
+        _ => RgbColor::from_str(value).map_err(|e| ColorSpecParseError(e.to_string())),
     }
 }
 
-pub(crate) fn parse_lights(values: Option<Values>) -> Option<BitFlags<Lights>> {
-    if values.is_none() {
-        None
+/// Opens the device selected by the global `--device` option, which may be a serial number, a
+/// device path, or an index into the list of connected devices.  Without `--device`, opens the
+/// first device found.
+///
+/// If the command is being run by `luxide record`, the device also records what's sent to it.
+pub(crate) fn open_luxafor(opts: &ArgMatches) -> Result<Luxafor, String> {
+    let luxafor = open_device(opts)?;
+    maybe_record(luxafor, opts)
+}
+
+/// Like `open_luxafor`, but for long-running commands: if the device is unplugged, it's reopened
+/// when it's plugged back in, and set back to whatever it was showing.  Until then, commands sent
+/// to it fail with `LuxaforError::Disconnected`.
+pub(crate) fn open_luxafor_reconnecting(opts: &ArgMatches) -> Result<Luxafor, String> {
+    if simulating(opts) {
+        // A simulated device can't be unplugged.
+        return open_luxafor(opts);
+    }
+
+    let luxafor = open_device(opts)?.watch(DEFAULT_POLL_INTERVAL, |event| match event {
+        DeviceEvent::Detached(d) => warn!("{} was unplugged; waiting for it to come back", d.model),
+        DeviceEvent::Attached(d) => warn!("{} is back", d.model),
+    })?;
+    maybe_record(luxafor, opts)
+}
+
+/// Whether `--simulate` was given, in which case commands draw a virtual flag in the terminal
+/// rather than using a real one.
+pub(crate) fn simulating(opts: &ArgMatches) -> bool {
+    opts.is_present("SIMULATE")
+}
+
+fn maybe_record(luxafor: Luxafor, opts: &ArgMatches) -> Result<Luxafor, String> {
+    match opts.value_of("RECORD-TO") {
+        None => Ok(luxafor),
+        Some(path) => start_recording(luxafor, path, opts.is_present("RECORD-APPEND")),
+    }
+}
+
+fn start_recording(luxafor: Luxafor, path: &str, append: bool) -> Result<Luxafor, String> {
+    let err = |e: io::Error| format!("Couldn't open recording {}: {}", path, e);
+
+    let existing = if append {
+        match fs::metadata(path) {
+            Ok(metadata) if metadata.len() > 0 => Some(metadata),
+            _ => None,
+        }
     } else {
-        let lights = values
-            .unwrap()
-            .map(|v| v.to_lowercase())
-            .collect::<Vec<String>>();
-
-        if lights.contains(&"all".to_string()) {
-            Some(Lights::all())
-        } else if lights.is_empty() {
-            // Should always be false, but...
-            None
-        } else {
-            let mut flags = BitFlags::<Lights>::empty();
-
-            lights.iter().for_each(|l| match l.to_lowercase().as_str() {
-                // "all","flag","back","flag-bottom","flag-middle","flag-top","back-bottom","back-middle","back-top"
-                "all" => flags |= Lights::all(),
-                "f" | "flag" => flags |= Lights::flag(),
-                "b" | "back" => flags |= Lights::back(),
-                "1" | "flag-bottom" => flags |= Lights::FlagBottom,
-                "2" | "flag-middle" => flags |= Lights::FlagMiddle,
-                "3" | "flag-top" => flags |= Lights::FlagTop,
-                "4" | "back-bottom" => flags |= Lights::BackBottom,
-                "5" | "back-middle" => flags |= Lights::BackMiddle,
-                "6" | "back_top" => flags |= Lights::BackTop,
-                _ => (),
-            });
-
-            Some(flags)
+        None
+    };
+
+    match existing {
+        Some(metadata) => {
+            // Pick up the timeline where the last recorded command left off, keeping the
+            // real-world gap between then and now.
+            let frames = recording::read_recording(BufReader::new(File::open(path).map_err(err)?))?;
+            let last = frames.last().map(|f| f.offset).unwrap_or_default();
+            let since = metadata
+                .modified()
+                .ok()
+                .and_then(|m| m.elapsed().ok())
+                .unwrap_or_default();
+            debug!("appending to {} at {:?} + {:?}", path, last, since);
+
+            let file = OpenOptions::new().append(true).open(path).map_err(err)?;
+            Ok(luxafor.continue_recording(BufWriter::new(file), last + since))
+        }
+        None => {
+            debug!("recording to {}", path);
+            Ok(luxafor.record(BufWriter::new(File::create(path).map_err(err)?))?)
+        }
+    }
+}
+
+fn open_device(opts: &ArgMatches) -> Result<Luxafor, String> {
+    if simulating(opts) {
+        debug!("simulating a device");
+        return Ok(Luxafor::with_transport(Box::new(SimulatorTransport::new())));
+    }
+
+    match opts.value_of("DEVICE") {
+        None => Luxafor::new().map_err(open_failed),
+        Some(device) => {
+            let devices = Luxafor::enumerate().map_err(open_failed)?;
+
+            if let Some(descriptor) = devices
+                .iter()
+                .find(|d| d.path == device || d.serial_number.as_deref() == Some(device))
+            {
+                debug!("device \"{}\" is {:?}", device, descriptor);
+                Luxafor::open_path(&descriptor.path).map_err(open_failed)
+            } else if let Ok(index) = usize::from_str(device) {
+                match devices.get(index) {
+                    Some(descriptor) => {
+                        debug!("device #{} is {:?}", index, descriptor);
+                        Luxafor::open_path(&descriptor.path).map_err(open_failed)
+                    }
+                    None => Err(format!(
+                        "No device #{} ({} device{} found)",
+                        index,
+                        devices.len(),
+                        if devices.len() == 1 { "" } else { "s" }
+                    )),
+                }
+            } else if device.contains('/') {
+                Luxafor::open_path(device).map_err(open_failed)
+            } else {
+                Err(format!("No device found with serial number {}", device))
+            }
         }
     }
 }
 
+/// Describes why a device couldn't be opened, pointing at `luxide doctor` for the errors it can
+/// help with.
+fn open_failed(e: LuxaforError) -> String {
+    match e {
+        LuxaforError::DeviceNotFound
+        | LuxaforError::PermissionDenied(_)
+        | LuxaforError::Open(_) => {
+            format!("{}.  Run `luxide doctor` to find out why.", e)
+        }
+        e => e.to_string(),
+    }
+}
+
+/// Parses the lights given with `-l`.  Each may be anything `LightSelection` accepts, and giving
+/// more than one is the same as separating them with commas.
+pub(crate) fn parse_lights(values: Option<Values>) -> Result<Option<BitFlags<Lights>>, String> {
+    match values {
+        None => Ok(None),
+        Some(values) => LightSelection::from_str(&values.collect::<Vec<&str>>().join(","))
+            .map(|selection| Some(selection.0))
+            .map_err(|e| e.to_string()),
+    }
+}
+
 #[derive(Debug)]
 pub(crate) enum ColorSpec<'a> {
     NamedColor(&'a str),
@@ -134,6 +253,73 @@ pub fn validate_string_is_u8(str: String) -> Result<(), String> {
     }
 }
 
+/// Reads a `--fade` or `--speed` value, which is either a number on the device's own 0-255 scale
+/// or a duration such as `1.5s`.  Durations are converted with the curve `curve` picks out of the
+/// device's calibration profile.
+pub(crate) fn parse_timing(
+    value: &str,
+    luxafor: &Luxafor,
+    curve: fn(&Calibration) -> &Curve,
+) -> Result<u8, String> {
+    if let Ok(raw) = u8::from_str(value) {
+        return Ok(raw);
+    }
+
+    let duration = parse_duration(value).map_err(|e| e.to_string())?;
+    let calibration = calibrate::load(luxafor);
+    let raw = curve(&calibration).value_for(duration);
+    debug!(
+        "{:?} is {} (about {:?})",
+        duration,
+        raw,
+        curve(&calibration).duration(raw)
+    );
+    Ok(raw)
+}
+
+/// Reads a `--speed` value: a number on the device's own 0-255 scale, or a duration such as
+/// `1.5s`.
+pub(crate) fn parse_rate(value: &str) -> Result<Rate, String> {
+    match u8::from_str(value) {
+        Ok(speed) => Ok(Rate::Speed(speed)),
+        Err(_) => parse_duration(value)
+            .map(Rate::Period)
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// Plays `spec` with the firmware if it can do it just as asked, and in software otherwise, or
+/// always in software if `--force-software` was given.  Software effects run to the end before
+/// this returns.
+pub(crate) fn play(
+    luxafor: &Luxafor,
+    calibration: &Calibration,
+    spec: EffectSpec,
+    opts: &ArgMatches,
+) -> Result<(), String> {
+    let plan = Planner::new(luxafor.model(), calibration)
+        .force_software(opts.is_present("FORCE-SOFTWARE"))
+        .plan(&spec);
+    plan.run(luxafor, DEFAULT_FPS, &CancelToken::new())?;
+    Ok(())
+}
+
+pub fn validate_timing(str: String) -> Result<(), String> {
+    if u8::from_str(&str).is_ok() || parse_duration(&str).is_ok() {
+        Ok(())
+    } else {
+        Err(String::from(
+            "Value should be from 0-255, or a duration such as 200ms or 1.5s.",
+        ))
+    }
+}
+
+pub fn validate_duration(str: String) -> Result<(), String> {
+    parse_duration(&str)
+        .map(|_| ())
+        .map_err(|_| String::from("Value should be a duration such as 200ms or 1.5s."))
+}
+
 pub fn validate_string_is_nonzero_u64(str: String) -> Result<(), String> {
     match NonZeroU64::from_str(&str) {
         Ok(_) => Ok(()),
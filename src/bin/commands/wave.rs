@@ -14,11 +14,11 @@
    along with this program.  If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::commands::util;
+use crate::commands::{calibrate, util};
 use clap::{App, Arg, ArgMatches, SubCommand};
 use either::Either::Left;
 use log::trace;
-use luxafor_usb::device::{Luxafor, WaveType};
+use luxafor_usb::{device::WaveType, planner::EffectSpec};
 use std::str::FromStr;
 
 pub struct Wave {}
@@ -32,17 +32,17 @@ impl Wave {
                     // .long("wave")
                     // .alias("wave-type")
                     // .short("w")
-                    .help("The wave type (1-4) to display.")
+                    .help("The wave type (1-4, or short, long, overlapping-short or overlapping-long) to display.")
                     .long_help("The wave type (1-4) to display.\nTypes 1 and 3 fade from off or the previous color, respectively, to the specified color, one LED at a time.\nTypes 2 and 4 fade up to an entire side of the device at one time from off or the previous color, respectively, to the specified color.")
-                    .possible_values(&["1","2","3","4"])
+                    .possible_values(&["1","2","3","4","short","long","overlapping-short","overlapping-long"])
                     .hide_possible_values(true)
                     .required(true)
             )
             .arg(
                 Arg::with_name("COLOR")
                     .required(true)
-                    .help("The color of the wave.  Can be a named color or an RGB color.")
-                    .long_help("The color of the wave, either a named color (red, green, blue, cyan, yellow, magenta, white), or an RGB color specified in either R,G,B format with R, G, and B being in the range 0-255, or an HTML-style #RRGGBB, or CSS shorthand #RGB, hex color.  #RGB will be expanded to #RRGGBB as in CSS; that is, #b0b => #bb00bb.")
+                    .help("The color of the wave.  Can be a named color, or any CSS color.")
+                    .long_help(util::COLOR_HELP)
             )
             .arg(
                 Arg::with_name("REPEATS")
@@ -57,43 +57,50 @@ impl Wave {
                 Arg::with_name("SPEED")
                     .long("speed")
                     .short("s")
-                    .help("Speed at which to animate the wave (0-255).  Smaller values are faster.")
-                    .long_help("Speed at which to animate the wave (0-255).  Smaller values are faster.  The actual real-time durations that these correspond to are determined by the hardware.")
+                    .help("Speed at which to animate the wave: either 0-255, where smaller values are faster, or how long each pass should take, such as 1.5s.")
+                    .long_help("Speed at which to animate the wave: either 0-255, where smaller values are faster, or how long each pass of the wave should take, such as 800ms or 1.5s.  The real-time durations the values correspond to are determined by the hardware; durations are converted using the device's calibration profile (see `luxide calibrate`).")
                     .required(false)
                     .default_value("31")
-                    .validator(util::validate_string_is_u8)
+                    .validator(util::validate_timing)
+            )
+            .arg(
+                Arg::with_name("FORCE-SOFTWARE")
+                    .long("force-software")
+                    .help("Plays the wave from this computer, even if the device could play it itself.")
+                    .long_help("Plays the wave from this computer, a frame at a time, even if the device could play it itself.  Without this, the device's own wave is used whenever it can do just what was asked (at a speed it has), and the wave is only played from this computer when it can't.  Run with -vv to see which was chosen and why.")
             )
     }
 
     pub fn exec(opts: &ArgMatches) -> Result<(), String> {
         trace!("executing \"wave\" command");
-        let luxafor = Luxafor::new()?;
+        let luxafor = util::open_luxafor(opts)?;
 
         let color_value = opts.value_of("COLOR").unwrap();
         let color = util::colorspec_to_rgb(Left(color_value))?;
         trace!("color is {:?}", color);
 
-        let wave_type_num = u8::from_str(opts.value_of("WAVE-TYPE").unwrap())
-            .expect("clap was supposed to have validated this!  Noooo...");
-        let wave_type = match wave_type_num {
-            1 => WaveType::Short,
-            2 => WaveType::Long,
-            3 => WaveType::OverlappingShort,
-            4 => WaveType::OverlappingLong,
-            _ => unreachable!(),
-        };
-        trace!("wave type is {} ({:?})", wave_type_num, wave_type);
+        let wave_type_value = opts.value_of("WAVE-TYPE").unwrap();
+        let wave_type = WaveType::from_str(wave_type_value).map_err(|e| e.to_string())?;
+        trace!("wave type is {} ({:?})", wave_type_value, wave_type);
 
         let repeat = u8::from_str(opts.value_of("REPEATS").unwrap())
             .expect("clap was supposed to have validated this!  Noooo...");
         trace!("repeat is {}", repeat);
 
-        let speed = u8::from_str(opts.value_of("SPEED").unwrap())
-            .expect("clap was supposed to have validated this!  Noooo...");
-        trace!("speed is {}", speed);
-
-        luxafor.wave(color, wave_type, speed, repeat);
+        let calibration = calibrate::load(&luxafor);
+        let rate = util::parse_rate(opts.value_of("SPEED").unwrap())?;
+        trace!("rate is {:?}", rate);
 
-        Ok(())
+        util::play(
+            &luxafor,
+            &calibration,
+            EffectSpec::Wave {
+                color,
+                wave_type,
+                rate,
+                repeat,
+            },
+            opts,
+        )
     }
 }
@@ -16,16 +16,18 @@
 
 mod commands;
 
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches};
 use log::error;
 use simplelog::*;
 
 use commands::{
-    color::Color, morse::Morse, off::Off, pattern::Pattern, strobe::Strobe, wave::Wave,
+    calibrate::Calibrate, color::Color, decode::Decode, doctor::Doctor, effect::Effect,
+    morse::Morse, off::Off, pattern::Pattern, record::Record, replay::Replay, status::Status,
+    strobe::Strobe, wave::Wave,
 };
 
-fn main() {
-    let opts = App::new("Luxide")
+pub(crate) fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new("Luxide")
         .version(env!("CARGO_PKG_VERSION"))
         .author("Mike Yount <michael@yount.me>")
         .about("Command-line control for Luxafor flags.")
@@ -36,13 +38,55 @@ fn main() {
                 .multiple(true)
                 .help("Increase verbosity (-v prints info; -vv prints debug output; -vvv prints trace output)"),
         )
+        .arg(
+            Arg::with_name("DEVICE")
+                .short("d")
+                .long("device")
+                .global(true)
+                .takes_value(true)
+                .help("The device to control, by serial number, path, or index.  Defaults to the first device found.")
+                .long_help("The device to control, if more than one is connected.  May be a serial number, a device path (such as /dev/hidraw3), or an index into the list of connected devices, starting from 0.  Defaults to the first device found."),
+        )
+        .arg(
+            Arg::with_name("SIMULATE")
+                .long("simulate")
+                .global(true)
+                .conflicts_with("DEVICE")
+                .help("Shows a simulated flag in the terminal instead of using a real one.")
+                .long_help("Shows a simulated flag in the terminal instead of using a real one, so that commands can be tried out without a device plugged in.  The six LEDs are drawn as colored blocks on standard error (which needs a terminal with 24-bit color), and fades, strobes, waves and patterns are played out in software, with the built-in timings that `luxide calibrate` would otherwise measure."),
+        )
+        // These two are set by `luxide record`, which runs another subcommand with them.
+        .arg(
+            Arg::with_name("RECORD-TO")
+                .long("record-to")
+                .global(true)
+                .takes_value(true)
+                .hidden(true),
+        )
+        .arg(
+            Arg::with_name("RECORD-APPEND")
+                .long("record-append")
+                .global(true)
+                .requires("RECORD-TO")
+                .hidden(true),
+        )
         .subcommand(Color::subcommand())
         .subcommand(Wave::subcommand())
         .subcommand(Pattern::subcommand())
         .subcommand(Strobe::subcommand())
         .subcommand(Off::subcommand())
+        .subcommand(Effect::subcommand())
         .subcommand(Morse::subcommand())
-        .get_matches();
+        .subcommand(Status::subcommand())
+        .subcommand(Decode::subcommand())
+        .subcommand(Record::subcommand())
+        .subcommand(Replay::subcommand())
+        .subcommand(Calibrate::subcommand())
+        .subcommand(Doctor::subcommand())
+}
+
+fn main() {
+    let opts = app().get_matches();
 
     let _ = TermLogger::init(
         match opts.occurrences_of("verbose") {
@@ -60,6 +104,10 @@ fn main() {
         TerminalMode::Mixed,
     );
 
+    dispatch(&opts);
+}
+
+pub(crate) fn dispatch(opts: &ArgMatches) {
     match opts.subcommand() {
         ("color", Some(opts)) => match Color::exec(opts) {
             Ok(_) => (),
@@ -77,11 +125,39 @@ fn main() {
             Ok(_) => (),
             Err(e) => error!("{}", e),
         },
+        ("effect", Some(opts)) => match Effect::exec(opts) {
+            Ok(_) => (),
+            Err(e) => error!("{}", e),
+        },
         ("morse", Some(opts)) => match Morse::exec(opts) {
             Ok(_) => (),
             Err(e) => error!("{}", e),
         },
-        ("off", _) => match Off::exec() {
+        ("status", Some(opts)) => match Status::exec(opts) {
+            Ok(_) => (),
+            Err(e) => error!("{}", e),
+        },
+        ("decode", Some(opts)) => match Decode::exec(opts) {
+            Ok(_) => (),
+            Err(e) => error!("{}", e),
+        },
+        ("record", Some(opts)) => match Record::exec(opts) {
+            Ok(_) => (),
+            Err(e) => error!("{}", e),
+        },
+        ("replay", Some(opts)) => match Replay::exec(opts) {
+            Ok(_) => (),
+            Err(e) => error!("{}", e),
+        },
+        ("off", Some(opts)) => match Off::exec(opts) {
+            Ok(_) => (),
+            Err(e) => error!("{}", e),
+        },
+        ("calibrate", Some(opts)) => match Calibrate::exec(opts) {
+            Ok(_) => (),
+            Err(e) => error!("{}", e),
+        },
+        ("doctor", Some(opts)) => match Doctor::exec(opts) {
             Ok(_) => (),
             Err(e) => error!("{}", e),
         },